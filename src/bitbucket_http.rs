@@ -0,0 +1,50 @@
+use std::net::SocketAddr;
+use anyhow::{Context, Result};
+use bitbucket_mcp::common::bitbucket::{BitbucketClient, BitbucketTool};
+use bitbucket_mcp::common::http_server::{self, SSE_PATH};
+use tokio::net::TcpListener;
+use tokio_util::sync::CancellationToken;
+use tracing_subscriber::{self, filter::EnvFilter};
+
+const DEFAULT_BIND: &str = "0.0.0.0:8080";
+
+#[tokio::main]
+async fn main() -> Result<()> {
+    tracing_subscriber::fmt()
+        .with_env_filter(EnvFilter::from_default_env().add_directive(tracing::Level::INFO.into()))
+        .with_writer(std::io::stderr)
+        .with_ansi(false)
+        .init();
+
+    let bind: SocketAddr = std::env::var("BITBUCKET_MCP_BIND")
+        .unwrap_or_else(|_| DEFAULT_BIND.to_string())
+        .parse()
+        .context("BITBUCKET_MCP_BIND must be a socket address such as 0.0.0.0:8080")?;
+
+    // Sessions bring their own credentials; env credentials are only a fallback for sessions without
+    // an Authorization header. Every session shares this client's rate limiter.
+    let client = BitbucketClient::from_env_settings().inspect_err(|e| {
+        tracing::error!("failed to configure Bitbucket client: {e}");
+    })?;
+    if client.credentials.is_none() {
+        tracing::info!("no fallback credentials configured; sessions must send an Authorization header");
+    } else {
+        let tool = BitbucketTool::new(client.clone());
+        tokio::spawn(async move { tool.log_auth_check().await });
+    }
+
+    let listener = TcpListener::bind(bind).await.with_context(|| format!("failed to listen on {bind}"))?;
+    tracing::info!("Bitbucket MCP server listening on http://{bind}{SSE_PATH}");
+
+    let ct = CancellationToken::new();
+    tokio::spawn({
+        let ct = ct.clone();
+        async move {
+            let _ = tokio::signal::ctrl_c().await;
+            tracing::info!("shutting down");
+            ct.cancel();
+        }
+    });
+    http_server::serve(listener, client, ct).await?;
+    Ok(())
+}
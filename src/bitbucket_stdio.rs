@@ -1,8 +1,7 @@
 use anyhow::Result;
-use common::bitbucket::BitbucketTool;
+use bitbucket_mcp::common::bitbucket::{BitbucketClient, BitbucketTool};
 use rmcp::{ServiceExt, transport::stdio};
 use tracing_subscriber::{self, filter::EnvFilter};
-mod common;
 
 #[tokio::main]
 async fn main() -> Result<()> {
@@ -13,11 +12,9 @@ async fn main() -> Result<()> {
         };
         let location = panic_info.location().map(|l| l.to_string()).unwrap_or_default();
         eprintln!("PANIC: {} at {}", msg, location);
-        if let Ok(bt) = std::env::var("RUST_BACKTRACE") {
-            if bt == "1" {
-                let bt = std::backtrace::Backtrace::force_capture();
-                eprintln!("Backtrace:\n{:?}", bt);
-            }
+        if let Ok(bt) = std::env::var("RUST_BACKTRACE") && bt == "1" {
+            let bt = std::backtrace::Backtrace::force_capture();
+            eprintln!("Backtrace:\n{:?}", bt);
         }
     }));
     tracing_subscriber::fmt()
@@ -28,7 +25,19 @@ async fn main() -> Result<()> {
 
     tracing::info!("Starting Bitbucket MCP server");
 
-    let service = BitbucketTool.serve(stdio()).await.inspect_err(|e| {
+    // Build the client once so missing credentials fail here rather than on the first tool call
+    let client = BitbucketClient::from_env().inspect_err(|e| {
+        tracing::error!("failed to configure Bitbucket client: {e}");
+    })?;
+
+    // The self-check runs alongside the server so a slow Bitbucket does not delay the handshake
+    let tool = BitbucketTool::new(client);
+    tokio::spawn({
+        let tool = tool.clone();
+        async move { tool.log_auth_check().await }
+    });
+
+    let service = tool.serve(stdio()).await.inspect_err(|e| {
         tracing::error!("serving error: {:?}", e);
     })?;
 
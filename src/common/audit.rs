@@ -0,0 +1,105 @@
+// Audit log of the requests that change Bitbucket state. Every non-GET request sent by
+// `BitbucketClient` appends one JSON line to the configured file (`BITBUCKET_MCP_AUDIT_LOG`),
+// successful or not, with secrets in the body redacted; `read_audit_log` reads recent entries back.
+
+use std::fs::{self, OpenOptions};
+use std::future::Future;
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use super::error::{BitbucketError, Result};
+use super::redact;
+
+tokio::task_local! {
+    static CURRENT_TOOL: String;
+}
+
+/// Runs `f` with `tool` recorded as the tool behind any request it sends.
+pub async fn with_tool<F: Future>(tool: &str, f: F) -> F::Output {
+    CURRENT_TOOL.scope(tool.to_string(), f).await
+}
+
+fn current_tool() -> Option<String> {
+    CURRENT_TOOL.try_with(Clone::clone).ok()
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct AuditEntry {
+    /// RFC 3339, UTC.
+    pub timestamp: String,
+    /// MCP tool that made the call; `None` when the client was used directly.
+    pub tool: Option<String>,
+    pub method: String,
+    pub workspace: Option<String>,
+    pub repo: Option<String>,
+    /// Path below the repository or workspace, e.g. `pullrequests/5/merge`.
+    pub target: Option<String>,
+    /// HTTP status, or `None` when no response was received.
+    pub status: Option<u16>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+    pub body: Option<Value>,
+}
+
+impl AuditEntry {
+    pub fn new(method: &str, (workspace, repo, target): (Option<String>, Option<String>, Option<String>), body: Option<&Value>) -> Self {
+        Self {
+            timestamp: chrono::Utc::now().to_rfc3339_opts(chrono::SecondsFormat::Millis, true),
+            tool: current_tool(),
+            method: method.to_string(),
+            workspace,
+            repo,
+            target,
+            status: None,
+            error: None,
+            body: body.map(redact::redact_value),
+        }
+    }
+}
+
+#[derive(Debug)]
+pub struct AuditLog {
+    path: PathBuf,
+    lock: Mutex<()>,
+}
+
+impl AuditLog {
+    pub fn new(path: impl Into<PathBuf>) -> Self {
+        Self { path: path.into(), lock: Mutex::new(()) }
+    }
+
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    /// Appends one entry. A failed write is logged rather than returned: the request it describes
+    /// has already been sent.
+    pub fn record(&self, entry: &AuditEntry) {
+        let _guard = self.lock.lock().unwrap();
+        let written = serde_json::to_string(entry).map_err(std::io::Error::other).and_then(|line| {
+            let mut file = OpenOptions::new().create(true).append(true).open(&self.path)?;
+            writeln!(file, "{line}")
+        });
+        if let Err(e) = written {
+            tracing::error!("cannot write audit log {}: {e}", self.path.display());
+        }
+    }
+
+    /// The last `limit` entries, oldest first, optionally only those made by `tool`.
+    pub fn read_recent(&self, limit: usize, tool: Option<&str>) -> Result<Vec<AuditEntry>> {
+        let text = match fs::read_to_string(&self.path) {
+            Ok(text) => text,
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => String::new(),
+            Err(e) => return Err(BitbucketError::Config(format!("cannot read audit log {}: {e}", self.path.display()))),
+        };
+        let mut entries: Vec<AuditEntry> = text
+            .lines()
+            .filter_map(|line| serde_json::from_str::<AuditEntry>(line).ok())
+            .filter(|entry| tool.is_none() || entry.tool.as_deref() == tool)
+            .collect();
+        let skip = entries.len().saturating_sub(limit);
+        Ok(entries.split_off(skip))
+    }
+}
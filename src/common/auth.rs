@@ -0,0 +1,297 @@
+// Credentials attached to every Bitbucket request.
+// The stdio server reads them from the environment once; the HTTP server builds them per session
+// from the client's `Authorization` header and only falls back to the environment.
+// OAuth consumers use the client-credentials grant; the access token is cached and fetched again
+// shortly before it expires.
+
+use std::env;
+use std::fmt;
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+use base64::Engine;
+use base64::engine::general_purpose::STANDARD;
+use reqwest::{Client, RequestBuilder};
+use serde::{Deserialize, Serialize};
+use tokio::sync::Mutex;
+use super::error::{BitbucketError, Result};
+
+pub const DEFAULT_OAUTH_TOKEN_URL: &str = "https://bitbucket.org/site/oauth2/access_token";
+
+/// Cached OAuth tokens are replaced once they are this close to expiring.
+const REFRESH_MARGIN: Duration = Duration::from_secs(60);
+
+#[derive(Clone, PartialEq, Eq)]
+pub enum Credentials {
+    /// Atlassian account email (or username) with an API token or app password.
+    Basic { username: String, password: String },
+    /// Repository, project or workspace access token, or an OAuth access token.
+    Bearer { token: String },
+    /// OAuth consumer using the client-credentials grant. Clones share the cached token.
+    OAuth2(Arc<OAuth2ClientCredentials>),
+}
+
+/// Strategy selected with `BITBUCKET_AUTH_METHOD`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum AuthMethod {
+    Basic,
+    Bearer,
+    OAuth2,
+}
+
+impl std::str::FromStr for AuthMethod {
+    type Err = BitbucketError;
+
+    fn from_str(s: &str) -> Result<Self> {
+        match s.trim().to_ascii_lowercase().as_str() {
+            "basic" => Ok(Self::Basic),
+            "bearer" | "access_token" => Ok(Self::Bearer),
+            "oauth2" | "oauth" | "client_credentials" => Ok(Self::OAuth2),
+            other => Err(BitbucketError::Config(format!(
+                "BITBUCKET_AUTH_METHOD has an invalid value '{other}', expected basic, bearer or oauth2"
+            ))),
+        }
+    }
+}
+
+impl fmt::Display for AuthMethod {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            Self::Basic => "basic",
+            Self::Bearer => "bearer",
+            Self::OAuth2 => "oauth2",
+        })
+    }
+}
+
+impl AuthMethod {
+    pub const ALL: [AuthMethod; 3] = [Self::Basic, Self::Bearer, Self::OAuth2];
+
+    /// Environment variables this strategy cannot work without.
+    pub fn required_env(self) -> &'static [&'static str] {
+        match self {
+            Self::Basic => &["BITBUCKET_API_USERNAME", "BITBUCKET_API_TOKEN"],
+            Self::Bearer => &["BITBUCKET_ACCESS_TOKEN"],
+            Self::OAuth2 => &["BITBUCKET_OAUTH_CLIENT_ID", "BITBUCKET_OAUTH_CLIENT_SECRET"],
+        }
+    }
+}
+
+/// Error for a server started without credentials. Names the variables of the strategy selected
+/// with `BITBUCKET_AUTH_METHOD`, or of every strategy when none is selected.
+pub fn missing_credentials(method: Option<AuthMethod>) -> BitbucketError {
+    let vars = |method: AuthMethod| method.required_env().join(" and ");
+    let message = match method {
+        Some(method) => format!("No Bitbucket credentials configured for {method} auth. Please set {}.", vars(method)),
+        None => format!(
+            "No Bitbucket credentials configured. Please set {}, or choose a strategy with BITBUCKET_AUTH_METHOD.",
+            AuthMethod::ALL.map(|method| format!("{} ({method})", vars(method))).join(", ")
+        ),
+    };
+    BitbucketError::Config(message)
+}
+
+impl Credentials {
+    pub fn basic(username: impl Into<String>, password: impl Into<String>) -> Self {
+        Self::Basic { username: username.into(), password: password.into() }
+    }
+
+    pub fn bearer(token: impl Into<String>) -> Self {
+        Self::Bearer { token: token.into() }
+    }
+
+    pub fn oauth2(client_id: impl Into<String>, client_secret: impl Into<String>, token_url: impl Into<String>) -> Self {
+        Self::OAuth2(Arc::new(OAuth2ClientCredentials::new(client_id, client_secret, token_url)))
+    }
+
+    /// Reads credentials for the strategy named by `BITBUCKET_AUTH_METHOD`:
+    ///
+    /// - `basic`: `BITBUCKET_API_USERNAME` and `BITBUCKET_API_TOKEN`
+    /// - `bearer`: `BITBUCKET_ACCESS_TOKEN`
+    /// - `oauth2`: `BITBUCKET_OAUTH_CLIENT_ID`, `BITBUCKET_OAUTH_CLIENT_SECRET` and optionally `BITBUCKET_OAUTH_TOKEN_URL`
+    ///
+    /// Without `BITBUCKET_AUTH_METHOD` the strategy is picked from whichever of those variables are set.
+    /// Returns `None` when none are set, and an error when a strategy is only partly configured.
+    pub fn from_env() -> Result<Option<Self>> {
+        let method = match env::var("BITBUCKET_AUTH_METHOD") {
+            Ok(method) => method.parse()?,
+            Err(_) if env::var("BITBUCKET_ACCESS_TOKEN").is_ok() => AuthMethod::Bearer,
+            Err(_) if env::var("BITBUCKET_OAUTH_CLIENT_ID").is_ok() => AuthMethod::OAuth2,
+            Err(_) => AuthMethod::Basic,
+        };
+        match method {
+            AuthMethod::Basic => match (env::var("BITBUCKET_API_USERNAME").ok(), env::var("BITBUCKET_API_TOKEN").ok()) {
+                (Some(username), Some(token)) => Ok(Some(Self::basic(username, token))),
+                (None, None) => Ok(None),
+                (None, Some(_)) => Err(missing_username()),
+                (Some(_), None) => Err(missing_token()),
+            },
+            AuthMethod::Bearer => Ok(Some(Self::bearer(require_env("BITBUCKET_ACCESS_TOKEN")?))),
+            AuthMethod::OAuth2 => Ok(Some(Self::oauth2(
+                require_env("BITBUCKET_OAUTH_CLIENT_ID")?,
+                require_env("BITBUCKET_OAUTH_CLIENT_SECRET")?,
+                env::var("BITBUCKET_OAUTH_TOKEN_URL").unwrap_or_else(|_| DEFAULT_OAUTH_TOKEN_URL.to_string()),
+            ))),
+        }
+    }
+
+    pub fn method(&self) -> AuthMethod {
+        match self {
+            Self::Basic { .. } => AuthMethod::Basic,
+            Self::Bearer { .. } => AuthMethod::Bearer,
+            Self::OAuth2(_) => AuthMethod::OAuth2,
+        }
+    }
+
+    /// String that differs between identities, used to keep cached responses apart. It contains the
+    /// secret, so it must only be stored hashed.
+    pub(crate) fn cache_identity(&self) -> String {
+        match self {
+            Self::Basic { username, password } => format!("basic:{username}:{password}"),
+            Self::Bearer { token } => format!("bearer:{token}"),
+            Self::OAuth2(oauth) => format!("oauth2:{}:{}:{}", oauth.token_url, oauth.client_id, oauth.client_secret),
+        }
+    }
+
+    /// `Authorization` header value with the secret replaced, for showing rendered requests.
+    pub fn redacted_header(&self) -> &'static str {
+        match self {
+            Self::Basic { .. } => "Basic ***",
+            Self::Bearer { .. } | Self::OAuth2(_) => "Bearer ***",
+        }
+    }
+
+    /// Parses an HTTP `Authorization` header value (`Basic <base64 user:password>` or `Bearer <token>`).
+    pub fn from_authorization_header(value: &str) -> Result<Self> {
+        let (scheme, param) = value.trim().split_once(' ').ok_or_else(|| unauthorized("expected '<scheme> <credentials>'"))?;
+        let param = param.trim();
+        if param.is_empty() {
+            return Err(unauthorized("credentials are empty"));
+        }
+        if scheme.eq_ignore_ascii_case("bearer") {
+            return Ok(Self::bearer(param));
+        }
+        if scheme.eq_ignore_ascii_case("basic") {
+            let decoded = STANDARD.decode(param).map_err(|_| unauthorized("basic credentials are not valid base64"))?;
+            let decoded = String::from_utf8(decoded).map_err(|_| unauthorized("basic credentials are not valid UTF-8"))?;
+            let (username, password) = decoded.split_once(':').ok_or_else(|| unauthorized("basic credentials must be 'username:password'"))?;
+            return Ok(Self::basic(username, password));
+        }
+        Err(unauthorized(&format!("unsupported scheme '{scheme}', use Basic or Bearer")))
+    }
+
+    /// Adds the `Authorization` header, fetching an OAuth token first if the cached one is missing or expiring.
+    pub(crate) async fn apply(&self, http: &Client, req: RequestBuilder) -> Result<RequestBuilder> {
+        Ok(match self {
+            Self::Basic { username, password } => req.basic_auth(username, Some(password)),
+            Self::Bearer { token } => req.bearer_auth(token),
+            Self::OAuth2(oauth) => req.bearer_auth(oauth.access_token(http).await?),
+        })
+    }
+
+    /// Drops a cached OAuth token after Bitbucket rejected it, so the next request fetches a new one.
+    pub(crate) async fn invalidate(&self) {
+        if let Self::OAuth2(oauth) = self {
+            *oauth.token.lock().await = None;
+        }
+    }
+}
+
+pub struct OAuth2ClientCredentials {
+    pub client_id: String,
+    client_secret: String,
+    pub token_url: String,
+    token: Mutex<Option<CachedToken>>,
+}
+
+struct CachedToken {
+    access_token: String,
+    expires_at: Instant,
+}
+
+#[derive(Deserialize)]
+struct TokenResponse {
+    access_token: String,
+    #[serde(default)]
+    expires_in: Option<u64>,
+}
+
+impl OAuth2ClientCredentials {
+    pub fn new(client_id: impl Into<String>, client_secret: impl Into<String>, token_url: impl Into<String>) -> Self {
+        Self {
+            client_id: client_id.into(),
+            client_secret: client_secret.into(),
+            token_url: token_url.into(),
+            token: Mutex::new(None),
+        }
+    }
+
+    /// Returns the cached access token, or requests a new one when it expires within `REFRESH_MARGIN`.
+    /// The lock is held during the request so concurrent callers wait for one refresh.
+    async fn access_token(&self, http: &Client) -> Result<String> {
+        let mut cached = self.token.lock().await;
+        if let Some(token) = cached.as_ref()
+            && token.expires_at > Instant::now() + REFRESH_MARGIN
+        {
+            return Ok(token.access_token.clone());
+        }
+        let resp = http
+            .post(&self.token_url)
+            .basic_auth(&self.client_id, Some(&self.client_secret))
+            .form(&[("grant_type", "client_credentials")])
+            .send()
+            .await?;
+        let status = resp.status();
+        if !status.is_success() {
+            let body: serde_json::Value = resp.json().await.unwrap_or_default();
+            let reason = body["error_description"].as_str().or(body["error"].as_str()).unwrap_or("no details");
+            return Err(BitbucketError::Unauthorized { message: format!("OAuth token request failed ({status}): {reason}") });
+        }
+        let token: TokenResponse = resp.json().await?;
+        // Bitbucket tokens last two hours; assume that when the response omits `expires_in`.
+        let expires_in = Duration::from_secs(token.expires_in.unwrap_or(7200));
+        tracing::debug!("fetched OAuth access token for client {} (expires in {expires_in:?})", self.client_id);
+        *cached = Some(CachedToken { access_token: token.access_token.clone(), expires_at: Instant::now() + expires_in });
+        Ok(token.access_token)
+    }
+}
+
+impl PartialEq for OAuth2ClientCredentials {
+    fn eq(&self, other: &Self) -> bool {
+        self.client_id == other.client_id && self.client_secret == other.client_secret && self.token_url == other.token_url
+    }
+}
+
+impl Eq for OAuth2ClientCredentials {}
+
+impl fmt::Debug for Credentials {
+    // Never print secrets, so credentials can sit inside structs that get logged.
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Basic { username, .. } => f.debug_struct("Basic").field("username", username).field("password", &"***").finish(),
+            Self::Bearer { .. } => f.debug_struct("Bearer").field("token", &"***").finish(),
+            Self::OAuth2(oauth) => f
+                .debug_struct("OAuth2")
+                .field("client_id", &oauth.client_id)
+                .field("client_secret", &"***")
+                .field("token_url", &oauth.token_url)
+                .finish(),
+        }
+    }
+}
+
+fn missing_username() -> BitbucketError {
+    BitbucketError::Config("BITBUCKET_API_USERNAME env var not set. Please set it to your Atlassian email.".to_string())
+}
+
+fn missing_token() -> BitbucketError {
+    BitbucketError::Config("BITBUCKET_API_TOKEN env var not set. Please set it to your Bitbucket API token (https://id.atlassian.com/manage-profile/security/api-tokens).".to_string())
+}
+
+fn require_env(name: &str) -> Result<String> {
+    env::var(name).map_err(|_| BitbucketError::Config(format!("{name} env var not set")))
+}
+
+fn unauthorized(reason: &str) -> BitbucketError {
+    BitbucketError::Unauthorized { message: format!("Invalid Authorization header: {reason}") }
+}
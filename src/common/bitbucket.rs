@@ -1,4 +1,4 @@
-use serde::{Serialize, Deserialize};
+use serde::{Serialize, Deserialize, de::DeserializeOwned};
 #[derive(Debug, Serialize, Deserialize)]
 pub struct BitbucketCommentContent {
     pub raw: String,
@@ -65,23 +65,23 @@ pub fn normalize_comment_input(body: serde_json::Value) -> Result<BitbucketComme
     }
     
     // Extract inline data if present
-    if let Some(inline) = body.get("inline") {
-        if let Some(path) = inline.get("path").and_then(|v| v.as_str()) {
-            // Safely convert i64 to i32 with range validation
-            let from = match inline.get("from").and_then(|v| v.as_i64()) {
-                Some(v) => Some(i32::try_from(v).map_err(|_| format!("'from' line number {} out of valid range", v))?),
-                None => None,
-            };
-            let to = match inline.get("to").and_then(|v| v.as_i64()) {
-                Some(v) => Some(i32::try_from(v).map_err(|_| format!("'to' line number {} out of valid range", v))?),
-                None => None,
-            };
-            inline_data = Some(BitbucketInline {
-                from,
-                to,
-                path: path.to_string(),
-            });
-        }
+    if let Some(inline) = body.get("inline")
+        && let Some(path) = inline.get("path").and_then(|v| v.as_str())
+    {
+        // Safely convert i64 to i32 with range validation
+        let from = match inline.get("from").and_then(|v| v.as_i64()) {
+            Some(v) => Some(i32::try_from(v).map_err(|_| format!("'from' line number {} out of valid range", v))?),
+            None => None,
+        };
+        let to = match inline.get("to").and_then(|v| v.as_i64()) {
+            Some(v) => Some(i32::try_from(v).map_err(|_| format!("'to' line number {} out of valid range", v))?),
+            None => None,
+        };
+        inline_data = Some(BitbucketInline {
+            from,
+            to,
+            path: path.to_string(),
+        });
     }
     
     if let Some(raw) = comment_raw {
@@ -94,20 +94,69 @@ pub fn normalize_comment_input(body: serde_json::Value) -> Result<BitbucketComme
     Err("Invalid comment input format".to_string())
 }
 // Bitbucket MCP Tool Implementation
-// This module provides MCP tools for Bitbucket Cloud REST API integration.
-// Credentials are fetched from environment variables: BITBUCKET_API_USERNAME, BITBUCKET_API_TOKEN
-
-use std::env;
-use anyhow::{Result, anyhow};
-use reqwest::{Client};
-use rmcp::{Error as McpError, ServerHandler, model::*, schemars, tool};
+// This module provides MCP tools for Bitbucket Cloud REST API integration, with a subset of the
+// pull request and repository methods also available on Data Center (see `datacenter`).
+// Credentials come from `auth::Credentials`, read from the environment or per HTTP session.
+// All requests go through `BitbucketClient::execute`, which waits on the shared `RateLimiter`,
+// retries transient failures according to `RetryPolicy` and maps the rest to a typed `BitbucketError`.
+
+use std::pin::pin;
+use std::sync::Arc;
+use futures::{Stream, StreamExt, TryStreamExt, stream};
+use reqwest::header::HeaderMap;
+use reqwest::{Client, Method, StatusCode};
+use rmcp::handler::server::tool::ToolCallContext;
+use rmcp::service::{RequestContext, RoleServer};
+use rmcp::{Error as McpError, ServerHandler, model::*, tool};
+use super::audit::{self, AuditEntry, AuditLog};
+use super::auth::{self, Credentials};
+use super::cache::{Body, CacheConfig, Lookup, ResponseCache};
+use super::config::ClientConfig;
+use super::confirm::{self, Confirmations};
+use super::datacenter::{self, Backend};
+use super::diagnose;
+use super::disk_cache::{self, DiskCache};
+use super::error::{BitbucketError, Result};
+use super::fields::{self, Projection, Resource};
+use super::models::{
+    AccountRef, BranchEndpoint, Commit, CreateIssue, CreatePullRequest, CreateRepository, Issue, MergePullRequest, Pipeline, PullRequest,
+    Repository, TriggerPipeline, UpdateIssue, UpdatePullRequest, WorkspaceMembership,
+};
+use super::pagination::{self, Page, PageOptions};
+use super::policy::{self, ToolPolicy};
+use super::query::{Filter, StateFilter};
+use super::rate_limit::{RateLimitConfig, RateLimiter};
+use super::retry::{self, RetryPolicy};
+use super::redact;
+use super::scope::{self, RepoScope};
+
+/// Result of `BitbucketClient::check_auth`.
+#[derive(Debug, Clone)]
+pub struct AuthCheck {
+    pub user: serde_json::Value,
+    /// Granted scopes, or `None` when Bitbucket does not report them (e.g. basic auth with an API token).
+    pub scopes: Option<Vec<String>>,
+}
 
 #[derive(Clone)]
 pub struct BitbucketClient {
-    pub api_username: String,
-    pub api_token: String,
+    /// `None` sends requests anonymously, which Bitbucket allows for public resources only.
+    pub credentials: Option<Credentials>,
     pub client: Client,
     pub base_url: String,
+    /// Which REST API `base_url` serves. Data Center supports a subset of the client methods.
+    pub backend: Backend,
+    /// Refuse every request other than GET and HEAD.
+    pub read_only: bool,
+    /// Return requests other than GET and HEAD as rendered JSON instead of sending them.
+    pub dry_run: bool,
+    pub retry: RetryPolicy,
+    limiter: Arc<RateLimiter>,
+    config: Arc<ClientConfig>,
+    scope: Arc<RepoScope>,
+    audit: Option<Arc<AuditLog>>,
+    cache: Option<Arc<ResponseCache>>,
+    disk_cache: Option<Arc<DiskCache>>,
 }
 
 impl BitbucketClient {
@@ -115,607 +164,1267 @@ impl BitbucketClient {
     /// Create a bitbucket pull request
     pub async fn create_pullrequest(&self, workspace: &str, repo_slug: &str, body: serde_json::Value) -> Result<serde_json::Value> {
         let url = format!("{}/repositories/{}/{}/pullrequests", self.base_url, workspace, repo_slug);
-        let req = self.client.post(&url).json(&body);
-        let resp = self.apply_auth(req).send().await?;
-        if !resp.status().is_success() {
-            let status = resp.status();
-            let text = resp.text().await.unwrap_or_default();
-            return Err(anyhow!("Bitbucket API error: {} - {}", status, text));
+        self.send_json(Method::POST, &url, Some(&body)).await
+    }
+
+    /// Typed variant of `create_pullrequest`
+    pub async fn create_pullrequest_typed(&self, workspace: &str, repo_slug: &str, body: &CreatePullRequest) -> Result<PullRequest> {
+        decode(self.create_pullrequest(workspace, repo_slug, encode(body)?).await?)
+    }
+
+    /// Create a bitbucket pull request after checking that its source and destination branches exist
+    pub async fn create_pullrequest_checked(&self, workspace: &str, repo_slug: &str, body: &CreatePullRequest) -> Result<serde_json::Value> {
+        let destination = body.destination.as_ref().map(|d| d.branch.name.as_str());
+        let (source, destination) = tokio::join!(
+            self.require_branch(workspace, repo_slug, "source_branch", &body.source.branch.name),
+            async {
+                match destination {
+                    Some(name) => self.require_branch(workspace, repo_slug, "destination_branch", name).await,
+                    None => Ok(()),
+                }
+            },
+        );
+        source?;
+        destination?;
+        self.create_pullrequest(workspace, repo_slug, encode(body)?).await
+    }
+
+    async fn require_branch(&self, workspace: &str, repo_slug: &str, param: &str, branch: &str) -> Result<()> {
+        match self.get_branch(workspace, repo_slug, branch).await {
+            Ok(_) => Ok(()),
+            Err(BitbucketError::NotFound { .. }) => Err(BitbucketError::Validation {
+                message: format!("Branch '{branch}' does not exist in {workspace}/{repo_slug}"),
+                fields: [(param.to_string(), vec![format!("branch '{branch}' not found")])].into(),
+            }),
+            Err(e) => Err(e),
         }
-        Ok(resp.json().await?)
     }
 
     /// Get bitbucket pull request details
-    pub async fn get_pullrequest(&self, workspace: &str, repo_slug: &str, pr_id: &str) -> Result<serde_json::Value> {
-        let url = format!("{}/repositories/{}/{}/pullrequests/{}", self.base_url, workspace, repo_slug, pr_id);
-        let req = self.client.get(&url);
-        let resp = self.apply_auth(req).send().await?;
-        if !resp.status().is_success() {
-            let status = resp.status();
-            let text = resp.text().await.unwrap_or_default();
-            return Err(anyhow!("Bitbucket API error: {} - {}", status, text));
+    pub async fn get_pullrequest(&self, workspace: &str, repo_slug: &str, pr_id: &str, fields: Option<&str>) -> Result<serde_json::Value> {
+        if self.backend == Backend::DataCenter {
+            return self.dc_get_pullrequest(workspace, repo_slug, pr_id).await;
         }
-        Ok(resp.json().await?)
+        let url = format!("{}/repositories/{}/{}/pullrequests/{}", self.base_url, workspace, repo_slug, pr_id);
+        self.get_json(&fields::with_fields(&url, fields, false)?).await
+    }
+
+    /// Typed variant of `get_pullrequest`
+    pub async fn get_pullrequest_typed(&self, workspace: &str, repo_slug: &str, pr_id: &str) -> Result<PullRequest> {
+        decode(self.get_pullrequest(workspace, repo_slug, pr_id, None).await?)
     }
 
     /// Update a bitbucket pull request
     pub async fn update_pullrequest(&self, workspace: &str, repo_slug: &str, pr_id: &str, body: serde_json::Value) -> Result<serde_json::Value> {
         let url = format!("{}/repositories/{}/{}/pullrequests/{}", self.base_url, workspace, repo_slug, pr_id);
-        let req = self.client.put(&url).json(&body);
-        let resp = self.apply_auth(req).send().await?;
-        if !resp.status().is_success() {
-            let status = resp.status();
-            let text = resp.text().await.unwrap_or_default();
-            return Err(anyhow!("Bitbucket API error: {} - {}", status, text));
-        }
-        Ok(resp.json().await?)
+        self.send_json(Method::PUT, &url, Some(&body)).await
+    }
+
+    /// Typed variant of `update_pullrequest`
+    pub async fn update_pullrequest_typed(&self, workspace: &str, repo_slug: &str, pr_id: &str, body: &UpdatePullRequest) -> Result<PullRequest> {
+        decode(self.update_pullrequest(workspace, repo_slug, pr_id, encode(body)?).await?)
     }
 
     /// Approve a bitbucket pull request
     pub async fn approve_pullrequest(&self, workspace: &str, repo_slug: &str, pr_id: &str) -> Result<serde_json::Value> {
-        let url = format!("{}/repositories/{}/{}/pullrequests/{}/approve", self.base_url, workspace, repo_slug, pr_id);
-        let req = self.client.post(&url);
-        let resp = self.apply_auth(req).send().await?;
-        if !resp.status().is_success() {
-            let status = resp.status();
-            let text = resp.text().await.unwrap_or_default();
-            return Err(anyhow!("Bitbucket API error: {} - {}", status, text));
+        if self.backend == Backend::DataCenter {
+            return self.dc_send(Method::POST, workspace, repo_slug, &format!("/pull-requests/{pr_id}/approve"), None).await;
         }
-        Ok(resp.json().await?)
+        let url = format!("{}/repositories/{}/{}/pullrequests/{}/approve", self.base_url, workspace, repo_slug, pr_id);
+        self.send_json(Method::POST, &url, None).await
     }
 
     /// Unapprove a bitbucket pull request
     pub async fn unapprove_pullrequest(&self, workspace: &str, repo_slug: &str, pr_id: &str) -> Result<serde_json::Value> {
-        let url = format!("{}/repositories/{}/{}/pullrequests/{}/approve", self.base_url, workspace, repo_slug, pr_id);
-        let req = self.client.delete(&url);
-        let resp = self.apply_auth(req).send().await?;
-        if !resp.status().is_success() {
-            let status = resp.status();
-            let text = resp.text().await.unwrap_or_default();
-            return Err(anyhow!("Bitbucket API error: {} - {}", status, text));
+        if self.backend == Backend::DataCenter {
+            return self.dc_send(Method::DELETE, workspace, repo_slug, &format!("/pull-requests/{pr_id}/approve"), None).await;
         }
-        Ok(resp.json().await?)
+        let url = format!("{}/repositories/{}/{}/pullrequests/{}/approve", self.base_url, workspace, repo_slug, pr_id);
+        self.send_json(Method::DELETE, &url, None).await
     }
 
     /// Decline a bitbucket pull request
     pub async fn decline_pullrequest(&self, workspace: &str, repo_slug: &str, pr_id: &str) -> Result<serde_json::Value> {
-        let url = format!("{}/repositories/{}/{}/pullrequests/{}/decline", self.base_url, workspace, repo_slug, pr_id);
-        let req = self.client.post(&url);
-        let resp = self.apply_auth(req).send().await?;
-        if !resp.status().is_success() {
-            let status = resp.status();
-            let text = resp.text().await.unwrap_or_default();
-            return Err(anyhow!("Bitbucket API error: {} - {}", status, text));
+        if self.backend == Backend::DataCenter {
+            return self.dc_change_state(workspace, repo_slug, pr_id, "decline", None).await;
         }
-        Ok(resp.json().await?)
+        let url = format!("{}/repositories/{}/{}/pullrequests/{}/decline", self.base_url, workspace, repo_slug, pr_id);
+        self.send_json(Method::POST, &url, None).await
     }
 
     /// Merge a bitbucket pull request
     pub async fn merge_pullrequest(&self, workspace: &str, repo_slug: &str, pr_id: &str, body: Option<serde_json::Value>) -> Result<serde_json::Value> {
-        let url = format!("{}/repositories/{}/{}/pullrequests/{}/merge", self.base_url, workspace, repo_slug, pr_id);
-        let req = if let Some(b) = body {
-            self.client.post(&url).json(&b)
-        } else {
-            self.client.post(&url)
-        };
-        let resp = self.apply_auth(req).send().await?;
-        if !resp.status().is_success() {
-            let status = resp.status();
-            let text = resp.text().await.unwrap_or_default();
-            return Err(anyhow!("Bitbucket API error: {} - {}", status, text));
+        if self.backend == Backend::DataCenter {
+            return self.dc_change_state(workspace, repo_slug, pr_id, "merge", Some(datacenter::merge_body(body.as_ref()))).await;
         }
-        Ok(resp.json().await?)
+        let url = format!("{}/repositories/{}/{}/pullrequests/{}/merge", self.base_url, workspace, repo_slug, pr_id);
+        self.send_json(Method::POST, &url, body.as_ref()).await
+    }
+
+    /// Typed variant of `merge_pullrequest`
+    pub async fn merge_pullrequest_typed(&self, workspace: &str, repo_slug: &str, pr_id: &str, body: &MergePullRequest) -> Result<PullRequest> {
+        decode(self.merge_pullrequest(workspace, repo_slug, pr_id, Some(encode(body)?)).await?)
     }
 
     /// List bitbucket pull request comments with pagination support
-    pub async fn list_pullrequest_comments(&self, workspace: &str, repo_slug: &str, pr_id: &str) -> Result<serde_json::Value> {
+    pub async fn list_pullrequest_comments(&self, workspace: &str, repo_slug: &str, pr_id: &str, page: &PageOptions, fields: Option<&str>) -> Result<serde_json::Value> {
+        if self.backend == Backend::DataCenter {
+            return self.dc_list_pullrequest_comments(workspace, repo_slug, pr_id, page).await;
+        }
         let url = format!("{}/repositories/{}/{}/pullrequests/{}/comments", self.base_url, workspace, repo_slug, pr_id);
-        self.fetch_paginated(url).await
+        let url = fields::with_fields(&url, fields, true)?;
+        self.fetch_paginated(url, page).await
     }
 
     /// Add a bitbucket pull request comment
     pub async fn add_pullrequest_comment(&self, workspace: &str, repo_slug: &str, pr_id: &str, body: BitbucketCommentPayload) -> Result<serde_json::Value> {
-        let url = format!("{}/repositories/{}/{}/pullrequests/{}/comments", self.base_url, workspace, repo_slug, pr_id);
-        let req = self.client.post(&url).json(&body);
-        let resp = self.apply_auth(req).send().await?;
-        if !resp.status().is_success() {
-            let status = resp.status();
-            let text = resp.text().await.unwrap_or_default();
-            return Err(anyhow!("Bitbucket API error: {} - {}", status, text));
+        if self.backend == Backend::DataCenter {
+            return self.dc_send(Method::POST, workspace, repo_slug, &format!("/pull-requests/{pr_id}/comments"), Some(datacenter::comment_body(&body))).await;
         }
-        Ok(resp.json().await?)
+        let url = format!("{}/repositories/{}/{}/pullrequests/{}/comments", self.base_url, workspace, repo_slug, pr_id);
+        let body = serde_json::to_value(&body)?;
+        self.send_json(Method::POST, &url, Some(&body)).await
     }
 
     /// List bitbucket pull request activity
-    pub async fn list_pullrequest_activity(&self, workspace: &str, repo_slug: &str, pr_id: &str) -> Result<serde_json::Value> {
+    pub async fn list_pullrequest_activity(&self, workspace: &str, repo_slug: &str, pr_id: &str, page: &PageOptions, fields: Option<&str>) -> Result<serde_json::Value> {
         let url = format!("{}/repositories/{}/{}/pullrequests/{}/activity", self.base_url, workspace, repo_slug, pr_id);
-        self.fetch_paginated(url).await
+        let url = fields::with_fields(&url, fields, true)?;
+        self.fetch_paginated(url, page).await
     }
 
-    /// Get bitbucket pull request diff
+    /// Get bitbucket pull request diff. Takes no `fields`: the diff is plain text, not JSON.
     pub async fn get_pullrequest_diff(&self, workspace: &str, repo_slug: &str, pr_id: &str) -> Result<String> {
-        let url = format!("{}/repositories/{}/{}/pullrequests/{}/diff", self.base_url, workspace, repo_slug, pr_id);
-        let req = self.client.get(&url);
-        let resp = self.apply_auth(req).send().await?;
-        if !resp.status().is_success() {
-            let status = resp.status();
-            let text = resp.text().await.unwrap_or_default();
-            return Err(anyhow!("Bitbucket API error: {} - {}", status, text));
+        if self.backend == Backend::DataCenter {
+            return self.dc_get_pullrequest_diff(workspace, repo_slug, pr_id).await;
         }
-        Ok(resp.text().await?)
+        let url = format!("{}/repositories/{}/{}/pullrequests/{}/diff", self.base_url, workspace, repo_slug, pr_id);
+        self.get_text(&url).await
     }
 
     /// Get bitbucket pull request commits with pagination
-    pub async fn list_pullrequest_commits(&self, workspace: &str, repo_slug: &str, pr_id: &str) -> Result<serde_json::Value> {
+    pub async fn list_pullrequest_commits(&self, workspace: &str, repo_slug: &str, pr_id: &str, page: &PageOptions, fields: Option<&str>) -> Result<serde_json::Value> {
         let url = format!("{}/repositories/{}/{}/pullrequests/{}/commits", self.base_url, workspace, repo_slug, pr_id);
-        self.fetch_paginated(url).await
+        let url = fields::with_fields(&url, fields, true)?;
+        self.fetch_paginated(url, page).await
     }
 
     /// List bitbucket pull request tasks with pagination
-    pub async fn list_pullrequest_tasks(&self, workspace: &str, repo_slug: &str, pr_id: &str) -> Result<serde_json::Value> {
+    pub async fn list_pullrequest_tasks(&self, workspace: &str, repo_slug: &str, pr_id: &str, page: &PageOptions, fields: Option<&str>) -> Result<serde_json::Value> {
         let url = format!("{}/repositories/{}/{}/pullrequests/{}/tasks", self.base_url, workspace, repo_slug, pr_id);
-        self.fetch_paginated(url).await
+        let url = fields::with_fields(&url, fields, true)?;
+        self.fetch_paginated(url, page).await
     }
 
     /// Add a bitbucket pull request task
     pub async fn add_pullrequest_task(&self, workspace: &str, repo_slug: &str, pr_id: &str, body: serde_json::Value) -> Result<serde_json::Value> {
         let url = format!("{}/repositories/{}/{}/pullrequests/{}/tasks", self.base_url, workspace, repo_slug, pr_id);
-        let req = self.client.post(&url).json(&body);
-        let resp = self.apply_auth(req).send().await?;
-        if !resp.status().is_success() {
-            let status = resp.status();
-            let text = resp.text().await.unwrap_or_default();
-            return Err(anyhow!("Bitbucket API error: {} - {}", status, text));
-        }
-        Ok(resp.json().await?)
+        self.send_json(Method::POST, &url, Some(&body)).await
     }
 
     /// Get bitbucket pull request diffstat
-    pub async fn get_pullrequest_diffstat(&self, workspace: &str, repo_slug: &str, pr_id: &str) -> Result<serde_json::Value> {
+    pub async fn get_pullrequest_diffstat(&self, workspace: &str, repo_slug: &str, pr_id: &str, fields: Option<&str>) -> Result<serde_json::Value> {
         let url = format!("{}/repositories/{}/{}/pullrequests/{}/diffstat", self.base_url, workspace, repo_slug, pr_id);
-        let req = self.client.get(&url);
-        let resp = self.apply_auth(req).send().await?;
-        if !resp.status().is_success() {
+        self.get_json(&fields::with_fields(&url, fields, false)?).await
+    }
+    pub fn from_env() -> Result<Self> {
+        let client = Self::from_env_settings()?;
+        if client.credentials.is_none() {
+            let method = std::env::var("BITBUCKET_AUTH_METHOD").ok().and_then(|method| method.parse().ok());
+            return Err(auth::missing_credentials(method));
+        }
+        Ok(client)
+    }
+
+    /// Like `from_env`, but credentials are optional.
+    ///
+    /// Used by the HTTP server, where each session normally brings its own credentials and the
+    /// environment only provides a fallback.
+    pub fn from_env_settings() -> Result<Self> {
+        let client = Self::from_config(ClientConfig::load()?)?.with_credentials(Credentials::from_env()?);
+        Ok(client
+            .with_retry_policy(RetryPolicy::from_env()?)
+            .with_rate_limit(RateLimitConfig::from_env()?))
+    }
+
+    /// Creates a client without credentials from validated connection settings.
+    pub fn from_config(config: ClientConfig) -> Result<Self> {
+        config.validate()?;
+        Ok(Self {
+            client: config.http_client()?,
+            base_url: config.base_url(),
+            backend: config.backend,
+            read_only: config.read_only,
+            dry_run: config.dry_run,
+            scope: Arc::new(RepoScope::new(&config.scope)?),
+            audit: config.audit_log.as_ref().map(|path| Arc::new(AuditLog::new(path))),
+            cache: config.cache().map(|cache| Arc::new(ResponseCache::new(cache))),
+            disk_cache: config.disk_cache_dir.as_ref().map(|dir| DiskCache::new(dir, config.disk_cache_max_bytes()).map(Arc::new)).transpose()?,
+            config: Arc::new(config),
+            ..Self::anonymous("")
+        })
+    }
+
+    /// Creates a client with basic auth and the default retry policy.
+    pub fn new(api_username: impl Into<String>, api_token: impl Into<String>, base_url: impl Into<String>) -> Self {
+        Self::anonymous(base_url).with_credentials(Some(Credentials::basic(api_username, api_token)))
+    }
+
+    /// Creates a client without credentials and with the default retry policy.
+    pub fn anonymous(base_url: impl Into<String>) -> Self {
+        Self {
+            credentials: None,
+            client: ClientConfig::default().http_client().expect("default HTTP client settings are valid"),
+            base_url: base_url.into(),
+            backend: Backend::Cloud,
+            read_only: false,
+            dry_run: false,
+            retry: RetryPolicy::default(),
+            limiter: Arc::new(RateLimiter::new(RateLimitConfig::default())),
+            config: Arc::new(ClientConfig::default()),
+            scope: Arc::new(RepoScope::default()),
+            audit: None,
+            cache: None,
+            disk_cache: None,
+        }
+    }
+
+    /// Replaces the credentials. The rate limiter stays shared with the client this was cloned from;
+    /// the response cache does not, so one identity never sees responses fetched by another.
+    pub fn with_credentials(mut self, credentials: Option<Credentials>) -> Self {
+        self.credentials = credentials;
+        self.cache = self.cache.map(|cache| Arc::new(ResponseCache::new(cache.config())));
+        self
+    }
+
+    /// Enables (`Some`) or disables the GET response cache.
+    pub fn with_cache(mut self, cache: Option<CacheConfig>) -> Self {
+        self.cache = cache.map(|cache| Arc::new(ResponseCache::new(cache)));
+        self
+    }
+
+    /// Enables (`Some`) or disables the persistent cache of responses fetched at a full commit hash.
+    /// Entries are keyed by the credentials, so clients with different identities can share it.
+    pub fn with_disk_cache(mut self, disk_cache: Option<DiskCache>) -> Self {
+        self.disk_cache = disk_cache.map(Arc::new);
+        self
+    }
+
+    /// Selects the REST API served at `base_url`. For Data Center, `base_url` is the server URL
+    /// without `/rest/api/1.0`.
+    pub fn with_backend(mut self, backend: Backend) -> Self {
+        self.backend = backend;
+        self
+    }
+
+    pub fn with_read_only(mut self, read_only: bool) -> Self {
+        self.read_only = read_only;
+        self
+    }
+
+    pub fn with_dry_run(mut self, dry_run: bool) -> Self {
+        self.dry_run = dry_run;
+        self
+    }
+
+    /// Records every request other than GET and HEAD in `audit`.
+    pub fn with_audit_log(mut self, audit: Option<AuditLog>) -> Self {
+        self.audit = audit.map(Arc::new);
+        self
+    }
+
+    pub fn audit_log(&self) -> Option<&AuditLog> {
+        self.audit.as_deref()
+    }
+
+    /// Restricts the workspaces and repositories requests may address.
+    pub fn with_scope(mut self, scope: RepoScope) -> Self {
+        self.scope = Arc::new(scope);
+        self
+    }
+
+    pub fn with_retry_policy(mut self, retry: RetryPolicy) -> Self {
+        self.retry = retry;
+        self
+    }
+
+    /// Replaces the rate limiter. Clones made afterwards share the new limiter.
+    pub fn with_rate_limit(mut self, config: RateLimitConfig) -> Self {
+        self.limiter = Arc::new(RateLimiter::new(config));
+        self
+    }
+
+    /// Effective connection, auth, retry and rate limit settings, without secrets.
+    pub fn diagnostics(&self) -> serde_json::Value {
+        let mut connection = self.config.summary();
+        connection["base_url"] = self.base_url.clone().into();
+        connection["backend"] = serde_json::json!(self.backend);
+        connection["read_only"] = self.read_only.into();
+        connection["dry_run"] = self.dry_run.into();
+        connection["audit_log"] = serde_json::json!(self.audit.as_ref().map(|audit| audit.path()));
+        connection["scope"] = self.scope.patterns().iter().map(ToString::to_string).collect::<Vec<_>>().into();
+        serde_json::json!({
+            "version": env!("CARGO_PKG_VERSION"),
+            "connection": connection,
+            "auth_method": self.credentials.as_ref().map(Credentials::method),
+            "retry": {
+                "max_retries": self.retry.max_retries,
+                "base_delay_ms": self.retry.base_delay.as_millis() as u64,
+                "max_delay_ms": self.retry.max_delay.as_millis() as u64,
+                "retry_non_idempotent": self.retry.retry_non_idempotent,
+            },
+            "rate_limit": self.limiter.snapshot(),
+            "cache": self.cache.as_ref().map(|cache| cache.stats()),
+            "disk_cache": self.disk_cache.as_ref().map(|disk_cache| disk_cache.stats()),
+        })
+    }
+
+    /// Client-side limiter state and the last `X-RateLimit-*` budget reported by Bitbucket.
+    pub fn rate_limit_status(&self) -> serde_json::Value {
+        self.limiter.snapshot()
+    }
+
+    async fn apply_auth(&self, req: reqwest::RequestBuilder) -> Result<reqwest::RequestBuilder> {
+        match &self.credentials {
+            Some(credentials) => credentials.apply(&self.client, req).await,
+            None => Ok(req),
+        }
+    }
+
+    /// Backend, scope and read-only checks that every request passes before it is sent or rendered.
+    fn check_request(&self, method: &Method, url: &str) -> Result<()> {
+        if self.backend == Backend::DataCenter && !url.starts_with(&format!("{}{}", self.base_url, datacenter::API_PATH)) {
+            return Err(BitbucketError::Unsupported(format!(
+                "{method} {} is a Bitbucket Cloud endpoint and is not available on Data Center",
+                self.display_path(url)
+            )));
+        }
+        self.scope.check_url(method, &self.base_url, url)?;
+        if self.read_only && !matches!(*method, Method::GET | Method::HEAD) {
+            return Err(BitbucketError::Denied(format!("{method} {} refused because the client is in read-only mode", self.display_path(url))));
+        }
+        Ok(())
+    }
+
+    /// Single request path used by every client method.
+    ///
+    /// Sends the request with authentication and returns the response on success.
+    /// 429/5xx responses and connection failures are retried per `self.retry` when the
+    /// method allows it; anything else is converted into a typed `BitbucketError`.
+    async fn execute(&self, method: Method, url: &str, body: Option<&serde_json::Value>) -> Result<reqwest::Response> {
+        self.execute_with(method, url, body, None).await
+    }
+
+    /// `execute` with extra request headers. With `conditions` (`If-None-Match`/`If-Modified-Since`),
+    /// a `304 Not Modified` response is returned instead of treated as an error.
+    async fn execute_with(&self, method: Method, url: &str, body: Option<&serde_json::Value>, conditions: Option<&HeaderMap>) -> Result<reqwest::Response> {
+        self.check_request(&method, url)?;
+        let can_retry = self.retry.allows_method(&method);
+        let mut attempt = 0;
+        loop {
+            let mut req = self.client.request(method.clone(), url);
+            if let Some(body) = body {
+                req = req.json(body);
+            }
+            if let Some(conditions) = conditions {
+                req = req.headers(conditions.clone());
+            }
+            let retries_left = can_retry && attempt < self.retry.max_retries;
+            let req = self.apply_auth(req).await?;
+            let sent = {
+                let _permit = self.limiter.acquire().await;
+                let sent = req.send().await;
+                if let Ok(resp) = &sent {
+                    self.limiter.observe(resp.headers());
+                }
+                sent
+            };
+            let resp = match sent {
+                Ok(resp) => resp,
+                Err(e) if retries_left && RetryPolicy::is_retryable_error(&e) => {
+                    let delay = self.retry.delay_for(attempt, None);
+                    tracing::warn!("{method} {} failed ({e}), retrying in {delay:?}", self.display_path(url));
+                    tokio::time::sleep(delay).await;
+                    attempt += 1;
+                    continue;
+                }
+                Err(e) => return Err(e.into()),
+            };
             let status = resp.status();
+            if status.is_success() || (status == StatusCode::NOT_MODIFIED && conditions.is_some()) {
+                return Ok(resp);
+            }
+            if retries_left && RetryPolicy::is_retryable_status(status) {
+                let delay = self.retry.delay_for(attempt, Some(resp.headers()));
+                tracing::warn!("{method} {} returned {status}, retrying in {delay:?}", self.display_path(url));
+                tokio::time::sleep(delay).await;
+                attempt += 1;
+                continue;
+            }
+            if status == StatusCode::UNAUTHORIZED
+                && let Some(credentials) = &self.credentials
+            {
+                credentials.invalidate().await;
+            }
+            let retry_after = retry::retry_after(resp.headers());
             let text = resp.text().await.unwrap_or_default();
-            return Err(anyhow!("Bitbucket API error: {} - {}", status, text));
+            return Err(BitbucketError::from_response(status, self.display_path(url), retry_after, &text));
+        }
+    }
+
+    /// Sends a request and decodes the JSON response. `204 No Content` yields an empty object.
+    async fn send_json(&self, method: Method, url: &str, body: Option<&serde_json::Value>) -> Result<serde_json::Value> {
+        if self.dry_run && !matches!(method, Method::GET | Method::HEAD) {
+            self.check_request(&method, url)?;
+            return Ok(self.render_request(&method, url, body));
+        }
+        if method == Method::GET
+            && let Some(cache) = &self.cache
+        {
+            return Ok(self.get_cached(cache, url, false).await?.into_json());
+        }
+        let mutating = !matches!(method, Method::GET | Method::HEAD);
+        let audit = self.audit.as_ref().filter(|_| mutating);
+        let mut entry = audit.map(|_| AuditEntry::new(method.as_str(), scope::describe(&self.base_url, url), body));
+        let sent = self.execute(method, url, body).await;
+        if let (Some(audit), Some(entry)) = (audit, entry.as_mut()) {
+            match &sent {
+                Ok(resp) => entry.status = Some(resp.status().as_u16()),
+                Err(e) => {
+                    entry.status = e.status().map(|s| s.as_u16());
+                    entry.error = Some(e.to_string());
+                }
+            }
+            audit.record(entry);
+        }
+        if mutating && let Some(cache) = &self.cache {
+            let (workspace, repo, _) = scope::describe(&self.base_url, url);
+            cache.invalidate(workspace.as_deref(), repo.as_deref());
+        }
+        let resp = sent?;
+        if resp.status() == StatusCode::NO_CONTENT {
+            return Ok(serde_json::json!({}));
         }
         Ok(resp.json().await?)
     }
-    pub fn from_env() -> Result<Self> {
-        let api_username = env::var("BITBUCKET_API_USERNAME")
-            .map_err(|_| anyhow!("BITBUCKET_API_USERNAME env var not set. Please set it to your Atlassian email."))?;
-        let api_token = env::var("BITBUCKET_API_TOKEN")
-            .map_err(|_| anyhow!("BITBUCKET_API_TOKEN env var not set. Please set it to your Bitbucket API token (https://id.atlassian.com/manage-profile/security/api-tokens)."))?;
-        Ok(Self {
-            api_username,
-            api_token,
-            client: Client::new(),
-            base_url: "https://api.bitbucket.org/2.0".to_string(),
+
+    /// The request `send_json` would send, with the credentials masked.
+    fn render_request(&self, method: &Method, url: &str, body: Option<&serde_json::Value>) -> serde_json::Value {
+        let mut headers = serde_json::Map::new();
+        if let Some(credentials) = &self.credentials {
+            headers.insert("Authorization".to_string(), credentials.redacted_header().into());
+        }
+        if body.is_some() {
+            headers.insert("Content-Type".to_string(), "application/json".into());
+        }
+        serde_json::json!({
+            "dry_run": true,
+            "method": method.as_str(),
+            "url": url,
+            "headers": headers,
+            "body": body.map(redact::redact_value),
         })
     }
 
-    fn apply_auth(&self, req: reqwest::RequestBuilder) -> reqwest::RequestBuilder {
-        req.basic_auth(&self.api_username, Some(&self.api_token))
+    /// GET through the response cache: fresh entries are served directly, stale ones revalidated
+    /// with a conditional request when they carry an `ETag` or `Last-Modified` validator. With
+    /// `text`, the body is kept as received instead of decoded as JSON.
+    async fn get_cached(&self, cache: &ResponseCache, url: &str, text: bool) -> Result<Body> {
+        let conditions = match cache.lookup(url) {
+            Lookup::Fresh(body) if body.is_text() == text => return Ok(body),
+            Lookup::Stale(conditions) => Some(conditions),
+            _ => None,
+        };
+        let mut resp = self.execute_with(Method::GET, url, None, conditions.as_ref()).await?;
+        if resp.status() == StatusCode::NOT_MODIFIED {
+            if let Some(body) = cache.revalidated(url).filter(|body| body.is_text() == text) {
+                return Ok(body);
+            }
+            // Evicted while the request was in flight.
+            resp = self.execute(Method::GET, url, None).await?;
+        }
+        let headers = resp.headers().clone();
+        let body = if text {
+            Body::Text(resp.text().await?.into())
+        } else if resp.status() == StatusCode::NO_CONTENT {
+            return Ok(Body::Json(serde_json::json!({})));
+        } else {
+            Body::Json(resp.json().await?)
+        };
+        let (workspace, repo, _) = scope::describe(&self.base_url, url);
+        cache.store(url, body.clone(), &headers, (workspace, repo));
+        Ok(body)
+    }
+
+    /// GET returning the response text as received, such as a diff or file content.
+    async fn get_text(&self, url: &str) -> Result<String> {
+        match &self.cache {
+            Some(cache) => Ok(self.get_cached(cache, url, true).await?.into_text()),
+            None => Ok(self.execute(Method::GET, url, None).await?.text().await?),
+        }
+    }
+
+    async fn get_json(&self, url: &str) -> Result<serde_json::Value> {
+        self.send_json(Method::GET, url, None).await
+    }
+
+    /// The disk cache, when enabled and `rev` is a full commit hash (so file contents at it cannot change).
+    fn disk_cache_for(&self, rev: &str) -> Option<&DiskCache> {
+        self.disk_cache.as_deref().filter(|_| disk_cache::is_commit_hash(rev))
+    }
+
+    /// Raw body of a GET whose response cannot change, served from and stored in `disk_cache`.
+    async fn get_immutable(&self, disk_cache: &DiskCache, url: &str) -> Result<Vec<u8>> {
+        self.check_request(&Method::GET, url)?;
+        let identity = self.credentials.as_ref().map(Credentials::cache_identity).unwrap_or_default();
+        let key = DiskCache::key(&identity, url);
+        if let Some(body) = disk_cache.get(&key).await {
+            return Ok(body);
+        }
+        let body = self.execute(Method::GET, url, None).await?.bytes().await?.to_vec();
+        disk_cache.put(&key, body.clone()).await;
+        Ok(body)
+    }
+
+    /// Strips the base URL so error messages show the API path, e.g. `/repositories/ws/repo`.
+    fn display_path<'a>(&self, url: &'a str) -> &'a str {
+        url.strip_prefix(self.base_url.as_str()).unwrap_or(url)
+    }
+
+    /// Lazily walks a listing page by page, following Bitbucket's `next` links.
+    ///
+    /// `url` is the listing endpoint, either absolute or relative to `base_url`
+    /// (e.g. `/repositories/ws/repo/commits`). `page.pagelen` and `page.page` select the first
+    /// page; `limit` and `max_pages` are left to the consumer. The stream owns a clone of the
+    /// client, so it can outlive `self` and be moved to another task.
+    pub fn paginate_pages(&self, url: impl Into<String>, page: &PageOptions) -> impl Stream<Item = Result<Page>> + Send + 'static {
+        let first = self.first_page_url(self.absolute_url(url.into()), page);
+        stream::try_unfold((self.clone(), Some(first)), |(client, next)| async move {
+            let url = match next {
+                Some(url) => url?,
+                None => return Ok(None),
+            };
+            let body = client.get_json(&url).await?;
+            let mut current: Page = match client.backend {
+                Backend::Cloud => serde_json::from_value(body)?,
+                Backend::DataCenter => datacenter::into_page(&url, body)?,
+            };
+            client.scope.filter_listing(&client.base_url, &url, &mut current.values);
+            if current.next.as_deref() == Some(url.as_str()) {
+                return Err(BitbucketError::Pagination(format!("Page {} links to itself - circular pagination", client.display_path(&url))));
+            }
+            let next = current.next.clone().map(Ok);
+            Ok(Some((current, (client, next))))
+        })
+    }
+
+    /// Streams the items of a listing one at a time, decoded into `T`.
+    ///
+    /// Pages are fetched on demand, so arbitrarily large listings can be processed without
+    /// holding them in memory. `page.limit` caps the number of items and `page.max_pages`
+    /// the number of pages requested; both are unbounded by default.
+    pub fn paginate_stream<T: DeserializeOwned>(&self, url: impl Into<String>, page: &PageOptions) -> impl Stream<Item = Result<T>> + Send + 'static {
+        self.paginate_pages(url, page)
+            .take(page.max_pages.unwrap_or(usize::MAX))
+            .map_ok(|current| stream::iter(current.values.into_iter().map(|value| Ok(serde_json::from_value(value)?))))
+            .try_flatten()
+            .take(page.limit.unwrap_or(usize::MAX))
     }
 
     /// Helper method to handle paginated API responses
-    /// Fetches all pages and aggregates results into a single response
-    /// 
+    /// Fetches pages and aggregates results into a single response
+    ///
+    /// Fetching stops at the end of the listing, once `page.limit` values have been collected
+    /// or after `page.max_pages` pages. When it stops early the response carries a `next`
+    /// cursor that can be passed back as `page.page` to resume.
+    ///
     /// # Safety
-    /// Includes a maximum page limit of 1000 to prevent infinite loops
-    /// in case of malformed API responses or circular pagination links.
-    async fn fetch_paginated(&self, initial_url: String) -> Result<serde_json::Value> {
+    /// Without an explicit `max_pages`, a limit of 1000 pages guards against infinite loops
+    /// in case of malformed API responses or circular pagination links; reaching it is an error.
+    /// An explicit `max_pages` replaces that limit, however large.
+    async fn fetch_paginated(&self, initial_url: String, page: &PageOptions) -> Result<serde_json::Value> {
         const MAX_PAGES: usize = 1000;
+        let mut pages = pin!(self.paginate_pages(initial_url, page));
         let mut all_values = Vec::new();
-        let mut url = initial_url;
         let mut page_count = 0;
-        
-        loop {
+
+        while let Some(current) = pages.try_next().await? {
             page_count += 1;
-            if page_count > MAX_PAGES {
-                return Err(anyhow!("Exceeded maximum page limit ({}) - possible circular pagination", MAX_PAGES));
-            }
-            
-            let req = self.client.get(&url);
-            let resp = self.apply_auth(req).send().await?;
-            if !resp.status().is_success() {
-                let status = resp.status();
-                let text = resp.text().await.unwrap_or_default();
-                return Err(anyhow!("Bitbucket API error: {} - {}", status, text));
+            all_values.extend(current.values);
+
+            let Some(next) = current.next else {
+                break;
+            };
+            let limit_reached = page.limit.is_some_and(|limit| all_values.len() >= limit);
+            if limit_reached || page.max_pages.is_some_and(|max| page_count >= max) {
+                return Ok(Self::page_result(all_values, Some(next)));
             }
-            
-            let page: serde_json::Value = resp.json().await?;
-            
-            // Collect values from this page
-            if let Some(values) = page.get("values").and_then(|v| v.as_array()) {
-                all_values.extend_from_slice(values);
+            if page.max_pages.is_none() && page_count >= MAX_PAGES {
+                return Err(BitbucketError::Pagination(format!("Exceeded maximum page limit ({}) - possible circular pagination", MAX_PAGES)));
             }
-            
-            // Check if there's a next page
-            if let Some(next_url) = page.get("next").and_then(|v| v.as_str()) {
-                url = next_url.to_string();
-            } else {
-                // No more pages, return combined results
-                return Ok(serde_json::json!({
-                    "values": all_values,
-                    "size": all_values.len()
-                }));
+        }
+        // No more pages, return combined results
+        Ok(Self::page_result(all_values, None))
+    }
+
+    fn absolute_url(&self, url: String) -> String {
+        if url.starts_with("http://") || url.starts_with("https://") {
+            url
+        } else {
+            format!("{}{}", self.base_url, url)
+        }
+    }
+
+    /// URL of the first page to fetch: the `next` cursor when resuming, otherwise the
+    /// listing URL with `pagelen`/`page` applied.
+    fn first_page_url(&self, url: String, page: &PageOptions) -> Result<String> {
+        if page.is_cursor() {
+            let cursor = page.page.clone().unwrap_or_default();
+            if !scope::is_under(&self.base_url, &cursor) {
+                return Err(BitbucketError::Pagination(format!("Cursor '{cursor}' does not belong to {}", self.base_url)));
             }
+            return Ok(cursor);
         }
+        if self.backend == Backend::DataCenter {
+            return pagination::append_query(&url, &datacenter::page_params(page)?);
+        }
+        let mut query = Vec::new();
+        if let Some(pagelen) = page.effective_pagelen() {
+            query.push(("pagelen", pagelen.to_string()));
+        }
+        if let Some(number) = &page.page {
+            query.push(("page", number.clone()));
+        }
+        pagination::append_query(&url, &query)
     }
 
-    pub async fn get_user(&self) -> Result<serde_json::Value> {
+    fn page_result(values: Vec<serde_json::Value>, next: Option<String>) -> serde_json::Value {
+        let mut result = serde_json::json!({
+            "size": values.len(),
+            "values": values,
+        });
+        if let Some(next) = next {
+            result["next"] = serde_json::Value::String(next);
+        }
+        result
+    }
+
+    // --- Data Center ---
+    // `workspace` is the project key. Responses are returned in Data Center's own shape.
+
+    fn dc_repo_url(&self, project_key: &str, repo_slug: &str, path: &str) -> String {
+        format!("{}{}/projects/{}/repos/{}{}", self.base_url, datacenter::API_PATH, project_key, repo_slug, path)
+    }
+
+    async fn dc_send(&self, method: Method, project_key: &str, repo_slug: &str, path: &str, body: Option<serde_json::Value>) -> Result<serde_json::Value> {
+        self.send_json(method, &self.dc_repo_url(project_key, repo_slug, path), body.as_ref()).await
+    }
+
+    async fn dc_list_repositories(&self, project_key: &str, filter: &Filter, page: &PageOptions) -> Result<serde_json::Value> {
+        datacenter::reject_filter(filter, "repositories")?;
+        let url = format!("{}{}/projects/{}/repos", self.base_url, datacenter::API_PATH, project_key);
+        self.fetch_paginated(url, page).await
+    }
+
+    async fn dc_list_pullrequests(&self, project_key: &str, repo_slug: &str, filter: &Filter, page: &PageOptions) -> Result<serde_json::Value> {
+        let url = self.dc_repo_url(project_key, repo_slug, "/pull-requests");
+        let url = pagination::append_query(&url, &datacenter::pull_request_params(filter)?)?;
+        self.fetch_paginated(url, page).await
+    }
+
+    async fn dc_get_pullrequest(&self, project_key: &str, repo_slug: &str, pr_id: &str) -> Result<serde_json::Value> {
+        self.get_json(&self.dc_repo_url(project_key, repo_slug, &format!("/pull-requests/{pr_id}"))).await
+    }
+
+    async fn dc_get_pullrequest_diff(&self, project_key: &str, repo_slug: &str, pr_id: &str) -> Result<String> {
+        let url = self.dc_repo_url(project_key, repo_slug, &format!("/pull-requests/{pr_id}.diff"));
+        self.get_text(&url).await
+    }
+
+    /// Comments are read from the activity stream, so `limit` counts activities rather than comments.
+    async fn dc_list_pullrequest_comments(&self, project_key: &str, repo_slug: &str, pr_id: &str, page: &PageOptions) -> Result<serde_json::Value> {
+        let url = self.dc_repo_url(project_key, repo_slug, &format!("/pull-requests/{pr_id}/activities"));
+        let mut result = self.fetch_paginated(url, page).await?;
+        let comments = datacenter::comments_from_activities(serde_json::from_value(result["values"].take())?);
+        result["size"] = comments.len().into();
+        result["values"] = comments.into();
+        Ok(result)
+    }
+
+    /// Declining and merging require the pull request's current `version`, so it is fetched first.
+    async fn dc_change_state(&self, project_key: &str, repo_slug: &str, pr_id: &str, action: &str, body: Option<serde_json::Value>) -> Result<serde_json::Value> {
+        let pr = self.dc_get_pullrequest(project_key, repo_slug, pr_id).await?;
+        let version = pr["version"]
+            .as_u64()
+            .ok_or_else(|| BitbucketError::Decode(format!("pull request {pr_id} has no `version`, which is needed to {action} it")))?;
+        let url = self.dc_repo_url(project_key, repo_slug, &format!("/pull-requests/{pr_id}/{action}"));
+        let url = pagination::append_query(&url, &[("version", version.to_string())])?;
+        self.send_json(Method::POST, &url, body.as_ref()).await
+    }
+
+    pub async fn get_user(&self, fields: Option<&str>) -> Result<serde_json::Value> {
         let url = format!("{}/user", self.base_url);
-        let req = self.client.get(&url);
-        let resp = self.apply_auth(req).send().await?;
-        if !resp.status().is_success() {
-            let status = resp.status();
-            let text = resp.text().await.unwrap_or_default();
-            return Err(anyhow!("Bitbucket API error: {} - {}", status, text));
+        self.get_json(&fields::with_fields(&url, fields, false)?).await
+    }
+
+    /// Checks the credentials and returns the authenticated user with the scopes granted to the token.
+    /// On Data Center the user comes from the `X-AUSERNAME` header and scopes are never reported.
+    pub async fn check_auth(&self) -> Result<AuthCheck> {
+        if self.backend == Backend::DataCenter {
+            let url = format!("{}{}/application-properties", self.base_url, datacenter::API_PATH);
+            let resp = self.execute(Method::GET, &url, None).await?;
+            let user = resp.headers().get("x-ausername").and_then(|v| v.to_str().ok()).map(|name| serde_json::json!({ "name": name }));
+            return Ok(AuthCheck { user: user.unwrap_or_default(), scopes: None });
         }
-        Ok(resp.json().await?)
+        let resp = self.execute(Method::GET, &format!("{}/user", self.base_url), None).await?;
+        let scopes = resp.headers().get(diagnose::SCOPES_HEADER).and_then(|v| v.to_str().ok()).map(diagnose::parse_scopes_header);
+        Ok(AuthCheck { user: resp.json().await?, scopes })
     }
 
-    pub async fn list_workspaces(&self) -> Result<serde_json::Value> {
+    pub async fn list_workspaces(&self, page: &PageOptions, fields: Option<&str>) -> Result<serde_json::Value> {
         let url = format!("{}/workspaces", self.base_url);
-        self.fetch_paginated(url).await
+        let url = fields::with_fields(&url, fields, true)?;
+        self.fetch_paginated(url, page).await
     }
 
-    pub async fn list_repositories(&self, workspace: &str) -> Result<serde_json::Value> {
+    pub async fn list_repositories(&self, workspace: &str, filter: &Filter, page: &PageOptions, fields: Option<&str>) -> Result<serde_json::Value> {
+        if self.backend == Backend::DataCenter {
+            return self.dc_list_repositories(workspace, filter, page).await;
+        }
         let url = format!("{}/repositories/{}", self.base_url, workspace);
-        self.fetch_paginated(url).await
+        let url = pagination::append_query(&url, &filter.query_pairs(StateFilter::Unsupported("repositories"))?)?;
+        let url = fields::with_fields(&url, fields, true)?;
+        self.fetch_paginated(url, page).await
     }
 
-    pub async fn list_pullrequests(&self, workspace: &str, repo_slug: &str) -> Result<serde_json::Value> {
+    pub async fn list_repositories_typed(&self, workspace: &str, filter: &Filter, page: &PageOptions) -> Result<Page<Repository>> {
+        decode(self.list_repositories(workspace, filter, page, None).await?)
+    }
+
+    pub async fn list_pullrequests(&self, workspace: &str, repo_slug: &str, filter: &Filter, page: &PageOptions, fields: Option<&str>) -> Result<serde_json::Value> {
+        if self.backend == Backend::DataCenter {
+            return self.dc_list_pullrequests(workspace, repo_slug, filter, page).await;
+        }
         let url = format!("{}/repositories/{}/{}/pullrequests", self.base_url, workspace, repo_slug);
-        self.fetch_paginated(url).await
+        let url = pagination::append_query(&url, &filter.query_pairs(StateFilter::Param)?)?;
+        let url = fields::with_fields(&url, fields, true)?;
+        self.fetch_paginated(url, page).await
+    }
+
+    pub async fn list_pullrequests_typed(&self, workspace: &str, repo_slug: &str, filter: &Filter, page: &PageOptions) -> Result<Page<PullRequest>> {
+        decode(self.list_pullrequests(workspace, repo_slug, filter, page, None).await?)
     }
 
-    pub async fn list_issues(&self, workspace: &str, repo_slug: &str) -> Result<serde_json::Value> {
+    pub async fn list_issues(&self, workspace: &str, repo_slug: &str, filter: &Filter, page: &PageOptions, fields: Option<&str>) -> Result<serde_json::Value> {
         let url = format!("{}/repositories/{}/{}/issues", self.base_url, workspace, repo_slug);
-        self.fetch_paginated(url).await
+        let url = pagination::append_query(&url, &filter.query_pairs(StateFilter::Field("state"))?)?;
+        let url = fields::with_fields(&url, fields, true)?;
+        self.fetch_paginated(url, page).await
     }
 
-    pub async fn get_workspace(&self, workspace: &str) -> Result<serde_json::Value> {
+    pub async fn list_issues_typed(&self, workspace: &str, repo_slug: &str, filter: &Filter, page: &PageOptions) -> Result<Page<Issue>> {
+        decode(self.list_issues(workspace, repo_slug, filter, page, None).await?)
+    }
+
+    pub async fn get_workspace(&self, workspace: &str, fields: Option<&str>) -> Result<serde_json::Value> {
         let url = format!("{}/workspaces/{}", self.base_url, workspace);
-        let req = self.client.get(&url);
-        let resp = self.apply_auth(req).send().await?;
-        if !resp.status().is_success() {
-            let status = resp.status();
-            let text = resp.text().await.unwrap_or_default();
-            return Err(anyhow!("Bitbucket API error: {} - {}", status, text));
-        }
-        Ok(resp.json().await?)
+        self.get_json(&fields::with_fields(&url, fields, false)?).await
     }
-    pub async fn get_repository(&self, workspace: &str, repo_slug: &str) -> Result<serde_json::Value> {
+    pub async fn get_repository(&self, workspace: &str, repo_slug: &str, fields: Option<&str>) -> Result<serde_json::Value> {
         let url = format!("{}/repositories/{}/{}", self.base_url, workspace, repo_slug);
-        let req = self.client.get(&url);
-        let resp = self.apply_auth(req).send().await?;
-        if !resp.status().is_success() {
-            let status = resp.status();
-            let text = resp.text().await.unwrap_or_default();
-            return Err(anyhow!("Bitbucket API error: {} - {}", status, text));
-        }
-        Ok(resp.json().await?)
+        self.get_json(&fields::with_fields(&url, fields, false)?).await
+    }
+    pub async fn get_repository_typed(&self, workspace: &str, repo_slug: &str) -> Result<Repository> {
+        decode(self.get_repository(workspace, repo_slug, None).await?)
     }
-    pub async fn list_branches(&self, workspace: &str, repo_slug: &str) -> Result<serde_json::Value> {
+    pub async fn list_branches(&self, workspace: &str, repo_slug: &str, filter: &Filter, page: &PageOptions, fields: Option<&str>) -> Result<serde_json::Value> {
         let url = format!("{}/repositories/{}/{}/refs/branches", self.base_url, workspace, repo_slug);
-        self.fetch_paginated(url).await
+        let url = pagination::append_query(&url, &filter.query_pairs(StateFilter::Unsupported("branches"))?)?;
+        let url = fields::with_fields(&url, fields, true)?;
+        self.fetch_paginated(url, page).await
     }
-    pub async fn list_tags(&self, workspace: &str, repo_slug: &str) -> Result<serde_json::Value> {
+    pub async fn list_tags(&self, workspace: &str, repo_slug: &str, page: &PageOptions, fields: Option<&str>) -> Result<serde_json::Value> {
         let url = format!("{}/repositories/{}/{}/refs/tags", self.base_url, workspace, repo_slug);
-        self.fetch_paginated(url).await
+        let url = fields::with_fields(&url, fields, true)?;
+        self.fetch_paginated(url, page).await
     }
-    pub async fn list_commits(&self, workspace: &str, repo_slug: &str) -> Result<serde_json::Value> {
+    pub async fn list_commits(&self, workspace: &str, repo_slug: &str, filter: &Filter, page: &PageOptions, fields: Option<&str>) -> Result<serde_json::Value> {
         let url = format!("{}/repositories/{}/{}/commits", self.base_url, workspace, repo_slug);
-        self.fetch_paginated(url).await
+        let url = pagination::append_query(&url, &filter.query_pairs(StateFilter::Unsupported("commits"))?)?;
+        let url = fields::with_fields(&url, fields, true)?;
+        self.fetch_paginated(url, page).await
     }
-    pub async fn list_pipelines(&self, workspace: &str, repo_slug: &str) -> Result<serde_json::Value> {
+    pub async fn list_commits_typed(&self, workspace: &str, repo_slug: &str, filter: &Filter, page: &PageOptions) -> Result<Page<Commit>> {
+        decode(self.list_commits(workspace, repo_slug, filter, page, None).await?)
+    }
+    pub async fn list_pipelines(&self, workspace: &str, repo_slug: &str, filter: &Filter, page: &PageOptions, fields: Option<&str>) -> Result<serde_json::Value> {
         let url = format!("{}/repositories/{}/{}/pipelines/", self.base_url, workspace, repo_slug);
-        self.fetch_paginated(url).await
+        let url = pagination::append_query(&url, &filter.query_pairs(StateFilter::Field("state.name"))?)?;
+        let url = fields::with_fields(&url, fields, true)?;
+        self.fetch_paginated(url, page).await
+    }
+    pub async fn list_pipelines_typed(&self, workspace: &str, repo_slug: &str, filter: &Filter, page: &PageOptions) -> Result<Page<Pipeline>> {
+        decode(self.list_pipelines(workspace, repo_slug, filter, page, None).await?)
     }
-    pub async fn list_deployments(&self, workspace: &str, repo_slug: &str) -> Result<serde_json::Value> {
+    pub async fn list_deployments(&self, workspace: &str, repo_slug: &str, page: &PageOptions, fields: Option<&str>) -> Result<serde_json::Value> {
         let url = format!("{}/repositories/{}/{}/deployments/", self.base_url, workspace, repo_slug);
-        self.fetch_paginated(url).await
+        let url = fields::with_fields(&url, fields, true)?;
+        self.fetch_paginated(url, page).await
     }
-    pub async fn list_downloads(&self, workspace: &str, repo_slug: &str) -> Result<serde_json::Value> {
+    pub async fn list_downloads(&self, workspace: &str, repo_slug: &str, page: &PageOptions, fields: Option<&str>) -> Result<serde_json::Value> {
         let url = format!("{}/repositories/{}/{}/downloads", self.base_url, workspace, repo_slug);
-        self.fetch_paginated(url).await
+        let url = fields::with_fields(&url, fields, true)?;
+        self.fetch_paginated(url, page).await
     }
-    pub async fn list_webhooks(&self, workspace: &str, repo_slug: &str) -> Result<serde_json::Value> {
+    pub async fn list_webhooks(&self, workspace: &str, repo_slug: &str, page: &PageOptions, fields: Option<&str>) -> Result<serde_json::Value> {
         let url = format!("{}/repositories/{}/{}/hooks", self.base_url, workspace, repo_slug);
-        self.fetch_paginated(url).await
+        let url = fields::with_fields(&url, fields, true)?;
+        self.fetch_paginated(url, page).await
     }
-    pub async fn list_snippets(&self, workspace: &str) -> Result<serde_json::Value> {
+    pub async fn list_snippets(&self, workspace: &str, page: &PageOptions, fields: Option<&str>) -> Result<serde_json::Value> {
         let url = format!("{}/snippets/{}", self.base_url, workspace);
-        self.fetch_paginated(url).await
+        let url = fields::with_fields(&url, fields, true)?;
+        self.fetch_paginated(url, page).await
     }
-    pub async fn list_projects(&self, workspace: &str) -> Result<serde_json::Value> {
+    pub async fn list_projects(&self, workspace: &str, page: &PageOptions, fields: Option<&str>) -> Result<serde_json::Value> {
         let url = format!("{}/workspaces/{}/projects", self.base_url, workspace);
-        self.fetch_paginated(url).await
+        let url = fields::with_fields(&url, fields, true)?;
+        self.fetch_paginated(url, page).await
     }
-    pub async fn list_branch_restrictions(&self, workspace: &str, repo_slug: &str) -> Result<serde_json::Value> {
+    pub async fn list_branch_restrictions(&self, workspace: &str, repo_slug: &str, page: &PageOptions, fields: Option<&str>) -> Result<serde_json::Value> {
         let url = format!("{}/repositories/{}/{}/branch-restrictions", self.base_url, workspace, repo_slug);
-        self.fetch_paginated(url).await
+        let url = fields::with_fields(&url, fields, true)?;
+        self.fetch_paginated(url, page).await
     }
-    pub async fn list_commit_statuses(&self, workspace: &str, repo_slug: &str, commit: &str) -> Result<serde_json::Value> {
+    pub async fn list_commit_statuses(&self, workspace: &str, repo_slug: &str, commit: &str, page: &PageOptions, fields: Option<&str>) -> Result<serde_json::Value> {
         let url = format!("{}/repositories/{}/{}/commit/{}/statuses", self.base_url, workspace, repo_slug, commit);
-        self.fetch_paginated(url).await
+        let url = fields::with_fields(&url, fields, true)?;
+        self.fetch_paginated(url, page).await
     }
-    pub async fn list_users(&self, workspace: &str) -> Result<serde_json::Value> {
+    pub async fn list_users(&self, workspace: &str, page: &PageOptions, fields: Option<&str>) -> Result<serde_json::Value> {
         let url = format!("{}/workspaces/{}/members", self.base_url, workspace);
-        self.fetch_paginated(url).await
+        let url = fields::with_fields(&url, fields, true)?;
+        self.fetch_paginated(url, page).await
+    }
+    /// Resolves a user given as a UUID (`{...}`), an Atlassian account id, or the nickname or
+    /// display name of a workspace member. Only the last form needs a lookup.
+    pub async fn resolve_account(&self, workspace: &str, user: &str) -> Result<AccountRef> {
+        let user = user.trim();
+        if user.starts_with('{') && user.ends_with('}') {
+            return Ok(AccountRef { uuid: Some(user.to_string()), account_id: None });
+        }
+        let is_account_id = user.contains(':') || (user.len() == 24 && user.chars().all(|c| c.is_ascii_hexdigit()));
+        if is_account_id {
+            return Ok(AccountRef { uuid: None, account_id: Some(user.to_string()) });
+        }
+        let mut members = pin!(self.paginate_stream::<WorkspaceMembership>(format!("/workspaces/{workspace}/members"), &PageOptions::default()));
+        while let Some(member) = members.try_next().await? {
+            let account = member.user;
+            let matches = [&account.nickname, &account.display_name]
+                .into_iter()
+                .flatten()
+                .any(|name| name.eq_ignore_ascii_case(user));
+            if matches {
+                return Ok(AccountRef { uuid: account.uuid, account_id: account.account_id });
+            }
+        }
+        Err(BitbucketError::Validation {
+            message: format!("No member of workspace '{workspace}' is called '{user}'. Use a UUID, account id or nickname."),
+            fields: [("reviewers".to_string(), vec![format!("unknown user '{user}'")])].into(),
+        })
     }
 
     /// Create a repository in a workspace
     pub async fn create_repository(&self, workspace: &str, repo_slug: &str, body: serde_json::Value) -> Result<serde_json::Value> {
         let url = format!("{}/repositories/{}/{}", self.base_url, workspace, repo_slug);
-        let req = self.client.post(&url).json(&body);
-        let resp = self.apply_auth(req).send().await?;
-        if !resp.status().is_success() {
-            let status = resp.status();
-            let text = resp.text().await.unwrap_or_default();
-            return Err(anyhow!("Bitbucket API error: {} - {}", status, text));
-        }
-        Ok(resp.json().await?)
+        self.send_json(Method::POST, &url, Some(&body)).await
+    }
+
+    pub async fn create_repository_typed(&self, workspace: &str, repo_slug: &str, body: &CreateRepository) -> Result<Repository> {
+        decode(self.create_repository(workspace, repo_slug, encode(body)?).await?)
     }
 
     /// Update a repository in a workspace
     pub async fn update_repository(&self, workspace: &str, repo_slug: &str, body: serde_json::Value) -> Result<serde_json::Value> {
         let url = format!("{}/repositories/{}/{}", self.base_url, workspace, repo_slug);
-        let req = self.client.put(&url).json(&body);
-        let resp = self.apply_auth(req).send().await?;
-        if !resp.status().is_success() {
-            let status = resp.status();
-            let text = resp.text().await.unwrap_or_default();
-            return Err(anyhow!("Bitbucket API error: {} - {}", status, text));
-        }
-        Ok(resp.json().await?)
+        self.send_json(Method::PUT, &url, Some(&body)).await
     }
 
     /// Delete a repository in a workspace
     pub async fn delete_repository(&self, workspace: &str, repo_slug: &str) -> Result<serde_json::Value> {
         let url = format!("{}/repositories/{}/{}", self.base_url, workspace, repo_slug);
-        let req = self.client.delete(&url);
-        let resp = self.apply_auth(req).send().await?;
-        if !resp.status().is_success() {
-            let status = resp.status();
-            let text = resp.text().await.unwrap_or_default();
-            return Err(anyhow!("Bitbucket API error: {} - {}", status, text));
-        }
-        if resp.status() == reqwest::StatusCode::NO_CONTENT {
-            return Ok(serde_json::json!({}));
-        }
-        Ok(resp.json().await?)
+        self.send_json(Method::DELETE, &url, None).await
     }
     // --- Branches ---
+    /// Get a branch in a repository
+    pub async fn get_branch(&self, workspace: &str, repo_slug: &str, branch: &str) -> Result<serde_json::Value> {
+        let url = format!("{}/repositories/{}/{}/refs/branches/{}", self.base_url, workspace, repo_slug, branch);
+        self.get_json(&url).await
+    }
     /// Create a branch in a repository
     pub async fn create_branch(&self, workspace: &str, repo_slug: &str, body: serde_json::Value) -> Result<serde_json::Value> {
         let url = format!("{}/repositories/{}/{}/refs/branches", self.base_url, workspace, repo_slug);
-        let req = self.client.post(&url).json(&body);
-        let resp = self.apply_auth(req).send().await?;
-        if !resp.status().is_success() {
-            let status = resp.status();
-            let text = resp.text().await.unwrap_or_default();
-            return Err(anyhow!("Bitbucket API error: {} - {}", status, text));
-        }
-        Ok(resp.json().await?)
+        self.send_json(Method::POST, &url, Some(&body)).await
     }
     /// Delete a branch in a repository
     pub async fn delete_branch(&self, workspace: &str, repo_slug: &str, branch: &str) -> Result<serde_json::Value> {
         let url = format!("{}/repositories/{}/{}/refs/branches/{}", self.base_url, workspace, repo_slug, branch);
-        let req = self.client.delete(&url);
-        let resp = self.apply_auth(req).send().await?;
-        if !resp.status().is_success() {
-            let status = resp.status();
-            let text = resp.text().await.unwrap_or_default();
-            return Err(anyhow!("Bitbucket API error: {} - {}", status, text));
-        }
-        if resp.status() == reqwest::StatusCode::NO_CONTENT {
-            return Ok(serde_json::json!({}));
-        }
-        Ok(resp.json().await?)
+        self.send_json(Method::DELETE, &url, None).await
     }
     // --- Branching Model ---
-    pub async fn get_branching_model(&self, workspace: &str, repo_slug: &str) -> Result<serde_json::Value> {
+    pub async fn get_branching_model(&self, workspace: &str, repo_slug: &str, fields: Option<&str>) -> Result<serde_json::Value> {
         let url = format!("{}/repositories/{}/{}/branching-model", self.base_url, workspace, repo_slug);
-        let req = self.client.get(&url);
-        let resp = self.apply_auth(req).send().await?;
-        if !resp.status().is_success() {
-            let status = resp.status();
-            let text = resp.text().await.unwrap_or_default();
-            return Err(anyhow!("Bitbucket API error: {} - {}", status, text));
-        }
-        Ok(resp.json().await?)
+        self.get_json(&fields::with_fields(&url, fields, false)?).await
     }
     pub async fn update_branching_model(&self, workspace: &str, repo_slug: &str, body: serde_json::Value) -> Result<serde_json::Value> {
         let url = format!("{}/repositories/{}/{}/branching-model", self.base_url, workspace, repo_slug);
-        let req = self.client.put(&url).json(&body);
-        let resp = self.apply_auth(req).send().await?;
-        if !resp.status().is_success() {
-            let status = resp.status();
-            let text = resp.text().await.unwrap_or_default();
-            return Err(anyhow!("Bitbucket API error: {} - {}", status, text));
-        }
-        Ok(resp.json().await?)
+        self.send_json(Method::PUT, &url, Some(&body)).await
     }
     // --- Commit Statuses ---
     pub async fn create_commit_status(&self, workspace: &str, repo_slug: &str, commit: &str, body: serde_json::Value) -> Result<serde_json::Value> {
         let url = format!("{}/repositories/{}/{}/commit/{}/statuses/build", self.base_url, workspace, repo_slug, commit);
-        let req = self.client.post(&url).json(&body);
-        let resp = self.apply_auth(req).send().await?;
-        if !resp.status().is_success() {
-            let status = resp.status();
-            let text = resp.text().await.unwrap_or_default();
-            return Err(anyhow!("Bitbucket API error: {} - {}", status, text));
-        }
-        Ok(resp.json().await?)
+        self.send_json(Method::POST, &url, Some(&body)).await
     }
     // --- Commits ---
-    pub async fn get_commit(&self, workspace: &str, repo_slug: &str, commit: &str) -> Result<serde_json::Value> {
+    pub async fn get_commit(&self, workspace: &str, repo_slug: &str, commit: &str, fields: Option<&str>) -> Result<serde_json::Value> {
         let url = format!("{}/repositories/{}/{}/commit/{}", self.base_url, workspace, repo_slug, commit);
-        let req = self.client.get(&url);
-        let resp = self.apply_auth(req).send().await?;
-        if !resp.status().is_success() {
-            let status = resp.status();
-            let text = resp.text().await.unwrap_or_default();
-            return Err(anyhow!("Bitbucket API error: {} - {}", status, text));
-        }
-        Ok(resp.json().await?)
+        let url = fields::with_fields(&url, fields, false)?;
+        // Not disk-cached: participants and approvals change after the commit is made.
+        self.get_json(&url).await
+    }
+    pub async fn get_commit_typed(&self, workspace: &str, repo_slug: &str, commit: &str) -> Result<Commit> {
+        decode(self.get_commit(workspace, repo_slug, commit, None).await?)
     }
     // --- Deployments ---
     pub async fn create_deployment(&self, workspace: &str, repo_slug: &str, body: serde_json::Value) -> Result<serde_json::Value> {
         let url = format!("{}/repositories/{}/{}/deployments/", self.base_url, workspace, repo_slug);
-        let req = self.client.post(&url).json(&body);
-        let resp = self.apply_auth(req).send().await?;
-        if !resp.status().is_success() {
-            let status = resp.status();
-            let text = resp.text().await.unwrap_or_default();
-            return Err(anyhow!("Bitbucket API error: {} - {}", status, text));
-        }
-        Ok(resp.json().await?)
+        self.send_json(Method::POST, &url, Some(&body)).await
     }
     // --- Issue Tracker ---
     pub async fn create_issue(&self, workspace: &str, repo_slug: &str, body: serde_json::Value) -> Result<serde_json::Value> {
         let url = format!("{}/repositories/{}/{}/issues", self.base_url, workspace, repo_slug);
-        let req = self.client.post(&url).json(&body);
-        let resp = self.apply_auth(req).send().await?;
-        if !resp.status().is_success() {
-            let status = resp.status();
-            let text = resp.text().await.unwrap_or_default();
-            return Err(anyhow!("Bitbucket API error: {} - {}", status, text));
-        }
-        Ok(resp.json().await?)
+        self.send_json(Method::POST, &url, Some(&body)).await
+    }
+    pub async fn create_issue_typed(&self, workspace: &str, repo_slug: &str, body: &CreateIssue) -> Result<Issue> {
+        decode(self.create_issue(workspace, repo_slug, encode(body)?).await?)
     }
     pub async fn update_issue(&self, workspace: &str, repo_slug: &str, issue_id: &str, body: serde_json::Value) -> Result<serde_json::Value> {
         let url = format!("{}/repositories/{}/{}/issues/{}", self.base_url, workspace, repo_slug, issue_id);
-        let req = self.client.put(&url).json(&body);
-        let resp = self.apply_auth(req).send().await?;
-        if !resp.status().is_success() {
-            let status = resp.status();
-            let text = resp.text().await.unwrap_or_default();
-            return Err(anyhow!("Bitbucket API error: {} - {}", status, text));
-        }
-        Ok(resp.json().await?)
+        self.send_json(Method::PUT, &url, Some(&body)).await
+    }
+    pub async fn update_issue_typed(&self, workspace: &str, repo_slug: &str, issue_id: &str, body: &UpdateIssue) -> Result<Issue> {
+        decode(self.update_issue(workspace, repo_slug, issue_id, encode(body)?).await?)
     }
     pub async fn delete_issue(&self, workspace: &str, repo_slug: &str, issue_id: &str) -> Result<serde_json::Value> {
         let url = format!("{}/repositories/{}/{}/issues/{}", self.base_url, workspace, repo_slug, issue_id);
-        let req = self.client.delete(&url);
-        let resp = self.apply_auth(req).send().await?;
-        if !resp.status().is_success() {
-            let status = resp.status();
-            let text = resp.text().await.unwrap_or_default();
-            return Err(anyhow!("Bitbucket API error: {} - {}", status, text));
-        }
-        if resp.status() == reqwest::StatusCode::NO_CONTENT {
-            return Ok(serde_json::json!({}));
-        }
-        Ok(resp.json().await?)
+        self.send_json(Method::DELETE, &url, None).await
     }
     // --- Pipelines ---
     pub async fn trigger_pipeline(&self, workspace: &str, repo_slug: &str, body: serde_json::Value) -> Result<serde_json::Value> {
         let url = format!("{}/repositories/{}/{}/pipelines/", self.base_url, workspace, repo_slug);
-        let req = self.client.post(&url).json(&body);
-        let resp = self.apply_auth(req).send().await?;
-        if !resp.status().is_success() {
-            let status = resp.status();
-            let text = resp.text().await.unwrap_or_default();
-            return Err(anyhow!("Bitbucket API error: {} - {}", status, text));
-        }
-        Ok(resp.json().await?)
+        self.send_json(Method::POST, &url, Some(&body)).await
+    }
+    pub async fn trigger_pipeline_typed(&self, workspace: &str, repo_slug: &str, body: &TriggerPipeline) -> Result<Pipeline> {
+        decode(self.trigger_pipeline(workspace, repo_slug, encode(body)?).await?)
     }
     // --- Projects ---
     pub async fn create_project(&self, workspace: &str, body: serde_json::Value) -> Result<serde_json::Value> {
         let url = format!("{}/workspaces/{}/projects", self.base_url, workspace);
-        let req = self.client.post(&url).json(&body);
-        let resp = self.apply_auth(req).send().await?;
-        if !resp.status().is_success() {
-            let status = resp.status();
-            let text = resp.text().await.unwrap_or_default();
-            return Err(anyhow!("Bitbucket API error: {} - {}", status, text));
-        }
-        Ok(resp.json().await?)
+        self.send_json(Method::POST, &url, Some(&body)).await
     }
     pub async fn update_project(&self, workspace: &str, project_key: &str, body: serde_json::Value) -> Result<serde_json::Value> {
         let url = format!("{}/workspaces/{}/projects/{}", self.base_url, workspace, project_key);
-        let req = self.client.put(&url).json(&body);
-        let resp = self.apply_auth(req).send().await?;
-        if !resp.status().is_success() {
-            let status = resp.status();
-            let text = resp.text().await.unwrap_or_default();
-            return Err(anyhow!("Bitbucket API error: {} - {}", status, text));
-        }
-        Ok(resp.json().await?)
+        self.send_json(Method::PUT, &url, Some(&body)).await
     }
     pub async fn delete_project(&self, workspace: &str, project_key: &str) -> Result<serde_json::Value> {
         let url = format!("{}/workspaces/{}/projects/{}", self.base_url, workspace, project_key);
-        let req = self.client.delete(&url);
-        let resp = self.apply_auth(req).send().await?;
-        if !resp.status().is_success() {
-            let status = resp.status();
-            let text = resp.text().await.unwrap_or_default();
-            return Err(anyhow!("Bitbucket API error: {} - {}", status, text));
-        }
-        if resp.status() == reqwest::StatusCode::NO_CONTENT {
-            return Ok(serde_json::json!({}));
-        }
-        Ok(resp.json().await?)
+        self.send_json(Method::DELETE, &url, None).await
     }
     // --- Snippets ---
     pub async fn create_snippet(&self, workspace: &str, body: serde_json::Value) -> Result<serde_json::Value> {
         let url = format!("{}/snippets/{}", self.base_url, workspace);
-        let req = self.client.post(&url).json(&body);
-        let resp = self.apply_auth(req).send().await?;
-        if !resp.status().is_success() {
-            let status = resp.status();
-            let text = resp.text().await.unwrap_or_default();
-            return Err(anyhow!("Bitbucket API error: {} - {}", status, text));
-        }
-        Ok(resp.json().await?)
+        self.send_json(Method::POST, &url, Some(&body)).await
     }
     pub async fn delete_snippet(&self, workspace: &str, snippet_id: &str) -> Result<serde_json::Value> {
         let url = format!("{}/snippets/{}/{}", self.base_url, workspace, snippet_id);
-        let req = self.client.delete(&url);
-        let resp = self.apply_auth(req).send().await?;
-        if !resp.status().is_success() {
-            let status = resp.status();
-            let text = resp.text().await.unwrap_or_default();
-            return Err(anyhow!("Bitbucket API error: {} - {}", status, text));
-        }
-        if resp.status() == reqwest::StatusCode::NO_CONTENT {
-            return Ok(serde_json::json!({}));
-        }
-        Ok(resp.json().await?)
+        self.send_json(Method::DELETE, &url, None).await
     }
     // --- Source ---
-    pub async fn get_file_source(&self, workspace: &str, repo_slug: &str, commit: &str, path: &str) -> Result<serde_json::Value> {
+    /// Raw file content at `commit` (a directory yields Bitbucket's JSON listing as text). Takes no
+    /// `fields`, since the content is returned as-is.
+    pub async fn get_file_source(&self, workspace: &str, repo_slug: &str, commit: &str, path: &str) -> Result<String> {
         let url = format!("{}/repositories/{}/{}/src/{}/{}", self.base_url, workspace, repo_slug, commit, path);
-        let req = self.client.get(&url);
-        let resp = self.apply_auth(req).send().await?;
-        Ok(resp.json().await?)
+        let Some(disk_cache) = self.disk_cache_for(commit) else {
+            return self.get_text(&url).await;
+        };
+        Ok(String::from_utf8_lossy(&self.get_immutable(disk_cache, &url).await?).into_owned())
     }
     // Add more methods for each Bitbucket REST API group here
 }
 
-// MCP tool trait implementation and registration will be added here
+/// Encodes a typed request body for the raw client methods.
+fn encode<T: Serialize>(body: &T) -> Result<serde_json::Value> {
+    Ok(serde_json::to_value(body)?)
+}
+
+/// Encodes a typed tool parameter as the JSON body expected by the raw client methods.
+fn tool_body<T: Serialize>(body: T) -> Result<serde_json::Value, McpError> {
+    serde_json::to_value(body).map_err(|e| McpError::invalid_params(e.to_string(), None))
+}
+
+/// Decodes a raw response into a typed model.
+fn decode<T: DeserializeOwned>(value: serde_json::Value) -> Result<T> {
+    Ok(serde_json::from_value(value)?)
+}
+
+/// Entries `read_audit_log` returns when no limit is given.
+const DEFAULT_AUDIT_ENTRIES: usize = 50;
+
+/// Per-call argument of mutating tools that renders the request instead of sending it.
+pub const DRY_RUN_PARAM: &str = "dry_run";
 
+fn add_tool_param(tool: &mut Tool, name: &str, schema: serde_json::Value) {
+    let mut input_schema = (*tool.input_schema).clone();
+    if let Some(properties) = input_schema.get_mut("properties").and_then(serde_json::Value::as_object_mut) {
+        properties.insert(name.to_string(), schema);
+    }
+    tool.input_schema = Arc::new(input_schema);
+}
+
+/// MCP server exposing Bitbucket operations as tools.
+///
+/// Holds a single `BitbucketClient` built at startup so that every tool call
+/// shares the same connection pool and configuration.
 #[derive(Clone)]
-pub struct BitbucketTool;
+pub struct BitbucketTool {
+    client: Arc<BitbucketClient>,
+    policy: Arc<ToolPolicy>,
+    /// Set when destructive tools need a confirmation token.
+    confirmations: Option<Arc<Confirmations>>,
+}
+
+impl BitbucketTool {
+    /// Creates the tool set with the toolsets and allow/deny lists from the client's config.
+    /// A read-only client also hides the mutating tools.
+    pub fn new(client: BitbucketClient) -> Self {
+        let policy = ToolPolicy { read_only: client.read_only, ..ToolPolicy::from_config(&client.config) };
+        let confirmations = client.config.confirm_destructive.then(|| Arc::new(Confirmations::default()));
+        Self { client: Arc::new(client), policy: Arc::new(policy), confirmations }
+    }
+
+    pub fn with_policy(mut self, policy: ToolPolicy) -> Self {
+        self.policy = Arc::new(policy);
+        self
+    }
+
+    /// Enables (`Some`) or disables the two-step confirmation of destructive tools.
+    pub fn with_confirmations(mut self, confirmations: Option<Confirmations>) -> Self {
+        self.confirmations = confirmations.map(Arc::new);
+        self
+    }
+
+    /// Tools enabled by the policy, as advertised by `list_tools`. Mutating tools also advertise
+    /// the `dry_run` argument and, with confirmations enabled, destructive ones `confirmation_token`.
+    pub fn tools(&self) -> Vec<Tool> {
+        Self::tool_box()
+            .list()
+            .into_iter()
+            .filter(|tool| self.policy.allows(&tool.name))
+            .map(|mut tool| {
+                if policy::is_mutating(&tool.name) {
+                    add_tool_param(&mut tool, DRY_RUN_PARAM, serde_json::json!({
+                        "type": "boolean",
+                        "description": "Return the HTTP request this call would send (credentials redacted) without sending it",
+                    }));
+                }
+                if self.confirmations.is_some() && confirm::is_destructive(&tool.name) {
+                    add_tool_param(&mut tool, confirm::TOKEN_PARAM, serde_json::json!({
+                        "type": "string",
+                        "description": "Token returned by the first call. Omit it to get a preview and a token; pass it to perform the action",
+                    }));
+                }
+                tool
+            })
+            .collect()
+    }
+
+    /// Checks the credentials and reports which enabled toolsets the token's scopes allow.
+    pub async fn auth_report(&self) -> serde_json::Value {
+        let tools: Vec<String> = self.tools().into_iter().map(|t| t.name.to_string()).collect();
+        let auth_method = self.client.credentials.as_ref().map(Credentials::method);
+        match self.client.check_auth().await {
+            Ok(check) => {
+                let toolsets = diagnose::toolset_report(&tools, check.scopes.as_deref());
+                let mut report = serde_json::json!({
+                    "authenticated": true,
+                    "backend": self.client.backend,
+                    "auth_method": auth_method,
+                    "user": diagnose::user_summary(&check.user),
+                    "scopes": check.scopes,
+                    "warnings": diagnose::warnings(&toolsets),
+                    "toolsets": toolsets,
+                });
+                if check.scopes.is_none() {
+                    report["note"] = "Bitbucket did not report the token's scopes, so tools that need missing permissions will only fail when called (403).".into();
+                }
+                report
+            }
+            Err(e) => serde_json::json!({
+                "authenticated": false,
+                "backend": self.client.backend,
+                "auth_method": auth_method,
+                "error": e.to_string(),
+            }),
+        }
+    }
+
+    /// Startup self-check: logs who the server is authenticated as and warns about tools whose
+    /// scopes the token lacks. Failures are only logged, so an unreachable Bitbucket does not
+    /// prevent the server from starting.
+    pub async fn log_auth_check(&self) {
+        let report = self.auth_report().await;
+        if report["authenticated"] != true {
+            tracing::warn!("Bitbucket authentication check failed: {}", report["error"].as_str().unwrap_or_default());
+            return;
+        }
+        tracing::info!("authenticated to Bitbucket as {}", report["user"]);
+        for warning in report["warnings"].as_array().into_iter().flatten() {
+            tracing::warn!("{}", warning.as_str().unwrap_or_default());
+        }
+    }
+
+    /// Removes the per-call `dry_run` argument of a mutating tool and returns its value.
+    fn take_dry_run(tool: &str, arguments: &mut Option<serde_json::Map<String, serde_json::Value>>) -> bool {
+        let requested = arguments.as_mut().and_then(|args| args.remove(DRY_RUN_PARAM));
+        policy::is_mutating(tool) && requested.and_then(|v| v.as_bool()).unwrap_or(false)
+    }
+
+    /// Applies the confirmation protocol to a call of `tool`. Returns the result to send back
+    /// instead of running the tool (a preview with a fresh token, or a rejected token), or `None`
+    /// when the call may proceed; the token argument is removed from `arguments` in that case.
+    pub async fn confirm(&self, tool: &str, arguments: &mut Option<serde_json::Map<String, serde_json::Value>>) -> Result<Option<CallToolResult>, McpError> {
+        let Some(confirmations) = self.confirmations.as_ref().filter(|_| confirm::is_destructive(tool)) else {
+            return Ok(None);
+        };
+        let args = arguments.get_or_insert_with(Default::default);
+        let token = args.remove(confirm::TOKEN_PARAM);
+        if let Some(token) = token {
+            return match confirmations.redeem(token.as_str().unwrap_or_default(), tool, args) {
+                Ok(()) => Ok(None),
+                Err(e) => {
+                    tracing::warn!("{tool} confirmation rejected: {e}");
+                    Ok(Some(CallToolResult::error(vec![Content::text(e.to_string())])))
+                }
+            };
+        }
+        let target = match self.preview(tool, args).await {
+            Ok(target) => target,
+            Err(e) => return Ok(Some(CallToolResult::error(vec![Content::text(e.to_string())]))),
+        };
+        let token = confirmations.issue(tool, args);
+        let preview = serde_json::json!({
+            "confirmation_required": true,
+            "action": tool,
+            "arguments": args,
+            "target": target,
+            "confirmation_token": token,
+            "expires_in_secs": confirmations.ttl().as_secs(),
+            "message": format!("Nothing was changed yet. Call {tool} again with the same arguments and {}=\"{token}\" to proceed.", confirm::TOKEN_PARAM),
+        });
+        Ok(Some(CallToolResult::success(vec![redact::json_content(preview)?])))
+    }
+
+    /// Runs a tool call through the policy, dry-run and confirmation checks.
+    async fn dispatch(&self, request: CallToolRequestParam, context: RequestContext<RoleServer>) -> Result<CallToolResult, McpError> {
+        if let Err(e) = self.policy.check(&request.name) {
+            tracing::warn!("{} rejected: {e}", request.name);
+            return Ok(CallToolResult::error(vec![Content::text(e.to_string())]));
+        }
+        let mut request = request;
+        if Self::take_dry_run(&request.name, &mut request.arguments) && !self.client.dry_run {
+            // Nothing is sent in a dry run, so there is nothing to confirm either.
+            let dry_run = Self { client: Arc::new((*self.client).clone().with_dry_run(true)), ..self.clone() };
+            return Self::tool_box().call(ToolCallContext::new(&dry_run, request, context)).await;
+        }
+        if !self.client.dry_run
+            && let Some(result) = self.confirm(&request.name, &mut request.arguments).await?
+        {
+            return Ok(result);
+        }
+        let name = request.name.clone();
+        audit::with_tool(&name, Self::tool_box().call(ToolCallContext::new(self, request, context))).await
+    }
+
+    /// Fetches the object a destructive tool would affect, so it can be shown before confirming.
+    async fn preview(&self, tool: &str, args: &serde_json::Map<String, serde_json::Value>) -> Result<serde_json::Value> {
+        let arg = |name: &str| -> Result<String> {
+            match args.get(name) {
+                Some(serde_json::Value::String(s)) => Ok(s.clone()),
+                Some(serde_json::Value::Number(n)) => Ok(n.to_string()),
+                _ => Err(BitbucketError::Validation { message: format!("missing required argument '{name}'"), fields: Default::default() }),
+            }
+        };
+        let client = &self.client;
+        let workspace = arg("workspace")?;
+        let base = &client.base_url;
+        match tool {
+            "delete_repository" => client.get_repository(&workspace, &arg("repo_slug")?, None).await,
+            "delete_branch" => client.get_branch(&workspace, &arg("repo_slug")?, &arg("branch")?).await,
+            "decline_pullrequest" | "merge_pullrequest" => client.get_pullrequest(&workspace, &arg("repo_slug")?, &arg("pr_id")?, None).await,
+            "delete_issue" => client.get_json(&format!("{base}/repositories/{workspace}/{}/issues/{}", arg("repo_slug")?, arg("issue_id")?)).await,
+            "delete_project" => client.get_json(&format!("{base}/workspaces/{workspace}/projects/{}", arg("project_key")?)).await,
+            "delete_snippet" => client.get_json(&format!("{base}/snippets/{workspace}/{}", arg("snippet_id")?)).await,
+            _ => Ok(serde_json::Value::Null),
+        }
+    }
+}
 
 #[tool(tool_box)]
 impl BitbucketTool {
-    #[tool(description = "Create a bitbucket pull request")]
-    pub async fn create_pullrequest(&self, #[tool(param)] workspace: String, #[tool(param)] repo_slug: String, #[tool(param)] body: serde_json::Value) -> Result<CallToolResult, McpError> {
-        let client = match super::bitbucket::BitbucketClient::from_env() {
-            Ok(c) => c,
-            Err(e) => {
-                tracing::error!("BitbucketClient::from_env error: {e}");
-                return Ok(CallToolResult::error(vec![Content::text(e.to_string())]))
-            },
+    #[tool(description = "Create a bitbucket pull request. Both branches are checked before the pull request is submitted.")]
+    #[allow(clippy::too_many_arguments)]
+    pub async fn create_pullrequest(
+        &self,
+        #[tool(param)] workspace: String,
+        #[tool(param)] repo_slug: String,
+        #[tool(param)] title: String,
+        #[tool(param)]
+        #[schemars(description = "Branch with the changes to merge")]
+        source_branch: String,
+        #[tool(param)]
+        #[schemars(description = "Branch to merge into. Defaults to the repository's main branch")]
+        destination_branch: Option<String>,
+        #[tool(param)]
+        #[schemars(description = "Pull request description (Markdown)")]
+        description: Option<String>,
+        #[tool(param)]
+        #[schemars(description = "Reviewers as UUIDs ({...}), Atlassian account ids or workspace member nicknames")]
+        reviewers: Option<Vec<String>>,
+        #[tool(param)]
+        #[schemars(description = "Delete the source branch once the pull request is merged")]
+        close_source_branch: Option<bool>,
+        #[tool(param)]
+        #[schemars(description = "Open the pull request as a draft")]
+        draft: Option<bool>,
+    ) -> Result<CallToolResult, McpError> {
+        let result = async {
+            let mut resolved = Vec::new();
+            for reviewer in reviewers.unwrap_or_default() {
+                resolved.push(self.client.resolve_account(&workspace, &reviewer).await?);
+            }
+            let body = CreatePullRequest {
+                title,
+                source: BranchEndpoint::branch(source_branch),
+                destination: destination_branch.map(BranchEndpoint::branch),
+                description,
+                reviewers: resolved,
+                close_source_branch,
+                draft,
+            };
+            self.client.create_pullrequest_checked(&workspace, &repo_slug, &body).await
         };
-        match client.create_pullrequest(&workspace, &repo_slug, body).await {
-            Ok(val) => Ok(CallToolResult::success(vec![Content::json(val)?])),
+        match result.await {
+            Ok(val) => Ok(CallToolResult::success(vec![redact::json_content(val)?])),
             Err(e) => {
                 tracing::error!("create_pullrequest error: {e}");
                 Ok(CallToolResult::error(vec![Content::text(e.to_string())]))
@@ -724,16 +1433,9 @@ impl BitbucketTool {
     }
 
     #[tool(description = "Get bitbucket pull request details")]
-    pub async fn get_pullrequest(&self, #[tool(param)] workspace: String, #[tool(param)] repo_slug: String, #[tool(param)] pr_id: String) -> Result<CallToolResult, McpError> {
-        let client = match super::bitbucket::BitbucketClient::from_env() {
-            Ok(c) => c,
-            Err(e) => {
-                tracing::error!("BitbucketClient::from_env error: {e}");
-                return Ok(CallToolResult::error(vec![Content::text(e.to_string())]))
-            },
-        };
-        match client.get_pullrequest(&workspace, &repo_slug, &pr_id).await {
-            Ok(val) => Ok(CallToolResult::success(vec![Content::json(val)?])),
+    pub async fn get_pullrequest(&self, #[tool(param)] workspace: String, #[tool(param)] repo_slug: String, #[tool(param)] pr_id: String, #[tool(param)] #[serde(flatten)] projection: Projection) -> Result<CallToolResult, McpError> {
+        match self.client.get_pullrequest(&workspace, &repo_slug, &pr_id, projection.fields()).await {
+            Ok(val) => Ok(CallToolResult::success(vec![redact::json_content(fields::present(val, Resource::PullRequest, projection.fields()))?])),
             Err(e) => {
                 tracing::error!("get_pullrequest error: {e}");
                 Ok(CallToolResult::error(vec![Content::text(e.to_string())]))
@@ -742,16 +1444,10 @@ impl BitbucketTool {
     }
 
     #[tool(description = "Update a bitbucket pull request")]
-    pub async fn update_pullrequest(&self, #[tool(param)] workspace: String, #[tool(param)] repo_slug: String, #[tool(param)] pr_id: String, #[tool(param)] body: serde_json::Value) -> Result<CallToolResult, McpError> {
-        let client = match super::bitbucket::BitbucketClient::from_env() {
-            Ok(c) => c,
-            Err(e) => {
-                tracing::error!("BitbucketClient::from_env error: {e}");
-                return Ok(CallToolResult::error(vec![Content::text(e.to_string())]))
-            },
-        };
-        match client.update_pullrequest(&workspace, &repo_slug, &pr_id, body).await {
-            Ok(val) => Ok(CallToolResult::success(vec![Content::json(val)?])),
+    pub async fn update_pullrequest(&self, #[tool(param)] workspace: String, #[tool(param)] repo_slug: String, #[tool(param)] pr_id: String, #[tool(param)] body: UpdatePullRequest) -> Result<CallToolResult, McpError> {
+        let body = tool_body(body)?;
+        match self.client.update_pullrequest(&workspace, &repo_slug, &pr_id, body).await {
+            Ok(val) => Ok(CallToolResult::success(vec![redact::json_content(val)?])),
             Err(e) => {
                 tracing::error!("update_pullrequest error: {e}");
                 Ok(CallToolResult::error(vec![Content::text(e.to_string())]))
@@ -761,15 +1457,8 @@ impl BitbucketTool {
 
     #[tool(description = "Approve a bitbucket pull request")]
     pub async fn approve_pullrequest(&self, #[tool(param)] workspace: String, #[tool(param)] repo_slug: String, #[tool(param)] pr_id: String) -> Result<CallToolResult, McpError> {
-        let client = match super::bitbucket::BitbucketClient::from_env() {
-            Ok(c) => c,
-            Err(e) => {
-                tracing::error!("BitbucketClient::from_env error: {e}");
-                return Ok(CallToolResult::error(vec![Content::text(e.to_string())]))
-            },
-        };
-        match client.approve_pullrequest(&workspace, &repo_slug, &pr_id).await {
-            Ok(val) => Ok(CallToolResult::success(vec![Content::json(val)?])),
+        match self.client.approve_pullrequest(&workspace, &repo_slug, &pr_id).await {
+            Ok(val) => Ok(CallToolResult::success(vec![redact::json_content(val)?])),
             Err(e) => {
                 tracing::error!("approve_pullrequest error: {e}");
                 Ok(CallToolResult::error(vec![Content::text(e.to_string())]))
@@ -779,15 +1468,8 @@ impl BitbucketTool {
 
     #[tool(description = "Unapprove a bitbucket pull request")]
     pub async fn unapprove_pullrequest(&self, #[tool(param)] workspace: String, #[tool(param)] repo_slug: String, #[tool(param)] pr_id: String) -> Result<CallToolResult, McpError> {
-        let client = match super::bitbucket::BitbucketClient::from_env() {
-            Ok(c) => c,
-            Err(e) => {
-                tracing::error!("BitbucketClient::from_env error: {e}");
-                return Ok(CallToolResult::error(vec![Content::text(e.to_string())]))
-            },
-        };
-        match client.unapprove_pullrequest(&workspace, &repo_slug, &pr_id).await {
-            Ok(val) => Ok(CallToolResult::success(vec![Content::json(val)?])),
+        match self.client.unapprove_pullrequest(&workspace, &repo_slug, &pr_id).await {
+            Ok(val) => Ok(CallToolResult::success(vec![redact::json_content(val)?])),
             Err(e) => {
                 tracing::error!("unapprove_pullrequest error: {e}");
                 Ok(CallToolResult::error(vec![Content::text(e.to_string())]))
@@ -797,15 +1479,8 @@ impl BitbucketTool {
 
     #[tool(description = "Decline a bitbucket pull request")]
     pub async fn decline_pullrequest(&self, #[tool(param)] workspace: String, #[tool(param)] repo_slug: String, #[tool(param)] pr_id: String) -> Result<CallToolResult, McpError> {
-        let client = match super::bitbucket::BitbucketClient::from_env() {
-            Ok(c) => c,
-            Err(e) => {
-                tracing::error!("BitbucketClient::from_env error: {e}");
-                return Ok(CallToolResult::error(vec![Content::text(e.to_string())]))
-            },
-        };
-        match client.decline_pullrequest(&workspace, &repo_slug, &pr_id).await {
-            Ok(val) => Ok(CallToolResult::success(vec![Content::json(val)?])),
+        match self.client.decline_pullrequest(&workspace, &repo_slug, &pr_id).await {
+            Ok(val) => Ok(CallToolResult::success(vec![redact::json_content(val)?])),
             Err(e) => {
                 tracing::error!("decline_pullrequest error: {e}");
                 Ok(CallToolResult::error(vec![Content::text(e.to_string())]))
@@ -814,16 +1489,10 @@ impl BitbucketTool {
     }
 
     #[tool(description = "Merge a bitbucket pull request")]
-    pub async fn merge_pullrequest(&self, #[tool(param)] workspace: String, #[tool(param)] repo_slug: String, #[tool(param)] pr_id: String, #[tool(param)] body: Option<serde_json::Value>) -> Result<CallToolResult, McpError> {
-        let client = match super::bitbucket::BitbucketClient::from_env() {
-            Ok(c) => c,
-            Err(e) => {
-                tracing::error!("BitbucketClient::from_env error: {e}");
-                return Ok(CallToolResult::error(vec![Content::text(e.to_string())]))
-            },
-        };
-        match client.merge_pullrequest(&workspace, &repo_slug, &pr_id, body).await {
-            Ok(val) => Ok(CallToolResult::success(vec![Content::json(val)?])),
+    pub async fn merge_pullrequest(&self, #[tool(param)] workspace: String, #[tool(param)] repo_slug: String, #[tool(param)] pr_id: String, #[tool(param)] body: Option<MergePullRequest>) -> Result<CallToolResult, McpError> {
+        let body = body.map(tool_body).transpose()?;
+        match self.client.merge_pullrequest(&workspace, &repo_slug, &pr_id, body).await {
+            Ok(val) => Ok(CallToolResult::success(vec![redact::json_content(val)?])),
             Err(e) => {
                 tracing::error!("merge_pullrequest error: {e}");
                 Ok(CallToolResult::error(vec![Content::text(e.to_string())]))
@@ -832,16 +1501,9 @@ impl BitbucketTool {
     }
 
     #[tool(description = "List bitbucket pull request comments")]
-    pub async fn list_pullrequest_comments(&self, #[tool(param)] workspace: String, #[tool(param)] repo_slug: String, #[tool(param)] pr_id: String) -> Result<CallToolResult, McpError> {
-        let client = match super::bitbucket::BitbucketClient::from_env() {
-            Ok(c) => c,
-            Err(e) => {
-                tracing::error!("BitbucketClient::from_env error: {e}");
-                return Ok(CallToolResult::error(vec![Content::text(e.to_string())]))
-            },
-        };
-        match client.list_pullrequest_comments(&workspace, &repo_slug, &pr_id).await {
-            Ok(val) => Ok(CallToolResult::success(vec![Content::json(val)?])),
+    pub async fn list_pullrequest_comments(&self, #[tool(param)] workspace: String, #[tool(param)] repo_slug: String, #[tool(param)] pr_id: String, #[tool(param)] #[serde(flatten)] page: PageOptions, #[tool(param)] #[serde(flatten)] projection: Projection) -> Result<CallToolResult, McpError> {
+        match self.client.list_pullrequest_comments(&workspace, &repo_slug, &pr_id, &page, projection.fields()).await {
+            Ok(val) => Ok(CallToolResult::success(vec![redact::json_content(fields::present(val, Resource::Comment, projection.fields()))?])),
             Err(e) => {
                 tracing::error!("list_pullrequest_comments error: {e}");
                 Ok(CallToolResult::error(vec![Content::text(e.to_string())]))
@@ -855,15 +1517,8 @@ impl BitbucketTool {
             Ok(p) => p,
             Err(e) => return Ok(CallToolResult::error(vec![Content::text(e)])),
         };
-        let client = match super::bitbucket::BitbucketClient::from_env() {
-            Ok(c) => c,
-            Err(e) => {
-                tracing::error!("BitbucketClient::from_env error: {e}");
-                return Ok(CallToolResult::error(vec![Content::text(e.to_string())]))
-            },
-        };
-        match client.add_pullrequest_comment(&workspace, &repo_slug, &pr_id, payload).await {
-            Ok(val) => Ok(CallToolResult::success(vec![Content::json(val)?])),
+        match self.client.add_pullrequest_comment(&workspace, &repo_slug, &pr_id, payload).await {
+            Ok(val) => Ok(CallToolResult::success(vec![redact::json_content(val)?])),
             Err(e) => {
                 tracing::error!("add_pullrequest_comment error: {e}");
                 Ok(CallToolResult::error(vec![Content::text(e.to_string())]))
@@ -872,16 +1527,9 @@ impl BitbucketTool {
     }
 
     #[tool(description = "List bitbucket pull request activity")]
-    pub async fn list_pullrequest_activity(&self, #[tool(param)] workspace: String, #[tool(param)] repo_slug: String, #[tool(param)] pr_id: String) -> Result<CallToolResult, McpError> {
-        let client = match super::bitbucket::BitbucketClient::from_env() {
-            Ok(c) => c,
-            Err(e) => {
-                tracing::error!("BitbucketClient::from_env error: {e}");
-                return Ok(CallToolResult::error(vec![Content::text(e.to_string())]))
-            },
-        };
-        match client.list_pullrequest_activity(&workspace, &repo_slug, &pr_id).await {
-            Ok(val) => Ok(CallToolResult::success(vec![Content::json(val)?])),
+    pub async fn list_pullrequest_activity(&self, #[tool(param)] workspace: String, #[tool(param)] repo_slug: String, #[tool(param)] pr_id: String, #[tool(param)] #[serde(flatten)] page: PageOptions, #[tool(param)] #[serde(flatten)] projection: Projection) -> Result<CallToolResult, McpError> {
+        match self.client.list_pullrequest_activity(&workspace, &repo_slug, &pr_id, &page, projection.fields()).await {
+            Ok(val) => Ok(CallToolResult::success(vec![redact::json_content(fields::present(val, Resource::Other, projection.fields()))?])),
             Err(e) => {
                 tracing::error!("list_pullrequest_activity error: {e}");
                 Ok(CallToolResult::error(vec![Content::text(e.to_string())]))
@@ -891,15 +1539,8 @@ impl BitbucketTool {
 
     #[tool(description = "Get bitbucket pull request diff")]
     pub async fn get_pullrequest_diff(&self, #[tool(param)] workspace: String, #[tool(param)] repo_slug: String, #[tool(param)] pr_id: String) -> Result<CallToolResult, McpError> {
-        let client = match super::bitbucket::BitbucketClient::from_env() {
-            Ok(c) => c,
-            Err(e) => {
-                tracing::error!("BitbucketClient::from_env error: {e}");
-                return Ok(CallToolResult::error(vec![Content::text(e.to_string())]))
-            },
-        };
-        match client.get_pullrequest_diff(&workspace, &repo_slug, &pr_id).await {
-            Ok(val) => Ok(CallToolResult::success(vec![Content::text(val)])),
+        match self.client.get_pullrequest_diff(&workspace, &repo_slug, &pr_id).await {
+            Ok(val) => Ok(CallToolResult::success(vec![Content::text(redact::redact_credentials(&val))])),
             Err(e) => {
                 tracing::error!("get_pullrequest_diff error: {e}");
                 Ok(CallToolResult::error(vec![Content::text(e.to_string())]))
@@ -908,16 +1549,9 @@ impl BitbucketTool {
     }
 
     #[tool(description = "Get bitbucket pull request commits")]
-    pub async fn list_pullrequest_commits(&self, #[tool(param)] workspace: String, #[tool(param)] repo_slug: String, #[tool(param)] pr_id: String) -> Result<CallToolResult, McpError> {
-        let client = match super::bitbucket::BitbucketClient::from_env() {
-            Ok(c) => c,
-            Err(e) => {
-                tracing::error!("BitbucketClient::from_env error: {e}");
-                return Ok(CallToolResult::error(vec![Content::text(e.to_string())]))
-            },
-        };
-        match client.list_pullrequest_commits(&workspace, &repo_slug, &pr_id).await {
-            Ok(val) => Ok(CallToolResult::success(vec![Content::json(val)?])),
+    pub async fn list_pullrequest_commits(&self, #[tool(param)] workspace: String, #[tool(param)] repo_slug: String, #[tool(param)] pr_id: String, #[tool(param)] #[serde(flatten)] page: PageOptions, #[tool(param)] #[serde(flatten)] projection: Projection) -> Result<CallToolResult, McpError> {
+        match self.client.list_pullrequest_commits(&workspace, &repo_slug, &pr_id, &page, projection.fields()).await {
+            Ok(val) => Ok(CallToolResult::success(vec![redact::json_content(fields::present(val, Resource::Commit, projection.fields()))?])),
             Err(e) => {
                 tracing::error!("list_pullrequest_commits error: {e}");
                 Ok(CallToolResult::error(vec![Content::text(e.to_string())]))
@@ -926,16 +1560,9 @@ impl BitbucketTool {
     }
 
     #[tool(description = "List bitbucket pull request tasks")]
-    pub async fn list_pullrequest_tasks(&self, #[tool(param)] workspace: String, #[tool(param)] repo_slug: String, #[tool(param)] pr_id: String) -> Result<CallToolResult, McpError> {
-        let client = match super::bitbucket::BitbucketClient::from_env() {
-            Ok(c) => c,
-            Err(e) => {
-                tracing::error!("BitbucketClient::from_env error: {e}");
-                return Ok(CallToolResult::error(vec![Content::text(e.to_string())]))
-            },
-        };
-        match client.list_pullrequest_tasks(&workspace, &repo_slug, &pr_id).await {
-            Ok(val) => Ok(CallToolResult::success(vec![Content::json(val)?])),
+    pub async fn list_pullrequest_tasks(&self, #[tool(param)] workspace: String, #[tool(param)] repo_slug: String, #[tool(param)] pr_id: String, #[tool(param)] #[serde(flatten)] page: PageOptions, #[tool(param)] #[serde(flatten)] projection: Projection) -> Result<CallToolResult, McpError> {
+        match self.client.list_pullrequest_tasks(&workspace, &repo_slug, &pr_id, &page, projection.fields()).await {
+            Ok(val) => Ok(CallToolResult::success(vec![redact::json_content(fields::present(val, Resource::Other, projection.fields()))?])),
             Err(e) => {
                 tracing::error!("list_pullrequest_tasks error: {e}");
                 Ok(CallToolResult::error(vec![Content::text(e.to_string())]))
@@ -945,15 +1572,8 @@ impl BitbucketTool {
 
     #[tool(description = "Add a bitbucket pull request task")]
     pub async fn add_pullrequest_task(&self, #[tool(param)] workspace: String, #[tool(param)] repo_slug: String, #[tool(param)] pr_id: String, #[tool(param)] body: serde_json::Value) -> Result<CallToolResult, McpError> {
-        let client = match super::bitbucket::BitbucketClient::from_env() {
-            Ok(c) => c,
-            Err(e) => {
-                tracing::error!("BitbucketClient::from_env error: {e}");
-                return Ok(CallToolResult::error(vec![Content::text(e.to_string())]))
-            },
-        };
-        match client.add_pullrequest_task(&workspace, &repo_slug, &pr_id, body).await {
-            Ok(val) => Ok(CallToolResult::success(vec![Content::json(val)?])),
+        match self.client.add_pullrequest_task(&workspace, &repo_slug, &pr_id, body).await {
+            Ok(val) => Ok(CallToolResult::success(vec![redact::json_content(val)?])),
             Err(e) => {
                 tracing::error!("add_pullrequest_task error: {e}");
                 Ok(CallToolResult::error(vec![Content::text(e.to_string())]))
@@ -962,33 +1582,56 @@ impl BitbucketTool {
     }
 
     #[tool(description = "Get bitbucket pull request diffstat")]
-    pub async fn get_pullrequest_diffstat(&self, #[tool(param)] workspace: String, #[tool(param)] repo_slug: String, #[tool(param)] pr_id: String) -> Result<CallToolResult, McpError> {
-        let client = match super::bitbucket::BitbucketClient::from_env() {
-            Ok(c) => c,
+    pub async fn get_pullrequest_diffstat(&self, #[tool(param)] workspace: String, #[tool(param)] repo_slug: String, #[tool(param)] pr_id: String, #[tool(param)] #[serde(flatten)] projection: Projection) -> Result<CallToolResult, McpError> {
+        match self.client.get_pullrequest_diffstat(&workspace, &repo_slug, &pr_id, projection.fields()).await {
+            Ok(val) => Ok(CallToolResult::success(vec![redact::json_content(fields::present(val, Resource::Other, projection.fields()))?])),
             Err(e) => {
-                tracing::error!("BitbucketClient::from_env error: {e}");
-                return Ok(CallToolResult::error(vec![Content::text(e.to_string())]))
+                tracing::error!("get_pullrequest_diffstat error: {e}");
+                Ok(CallToolResult::error(vec![Content::text(e.to_string())]))
             },
+        }
+    }
+    #[tool(description = "Get the remaining Bitbucket API budget: client-side rate limiter state and the last X-RateLimit headers returned by Bitbucket")]
+    pub async fn get_rate_limit_status(&self) -> Result<CallToolResult, McpError> {
+        Ok(CallToolResult::success(vec![redact::json_content(self.client.rate_limit_status())?]))
+    }
+
+    #[tool(description = "Show the effective client configuration: base URL, backend, proxy, timeouts, user agent, CA certificates, auth method, retry and rate limit settings (secrets are never included)")]
+    pub async fn get_diagnostics(&self) -> Result<CallToolResult, McpError> {
+        Ok(CallToolResult::success(vec![redact::json_content(self.client.diagnostics())?]))
+    }
+
+    #[tool(description = "Read the most recent entries of the audit log of mutating calls (create, update, delete, merge, ...), oldest first")]
+    pub async fn read_audit_log(
+        &self,
+        #[tool(param)]
+        #[schemars(description = "Maximum number of entries to return (default 50)")]
+        limit: Option<usize>,
+        #[tool(param)]
+        #[schemars(description = "Only return entries made by this tool, e.g. merge_pullrequest")]
+        tool: Option<String>,
+    ) -> Result<CallToolResult, McpError> {
+        let Some(audit) = self.client.audit_log() else {
+            return Ok(CallToolResult::error(vec![Content::text("The audit log is not enabled. Set BITBUCKET_MCP_AUDIT_LOG (or audit_log in the config file) to a file path.")]));
         };
-        match client.get_pullrequest_diffstat(&workspace, &repo_slug, &pr_id).await {
-            Ok(val) => Ok(CallToolResult::success(vec![Content::json(val)?])),
+        match audit.read_recent(limit.unwrap_or(DEFAULT_AUDIT_ENTRIES), tool.as_deref()) {
+            Ok(entries) => Ok(CallToolResult::success(vec![redact::json_content(serde_json::json!({ "path": audit.path(), "entries": entries }))?])),
             Err(e) => {
-                tracing::error!("get_pullrequest_diffstat error: {e}");
+                tracing::error!("read_audit_log error: {e}");
                 Ok(CallToolResult::error(vec![Content::text(e.to_string())]))
             },
         }
     }
+
+    #[tool(description = "Check the configured credentials: who they authenticate as, which scopes the token has, which toolsets will work and which tools will fail for lack of a scope")]
+    pub async fn diagnose_auth(&self) -> Result<CallToolResult, McpError> {
+        Ok(CallToolResult::success(vec![redact::json_content(self.auth_report().await)?]))
+    }
+
     #[tool(description = "Get bitbucket user info")]
-    pub async fn get_user(&self) -> Result<CallToolResult, McpError> {
-        let client = match super::bitbucket::BitbucketClient::from_env() {
-            Ok(c) => c,
-            Err(e) => {
-                tracing::error!("BitbucketClient::from_env error: {e}");
-                return Ok(CallToolResult::error(vec![Content::text(e.to_string())]))
-            },
-        };
-        match client.get_user().await {
-            Ok(val) => Ok(CallToolResult::success(vec![Content::json(val)?])),
+    pub async fn get_user(&self, #[tool(param)] #[serde(flatten)] projection: Projection) -> Result<CallToolResult, McpError> {
+        match self.client.get_user(projection.fields()).await {
+            Ok(val) => Ok(CallToolResult::success(vec![redact::json_content(fields::present(val, Resource::Other, projection.fields()))?])),
             Err(e) => {
                 tracing::error!("get_user error: {e}");
                 Ok(CallToolResult::error(vec![Content::text(e.to_string())]))
@@ -997,16 +1640,9 @@ impl BitbucketTool {
     }
 
     #[tool(description = "List bitbucket workspaces")]
-    pub async fn list_workspaces(&self) -> Result<CallToolResult, McpError> {
-        let client = match super::bitbucket::BitbucketClient::from_env() {
-            Ok(c) => c,
-            Err(e) => {
-                tracing::error!("BitbucketClient::from_env error: {e}");
-                return Ok(CallToolResult::error(vec![Content::text(e.to_string())]))
-            },
-        };
-        match client.list_workspaces().await {
-            Ok(val) => Ok(CallToolResult::success(vec![Content::json(val)?])),
+    pub async fn list_workspaces(&self, #[tool(param)] #[serde(flatten)] page: PageOptions, #[tool(param)] #[serde(flatten)] projection: Projection) -> Result<CallToolResult, McpError> {
+        match self.client.list_workspaces(&page, projection.fields()).await {
+            Ok(val) => Ok(CallToolResult::success(vec![redact::json_content(fields::present(val, Resource::Other, projection.fields()))?])),
             Err(e) => {
                 tracing::error!("list_workspaces error: {e}");
                 Ok(CallToolResult::error(vec![Content::text(e.to_string())]))
@@ -1015,16 +1651,9 @@ impl BitbucketTool {
     }
 
     #[tool(description = "List bitbucket repositories in a workspace")]
-    pub async fn list_repositories(&self, #[tool(param)] workspace: String) -> Result<CallToolResult, McpError> {
-        let client = match super::bitbucket::BitbucketClient::from_env() {
-            Ok(c) => c,
-            Err(e) => {
-                tracing::error!("BitbucketClient::from_env error: {e}");
-                return Ok(CallToolResult::error(vec![Content::text(e.to_string())]))
-            },
-        };
-        match client.list_repositories(&workspace).await {
-            Ok(val) => Ok(CallToolResult::success(vec![Content::json(val)?])),
+    pub async fn list_repositories(&self, #[tool(param)] workspace: String, #[tool(param)] #[serde(flatten)] filter: Filter, #[tool(param)] #[serde(flatten)] page: PageOptions, #[tool(param)] #[serde(flatten)] projection: Projection) -> Result<CallToolResult, McpError> {
+        match self.client.list_repositories(&workspace, &filter, &page, projection.fields()).await {
+            Ok(val) => Ok(CallToolResult::success(vec![redact::json_content(fields::present(val, Resource::Repository, projection.fields()))?])),
             Err(e) => {
                 tracing::error!("list_repositories error: {e}");
                 Ok(CallToolResult::error(vec![Content::text(e.to_string())]))
@@ -1033,20 +1662,13 @@ impl BitbucketTool {
     }
 
     #[tool(description = "List bitbucket pull requests for a repository")]
-    pub async fn list_pullrequests(&self, #[tool(param)] workspace: String, #[tool(param)] repo_slug: String) -> Result<CallToolResult, McpError> {
+    pub async fn list_pullrequests(&self, #[tool(param)] workspace: String, #[tool(param)] repo_slug: String, #[tool(param)] #[serde(flatten)] filter: Filter, #[tool(param)] #[serde(flatten)] page: PageOptions, #[tool(param)] #[serde(flatten)] projection: Projection) -> Result<CallToolResult, McpError> {
         tracing::info!("list_pullrequests called with workspace='{}', repo_slug='{}'", workspace, repo_slug);
-        let client = match super::bitbucket::BitbucketClient::from_env() {
-            Ok(c) => c,
-            Err(e) => {
-                tracing::error!("BitbucketClient::from_env error: {e}");
-                return Ok(CallToolResult::error(vec![Content::text(format!("env error: {e}"))]))
-            },
-        };
-        let result = client.list_pullrequests(&workspace, &repo_slug).await;
+        let result = self.client.list_pullrequests(&workspace, &repo_slug, &filter, &page, projection.fields()).await;
         match result {
             Ok(val) => {
                 tracing::info!("list_pullrequests API call succeeded");
-                Ok(CallToolResult::success(vec![Content::json(val)?]))
+                Ok(CallToolResult::success(vec![redact::json_content(fields::present(val, Resource::PullRequest, projection.fields()))?]))
             },
             Err(e) => {
                 tracing::error!("list_pullrequests API call error: {e}");
@@ -1056,16 +1678,9 @@ impl BitbucketTool {
     }
 
     #[tool(description = "List bitbucket issues for a repository")]
-    pub async fn list_issues(&self, #[tool(param)] workspace: String, #[tool(param)] repo_slug: String) -> Result<CallToolResult, McpError> {
-        let client = match super::bitbucket::BitbucketClient::from_env() {
-            Ok(c) => c,
-            Err(e) => {
-                tracing::error!("BitbucketClient::from_env error: {e}");
-                return Ok(CallToolResult::error(vec![Content::text(e.to_string())]))
-            },
-        };
-        match client.list_issues(&workspace, &repo_slug).await {
-            Ok(val) => Ok(CallToolResult::success(vec![Content::json(val)?])),
+    pub async fn list_issues(&self, #[tool(param)] workspace: String, #[tool(param)] repo_slug: String, #[tool(param)] #[serde(flatten)] filter: Filter, #[tool(param)] #[serde(flatten)] page: PageOptions, #[tool(param)] #[serde(flatten)] projection: Projection) -> Result<CallToolResult, McpError> {
+        match self.client.list_issues(&workspace, &repo_slug, &filter, &page, projection.fields()).await {
+            Ok(val) => Ok(CallToolResult::success(vec![redact::json_content(fields::present(val, Resource::Issue, projection.fields()))?])),
             Err(e) => {
                 tracing::error!("list_issues error: {e}");
                 Ok(CallToolResult::error(vec![Content::text(e.to_string())]))
@@ -1074,16 +1689,9 @@ impl BitbucketTool {
     }
 
     #[tool(description = "Get bitbucket workspace details")]
-    pub async fn get_workspace(&self, #[tool(param)] workspace: String) -> Result<CallToolResult, McpError> {
-        let client = match super::bitbucket::BitbucketClient::from_env() {
-            Ok(c) => c,
-            Err(e) => {
-                tracing::error!("BitbucketClient::from_env error: {e}");
-                return Ok(CallToolResult::error(vec![Content::text(e.to_string())]))
-            },
-        };
-        match client.get_workspace(&workspace).await {
-            Ok(val) => Ok(CallToolResult::success(vec![Content::json(val)?])),
+    pub async fn get_workspace(&self, #[tool(param)] workspace: String, #[tool(param)] #[serde(flatten)] projection: Projection) -> Result<CallToolResult, McpError> {
+        match self.client.get_workspace(&workspace, projection.fields()).await {
+            Ok(val) => Ok(CallToolResult::success(vec![redact::json_content(fields::present(val, Resource::Other, projection.fields()))?])),
             Err(e) => {
                 tracing::error!("get_workspace error: {e}");
                 Ok(CallToolResult::error(vec![Content::text(e.to_string())]))
@@ -1092,16 +1700,9 @@ impl BitbucketTool {
     }
 
     #[tool(description = "Get bitbucket repository details")]
-    pub async fn get_repository(&self, #[tool(param)] workspace: String, #[tool(param)] repo_slug: String) -> Result<CallToolResult, McpError> {
-        let client = match super::bitbucket::BitbucketClient::from_env() {
-            Ok(c) => c,
-            Err(e) => {
-                tracing::error!("BitbucketClient::from_env error: {e}");
-                return Ok(CallToolResult::error(vec![Content::text(e.to_string())]))
-            },
-        };
-        match client.get_repository(&workspace, &repo_slug).await {
-            Ok(val) => Ok(CallToolResult::success(vec![Content::json(val)?])),
+    pub async fn get_repository(&self, #[tool(param)] workspace: String, #[tool(param)] repo_slug: String, #[tool(param)] #[serde(flatten)] projection: Projection) -> Result<CallToolResult, McpError> {
+        match self.client.get_repository(&workspace, &repo_slug, projection.fields()).await {
+            Ok(val) => Ok(CallToolResult::success(vec![redact::json_content(fields::present(val, Resource::Repository, projection.fields()))?])),
             Err(e) => {
                 tracing::error!("get_repository error: {e}");
                 Ok(CallToolResult::error(vec![Content::text(e.to_string())]))
@@ -1110,16 +1711,9 @@ impl BitbucketTool {
     }
 
     #[tool(description = "List bitbucket branches for a repository")]
-    pub async fn list_branches(&self, #[tool(param)] workspace: String, #[tool(param)] repo_slug: String) -> Result<CallToolResult, McpError> {
-        let client = match super::bitbucket::BitbucketClient::from_env() {
-            Ok(c) => c,
-            Err(e) => {
-                tracing::error!("BitbucketClient::from_env error: {e}");
-                return Ok(CallToolResult::error(vec![Content::text(e.to_string())]))
-            },
-        };
-        match client.list_branches(&workspace, &repo_slug).await {
-            Ok(val) => Ok(CallToolResult::success(vec![Content::json(val)?])),
+    pub async fn list_branches(&self, #[tool(param)] workspace: String, #[tool(param)] repo_slug: String, #[tool(param)] #[serde(flatten)] filter: Filter, #[tool(param)] #[serde(flatten)] page: PageOptions, #[tool(param)] #[serde(flatten)] projection: Projection) -> Result<CallToolResult, McpError> {
+        match self.client.list_branches(&workspace, &repo_slug, &filter, &page, projection.fields()).await {
+            Ok(val) => Ok(CallToolResult::success(vec![redact::json_content(fields::present(val, Resource::Other, projection.fields()))?])),
             Err(e) => {
                 tracing::error!("list_branches error: {e}");
                 Ok(CallToolResult::error(vec![Content::text(e.to_string())]))
@@ -1128,16 +1722,9 @@ impl BitbucketTool {
     }
 
     #[tool(description = "List bitbucket tags for a repository")]
-    pub async fn list_tags(&self, #[tool(param)] workspace: String, #[tool(param)] repo_slug: String) -> Result<CallToolResult, McpError> {
-        let client = match super::bitbucket::BitbucketClient::from_env() {
-            Ok(c) => c,
-            Err(e) => {
-                tracing::error!("BitbucketClient::from_env error: {e}");
-                return Ok(CallToolResult::error(vec![Content::text(e.to_string())]))
-            },
-        };
-        match client.list_tags(&workspace, &repo_slug).await {
-            Ok(val) => Ok(CallToolResult::success(vec![Content::json(val)?])),
+    pub async fn list_tags(&self, #[tool(param)] workspace: String, #[tool(param)] repo_slug: String, #[tool(param)] #[serde(flatten)] page: PageOptions, #[tool(param)] #[serde(flatten)] projection: Projection) -> Result<CallToolResult, McpError> {
+        match self.client.list_tags(&workspace, &repo_slug, &page, projection.fields()).await {
+            Ok(val) => Ok(CallToolResult::success(vec![redact::json_content(fields::present(val, Resource::Other, projection.fields()))?])),
             Err(e) => {
                 tracing::error!("list_tags error: {e}");
                 Ok(CallToolResult::error(vec![Content::text(e.to_string())]))
@@ -1146,16 +1733,9 @@ impl BitbucketTool {
     }
 
     #[tool(description = "List bitbucket commits for a repository")]
-    pub async fn list_commits(&self, #[tool(param)] workspace: String, #[tool(param)] repo_slug: String) -> Result<CallToolResult, McpError> {
-        let client = match super::bitbucket::BitbucketClient::from_env() {
-            Ok(c) => c,
-            Err(e) => {
-                tracing::error!("BitbucketClient::from_env error: {e}");
-                return Ok(CallToolResult::error(vec![Content::text(e.to_string())]))
-            },
-        };
-        match client.list_commits(&workspace, &repo_slug).await {
-            Ok(val) => Ok(CallToolResult::success(vec![Content::json(val)?])),
+    pub async fn list_commits(&self, #[tool(param)] workspace: String, #[tool(param)] repo_slug: String, #[tool(param)] #[serde(flatten)] filter: Filter, #[tool(param)] #[serde(flatten)] page: PageOptions, #[tool(param)] #[serde(flatten)] projection: Projection) -> Result<CallToolResult, McpError> {
+        match self.client.list_commits(&workspace, &repo_slug, &filter, &page, projection.fields()).await {
+            Ok(val) => Ok(CallToolResult::success(vec![redact::json_content(fields::present(val, Resource::Commit, projection.fields()))?])),
             Err(e) => {
                 tracing::error!("list_commits error: {e}");
                 Ok(CallToolResult::error(vec![Content::text(e.to_string())]))
@@ -1164,16 +1744,9 @@ impl BitbucketTool {
     }
 
     #[tool(description = "List bitbucket pipelines for a repository")]
-    pub async fn list_pipelines(&self, #[tool(param)] workspace: String, #[tool(param)] repo_slug: String) -> Result<CallToolResult, McpError> {
-        let client = match super::bitbucket::BitbucketClient::from_env() {
-            Ok(c) => c,
-            Err(e) => {
-                tracing::error!("BitbucketClient::from_env error: {e}");
-                return Ok(CallToolResult::error(vec![Content::text(e.to_string())]))
-            },
-        };
-        match client.list_pipelines(&workspace, &repo_slug).await {
-            Ok(val) => Ok(CallToolResult::success(vec![Content::json(val)?])),
+    pub async fn list_pipelines(&self, #[tool(param)] workspace: String, #[tool(param)] repo_slug: String, #[tool(param)] #[serde(flatten)] filter: Filter, #[tool(param)] #[serde(flatten)] page: PageOptions, #[tool(param)] #[serde(flatten)] projection: Projection) -> Result<CallToolResult, McpError> {
+        match self.client.list_pipelines(&workspace, &repo_slug, &filter, &page, projection.fields()).await {
+            Ok(val) => Ok(CallToolResult::success(vec![redact::json_content(fields::present(val, Resource::Pipeline, projection.fields()))?])),
             Err(e) => {
                 tracing::error!("list_pipelines error: {e}");
                 Ok(CallToolResult::error(vec![Content::text(e.to_string())]))
@@ -1182,16 +1755,9 @@ impl BitbucketTool {
     }
 
     #[tool(description = "List bitbucket deployments for a repository")]
-    pub async fn list_deployments(&self, #[tool(param)] workspace: String, #[tool(param)] repo_slug: String) -> Result<CallToolResult, McpError> {
-        let client = match super::bitbucket::BitbucketClient::from_env() {
-            Ok(c) => c,
-            Err(e) => {
-                tracing::error!("BitbucketClient::from_env error: {e}");
-                return Ok(CallToolResult::error(vec![Content::text(e.to_string())]))
-            },
-        };
-        match client.list_deployments(&workspace, &repo_slug).await {
-            Ok(val) => Ok(CallToolResult::success(vec![Content::json(val)?])),
+    pub async fn list_deployments(&self, #[tool(param)] workspace: String, #[tool(param)] repo_slug: String, #[tool(param)] #[serde(flatten)] page: PageOptions, #[tool(param)] #[serde(flatten)] projection: Projection) -> Result<CallToolResult, McpError> {
+        match self.client.list_deployments(&workspace, &repo_slug, &page, projection.fields()).await {
+            Ok(val) => Ok(CallToolResult::success(vec![redact::json_content(fields::present(val, Resource::Other, projection.fields()))?])),
             Err(e) => {
                 tracing::error!("list_deployments error: {e}");
                 Ok(CallToolResult::error(vec![Content::text(e.to_string())]))
@@ -1200,16 +1766,9 @@ impl BitbucketTool {
     }
 
     #[tool(description = "List bitbucket downloads for a repository")]
-    pub async fn list_downloads(&self, #[tool(param)] workspace: String, #[tool(param)] repo_slug: String) -> Result<CallToolResult, McpError> {
-        let client = match super::bitbucket::BitbucketClient::from_env() {
-            Ok(c) => c,
-            Err(e) => {
-                tracing::error!("BitbucketClient::from_env error: {e}");
-                return Ok(CallToolResult::error(vec![Content::text(e.to_string())]))
-            },
-        };
-        match client.list_downloads(&workspace, &repo_slug).await {
-            Ok(val) => Ok(CallToolResult::success(vec![Content::json(val)?])),
+    pub async fn list_downloads(&self, #[tool(param)] workspace: String, #[tool(param)] repo_slug: String, #[tool(param)] #[serde(flatten)] page: PageOptions, #[tool(param)] #[serde(flatten)] projection: Projection) -> Result<CallToolResult, McpError> {
+        match self.client.list_downloads(&workspace, &repo_slug, &page, projection.fields()).await {
+            Ok(val) => Ok(CallToolResult::success(vec![redact::json_content(fields::present(val, Resource::Other, projection.fields()))?])),
             Err(e) => {
                 tracing::error!("list_downloads error: {e}");
                 Ok(CallToolResult::error(vec![Content::text(e.to_string())]))
@@ -1218,16 +1777,9 @@ impl BitbucketTool {
     }
 
     #[tool(description = "List bitbucket webhooks for a repository")]
-    pub async fn list_webhooks(&self, #[tool(param)] workspace: String, #[tool(param)] repo_slug: String) -> Result<CallToolResult, McpError> {
-        let client = match super::bitbucket::BitbucketClient::from_env() {
-            Ok(c) => c,
-            Err(e) => {
-                tracing::error!("BitbucketClient::from_env error: {e}");
-                return Ok(CallToolResult::error(vec![Content::text(e.to_string())]))
-            },
-        };
-        match client.list_webhooks(&workspace, &repo_slug).await {
-            Ok(val) => Ok(CallToolResult::success(vec![Content::json(val)?])),
+    pub async fn list_webhooks(&self, #[tool(param)] workspace: String, #[tool(param)] repo_slug: String, #[tool(param)] #[serde(flatten)] page: PageOptions, #[tool(param)] #[serde(flatten)] projection: Projection) -> Result<CallToolResult, McpError> {
+        match self.client.list_webhooks(&workspace, &repo_slug, &page, projection.fields()).await {
+            Ok(val) => Ok(CallToolResult::success(vec![redact::json_content(fields::present(val, Resource::Other, projection.fields()))?])),
             Err(e) => {
                 tracing::error!("list_webhooks error: {e}");
                 Ok(CallToolResult::error(vec![Content::text(e.to_string())]))
@@ -1236,16 +1788,9 @@ impl BitbucketTool {
     }
 
     #[tool(description = "List bitbucket snippets for a workspace")]
-    pub async fn list_snippets(&self, #[tool(param)] workspace: String) -> Result<CallToolResult, McpError> {
-        let client = match super::bitbucket::BitbucketClient::from_env() {
-            Ok(c) => c,
-            Err(e) => {
-                tracing::error!("BitbucketClient::from_env error: {e}");
-                return Ok(CallToolResult::error(vec![Content::text(e.to_string())]))
-            },
-        };
-        match client.list_snippets(&workspace).await {
-            Ok(val) => Ok(CallToolResult::success(vec![Content::json(val)?])),
+    pub async fn list_snippets(&self, #[tool(param)] workspace: String, #[tool(param)] #[serde(flatten)] page: PageOptions, #[tool(param)] #[serde(flatten)] projection: Projection) -> Result<CallToolResult, McpError> {
+        match self.client.list_snippets(&workspace, &page, projection.fields()).await {
+            Ok(val) => Ok(CallToolResult::success(vec![redact::json_content(fields::present(val, Resource::Other, projection.fields()))?])),
             Err(e) => {
                 tracing::error!("list_snippets error: {e}");
                 Ok(CallToolResult::error(vec![Content::text(e.to_string())]))
@@ -1254,16 +1799,9 @@ impl BitbucketTool {
     }
 
     #[tool(description = "List bitbucket projects for a workspace")]
-    pub async fn list_projects(&self, #[tool(param)] workspace: String) -> Result<CallToolResult, McpError> {
-        let client = match super::bitbucket::BitbucketClient::from_env() {
-            Ok(c) => c,
-            Err(e) => {
-                tracing::error!("BitbucketClient::from_env error: {e}");
-                return Ok(CallToolResult::error(vec![Content::text(e.to_string())]))
-            },
-        };
-        match client.list_projects(&workspace).await {
-            Ok(val) => Ok(CallToolResult::success(vec![Content::json(val)?])),
+    pub async fn list_projects(&self, #[tool(param)] workspace: String, #[tool(param)] #[serde(flatten)] page: PageOptions, #[tool(param)] #[serde(flatten)] projection: Projection) -> Result<CallToolResult, McpError> {
+        match self.client.list_projects(&workspace, &page, projection.fields()).await {
+            Ok(val) => Ok(CallToolResult::success(vec![redact::json_content(fields::present(val, Resource::Other, projection.fields()))?])),
             Err(e) => {
                 tracing::error!("list_projects error: {e}");
                 Ok(CallToolResult::error(vec![Content::text(e.to_string())]))
@@ -1272,16 +1810,9 @@ impl BitbucketTool {
     }
 
     #[tool(description = "List bitbucket branch restrictions for a repository")]
-    pub async fn list_branch_restrictions(&self, #[tool(param)] workspace: String, #[tool(param)] repo_slug: String) -> Result<CallToolResult, McpError> {
-        let client = match super::bitbucket::BitbucketClient::from_env() {
-            Ok(c) => c,
-            Err(e) => {
-                tracing::error!("BitbucketClient::from_env error: {e}");
-                return Ok(CallToolResult::error(vec![Content::text(e.to_string())]))
-            },
-        };
-        match client.list_branch_restrictions(&workspace, &repo_slug).await {
-            Ok(val) => Ok(CallToolResult::success(vec![Content::json(val)?])),
+    pub async fn list_branch_restrictions(&self, #[tool(param)] workspace: String, #[tool(param)] repo_slug: String, #[tool(param)] #[serde(flatten)] page: PageOptions, #[tool(param)] #[serde(flatten)] projection: Projection) -> Result<CallToolResult, McpError> {
+        match self.client.list_branch_restrictions(&workspace, &repo_slug, &page, projection.fields()).await {
+            Ok(val) => Ok(CallToolResult::success(vec![redact::json_content(fields::present(val, Resource::Other, projection.fields()))?])),
             Err(e) => {
                 tracing::error!("list_branch_restrictions error: {e}");
                 Ok(CallToolResult::error(vec![Content::text(e.to_string())]))
@@ -1290,16 +1821,9 @@ impl BitbucketTool {
     }
 
     #[tool(description = "List bitbucket commit statuses for a commit")]
-    pub async fn list_commit_statuses(&self, #[tool(param)] workspace: String, #[tool(param)] repo_slug: String, #[tool(param)] commit: String) -> Result<CallToolResult, McpError> {
-        let client = match super::bitbucket::BitbucketClient::from_env() {
-            Ok(c) => c,
-            Err(e) => {
-                tracing::error!("BitbucketClient::from_env error: {e}");
-                return Ok(CallToolResult::error(vec![Content::text(e.to_string())]))
-            },
-        };
-        match client.list_commit_statuses(&workspace, &repo_slug, &commit).await {
-            Ok(val) => Ok(CallToolResult::success(vec![Content::json(val)?])),
+    pub async fn list_commit_statuses(&self, #[tool(param)] workspace: String, #[tool(param)] repo_slug: String, #[tool(param)] commit: String, #[tool(param)] #[serde(flatten)] page: PageOptions, #[tool(param)] #[serde(flatten)] projection: Projection) -> Result<CallToolResult, McpError> {
+        match self.client.list_commit_statuses(&workspace, &repo_slug, &commit, &page, projection.fields()).await {
+            Ok(val) => Ok(CallToolResult::success(vec![redact::json_content(fields::present(val, Resource::Other, projection.fields()))?])),
             Err(e) => {
                 tracing::error!("list_commit_statuses error: {e}");
                 Ok(CallToolResult::error(vec![Content::text(e.to_string())]))
@@ -1308,16 +1832,9 @@ impl BitbucketTool {
     }
 
     #[tool(description = "List bitbucket users in a workspace")]
-    pub async fn list_users(&self, #[tool(param)] workspace: String) -> Result<CallToolResult, McpError> {
-        let client = match super::bitbucket::BitbucketClient::from_env() {
-            Ok(c) => c,
-            Err(e) => {
-                tracing::error!("BitbucketClient::from_env error: {e}");
-                return Ok(CallToolResult::error(vec![Content::text(e.to_string())]))
-            },
-        };
-        match client.list_users(&workspace).await {
-            Ok(val) => Ok(CallToolResult::success(vec![Content::json(val)?])),
+    pub async fn list_users(&self, #[tool(param)] workspace: String, #[tool(param)] #[serde(flatten)] page: PageOptions, #[tool(param)] #[serde(flatten)] projection: Projection) -> Result<CallToolResult, McpError> {
+        match self.client.list_users(&workspace, &page, projection.fields()).await {
+            Ok(val) => Ok(CallToolResult::success(vec![redact::json_content(fields::present(val, Resource::Other, projection.fields()))?])),
             Err(e) => {
                 tracing::error!("list_users error: {e}");
                 Ok(CallToolResult::error(vec![Content::text(e.to_string())]))
@@ -1326,16 +1843,10 @@ impl BitbucketTool {
     }
 
     #[tool(description = "Create a bitbucket repository in a workspace")]
-    pub async fn create_repository(&self, #[tool(param)] workspace: String, #[tool(param)] repo_slug: String, #[tool(param)] body: serde_json::Value) -> Result<CallToolResult, McpError> {
-        let client = match super::bitbucket::BitbucketClient::from_env() {
-            Ok(c) => c,
-            Err(e) => {
-                tracing::error!("BitbucketClient::from_env error: {e}");
-                return Ok(CallToolResult::error(vec![Content::text(e.to_string())]))
-            },
-        };
-        match client.create_repository(&workspace, &repo_slug, body).await {
-            Ok(val) => Ok(CallToolResult::success(vec![Content::json(val)?])),
+    pub async fn create_repository(&self, #[tool(param)] workspace: String, #[tool(param)] repo_slug: String, #[tool(param)] body: CreateRepository) -> Result<CallToolResult, McpError> {
+        let body = tool_body(body)?;
+        match self.client.create_repository(&workspace, &repo_slug, body).await {
+            Ok(val) => Ok(CallToolResult::success(vec![redact::json_content(val)?])),
             Err(e) => {
                 tracing::error!("create_repository error: {e}");
                 Ok(CallToolResult::error(vec![Content::text(e.to_string())]))
@@ -1345,15 +1856,8 @@ impl BitbucketTool {
 
     #[tool(description = "Update a bitbucket repository in a workspace")]
     pub async fn update_repository(&self, #[tool(param)] workspace: String, #[tool(param)] repo_slug: String, #[tool(param)] body: serde_json::Value) -> Result<CallToolResult, McpError> {
-        let client = match super::bitbucket::BitbucketClient::from_env() {
-            Ok(c) => c,
-            Err(e) => {
-                tracing::error!("BitbucketClient::from_env error: {e}");
-                return Ok(CallToolResult::error(vec![Content::text(e.to_string())]))
-            },
-        };
-        match client.update_repository(&workspace, &repo_slug, body).await {
-            Ok(val) => Ok(CallToolResult::success(vec![Content::json(val)?])),
+        match self.client.update_repository(&workspace, &repo_slug, body).await {
+            Ok(val) => Ok(CallToolResult::success(vec![redact::json_content(val)?])),
             Err(e) => {
                 tracing::error!("update_repository error: {e}");
                 Ok(CallToolResult::error(vec![Content::text(e.to_string())]))
@@ -1363,15 +1867,8 @@ impl BitbucketTool {
 
     #[tool(description = "Delete a bitbucket repository in a workspace")]
     pub async fn delete_repository(&self, #[tool(param)] workspace: String, #[tool(param)] repo_slug: String) -> Result<CallToolResult, McpError> {
-        let client = match super::bitbucket::BitbucketClient::from_env() {
-            Ok(c) => c,
-            Err(e) => {
-                tracing::error!("BitbucketClient::from_env error: {e}");
-                return Ok(CallToolResult::error(vec![Content::text(e.to_string())]))
-            },
-        };
-        match client.delete_repository(&workspace, &repo_slug).await {
-            Ok(val) => Ok(CallToolResult::success(vec![Content::json(val)?])),
+        match self.client.delete_repository(&workspace, &repo_slug).await {
+            Ok(val) => Ok(CallToolResult::success(vec![redact::json_content(val)?])),
             Err(e) => {
                 tracing::error!("delete_repository error: {e}");
                 Ok(CallToolResult::error(vec![Content::text(e.to_string())]))
@@ -1381,15 +1878,8 @@ impl BitbucketTool {
 
     #[tool(description = "Create a bitbucket branch in a repository")]
     pub async fn create_branch(&self, #[tool(param)] workspace: String, #[tool(param)] repo_slug: String, #[tool(param)] body: serde_json::Value) -> Result<CallToolResult, McpError> {
-        let client = match super::bitbucket::BitbucketClient::from_env() {
-            Ok(c) => c,
-            Err(e) => {
-                tracing::error!("BitbucketClient::from_env error: {e}");
-                return Ok(CallToolResult::error(vec![Content::text(e.to_string())]))
-            },
-        };
-        match client.create_branch(&workspace, &repo_slug, body).await {
-            Ok(val) => Ok(CallToolResult::success(vec![Content::json(val)?])),
+        match self.client.create_branch(&workspace, &repo_slug, body).await {
+            Ok(val) => Ok(CallToolResult::success(vec![redact::json_content(val)?])),
             Err(e) => {
                 tracing::error!("create_branch error: {e}");
                 Ok(CallToolResult::error(vec![Content::text(e.to_string())]))
@@ -1399,15 +1889,8 @@ impl BitbucketTool {
 
     #[tool(description = "Delete a bitbucket branch in a repository")]
     pub async fn delete_branch(&self, #[tool(param)] workspace: String, #[tool(param)] repo_slug: String, #[tool(param)] branch: String) -> Result<CallToolResult, McpError> {
-        let client = match super::bitbucket::BitbucketClient::from_env() {
-            Ok(c) => c,
-            Err(e) => {
-                tracing::error!("BitbucketClient::from_env error: {e}");
-                return Ok(CallToolResult::error(vec![Content::text(e.to_string())]))
-            },
-        };
-        match client.delete_branch(&workspace, &repo_slug, &branch).await {
-            Ok(val) => Ok(CallToolResult::success(vec![Content::json(val)?])),
+        match self.client.delete_branch(&workspace, &repo_slug, &branch).await {
+            Ok(val) => Ok(CallToolResult::success(vec![redact::json_content(val)?])),
             Err(e) => {
                 tracing::error!("delete_branch error: {e}");
                 Ok(CallToolResult::error(vec![Content::text(e.to_string())]))
@@ -1416,16 +1899,9 @@ impl BitbucketTool {
     }
 
     #[tool(description = "Get bitbucket branching model")]
-    pub async fn get_branching_model(&self, #[tool(param)] workspace: String, #[tool(param)] repo_slug: String) -> Result<CallToolResult, McpError> {
-        let client = match super::bitbucket::BitbucketClient::from_env() {
-            Ok(c) => c,
-            Err(e) => {
-                tracing::error!("BitbucketClient::from_env error: {e}");
-                return Ok(CallToolResult::error(vec![Content::text(e.to_string())]))
-            },
-        };
-        match client.get_branching_model(&workspace, &repo_slug).await {
-            Ok(val) => Ok(CallToolResult::success(vec![Content::json(val)?])),
+    pub async fn get_branching_model(&self, #[tool(param)] workspace: String, #[tool(param)] repo_slug: String, #[tool(param)] #[serde(flatten)] projection: Projection) -> Result<CallToolResult, McpError> {
+        match self.client.get_branching_model(&workspace, &repo_slug, projection.fields()).await {
+            Ok(val) => Ok(CallToolResult::success(vec![redact::json_content(fields::present(val, Resource::Other, projection.fields()))?])),
             Err(e) => {
                 tracing::error!("get_branching_model error: {e}");
                 Ok(CallToolResult::error(vec![Content::text(e.to_string())]))
@@ -1435,15 +1911,8 @@ impl BitbucketTool {
 
     #[tool(description = "Update bitbucket branching model")]
     pub async fn update_branching_model(&self, #[tool(param)] workspace: String, #[tool(param)] repo_slug: String, #[tool(param)] body: serde_json::Value) -> Result<CallToolResult, McpError> {
-        let client = match super::bitbucket::BitbucketClient::from_env() {
-            Ok(c) => c,
-            Err(e) => {
-                tracing::error!("BitbucketClient::from_env error: {e}");
-                return Ok(CallToolResult::error(vec![Content::text(e.to_string())]))
-            },
-        };
-        match client.update_branching_model(&workspace, &repo_slug, body).await {
-            Ok(val) => Ok(CallToolResult::success(vec![Content::json(val)?])),
+        match self.client.update_branching_model(&workspace, &repo_slug, body).await {
+            Ok(val) => Ok(CallToolResult::success(vec![redact::json_content(val)?])),
             Err(e) => {
                 tracing::error!("update_branching_model error: {e}");
                 Ok(CallToolResult::error(vec![Content::text(e.to_string())]))
@@ -1453,15 +1922,8 @@ impl BitbucketTool {
 
     #[tool(description = "Create a bitbucket commit status")]
     pub async fn create_commit_status(&self, #[tool(param)] workspace: String, #[tool(param)] repo_slug: String, #[tool(param)] commit: String, #[tool(param)] body: serde_json::Value) -> Result<CallToolResult, McpError> {
-        let client = match super::bitbucket::BitbucketClient::from_env() {
-            Ok(c) => c,
-            Err(e) => {
-                tracing::error!("BitbucketClient::from_env error: {e}");
-                return Ok(CallToolResult::error(vec![Content::text(e.to_string())]))
-            },
-        };
-        match client.create_commit_status(&workspace, &repo_slug, &commit, body).await {
-            Ok(val) => Ok(CallToolResult::success(vec![Content::json(val)?])),
+        match self.client.create_commit_status(&workspace, &repo_slug, &commit, body).await {
+            Ok(val) => Ok(CallToolResult::success(vec![redact::json_content(val)?])),
             Err(e) => {
                 tracing::error!("create_commit_status error: {e}");
                 Ok(CallToolResult::error(vec![Content::text(e.to_string())]))
@@ -1470,16 +1932,9 @@ impl BitbucketTool {
     }
 
     #[tool(description = "Get bitbucket commit details")]
-    pub async fn get_commit(&self, #[tool(param)] workspace: String, #[tool(param)] repo_slug: String, #[tool(param)] commit: String) -> Result<CallToolResult, McpError> {
-        let client = match super::bitbucket::BitbucketClient::from_env() {
-            Ok(c) => c,
-            Err(e) => {
-                tracing::error!("BitbucketClient::from_env error: {e}");
-                return Ok(CallToolResult::error(vec![Content::text(e.to_string())]))
-            },
-        };
-        match client.get_commit(&workspace, &repo_slug, &commit).await {
-            Ok(val) => Ok(CallToolResult::success(vec![Content::json(val)?])),
+    pub async fn get_commit(&self, #[tool(param)] workspace: String, #[tool(param)] repo_slug: String, #[tool(param)] commit: String, #[tool(param)] #[serde(flatten)] projection: Projection) -> Result<CallToolResult, McpError> {
+        match self.client.get_commit(&workspace, &repo_slug, &commit, projection.fields()).await {
+            Ok(val) => Ok(CallToolResult::success(vec![redact::json_content(fields::present(val, Resource::Commit, projection.fields()))?])),
             Err(e) => {
                 tracing::error!("get_commit error: {e}");
                 Ok(CallToolResult::error(vec![Content::text(e.to_string())]))
@@ -1489,15 +1944,8 @@ impl BitbucketTool {
 
     #[tool(description = "Create a bitbucket deployment")]
     pub async fn create_deployment(&self, #[tool(param)] workspace: String, #[tool(param)] repo_slug: String, #[tool(param)] body: serde_json::Value) -> Result<CallToolResult, McpError> {
-        let client = match super::bitbucket::BitbucketClient::from_env() {
-            Ok(c) => c,
-            Err(e) => {
-                tracing::error!("BitbucketClient::from_env error: {e}");
-                return Ok(CallToolResult::error(vec![Content::text(e.to_string())]))
-            },
-        };
-        match client.create_deployment(&workspace, &repo_slug, body).await {
-            Ok(val) => Ok(CallToolResult::success(vec![Content::json(val)?])),
+        match self.client.create_deployment(&workspace, &repo_slug, body).await {
+            Ok(val) => Ok(CallToolResult::success(vec![redact::json_content(val)?])),
             Err(e) => {
                 tracing::error!("create_deployment error: {e}");
                 Ok(CallToolResult::error(vec![Content::text(e.to_string())]))
@@ -1506,16 +1954,10 @@ impl BitbucketTool {
     }
 
     #[tool(description = "Create a bitbucket issue")]
-    pub async fn create_issue(&self, #[tool(param)] workspace: String, #[tool(param)] repo_slug: String, #[tool(param)] body: serde_json::Value) -> Result<CallToolResult, McpError> {
-        let client = match super::bitbucket::BitbucketClient::from_env() {
-            Ok(c) => c,
-            Err(e) => {
-                tracing::error!("BitbucketClient::from_env error: {e}");
-                return Ok(CallToolResult::error(vec![Content::text(e.to_string())]))
-            },
-        };
-        match client.create_issue(&workspace, &repo_slug, body).await {
-            Ok(val) => Ok(CallToolResult::success(vec![Content::json(val)?])),
+    pub async fn create_issue(&self, #[tool(param)] workspace: String, #[tool(param)] repo_slug: String, #[tool(param)] body: CreateIssue) -> Result<CallToolResult, McpError> {
+        let body = tool_body(body)?;
+        match self.client.create_issue(&workspace, &repo_slug, body).await {
+            Ok(val) => Ok(CallToolResult::success(vec![redact::json_content(val)?])),
             Err(e) => {
                 tracing::error!("create_issue error: {e}");
                 Ok(CallToolResult::error(vec![Content::text(e.to_string())]))
@@ -1524,16 +1966,10 @@ impl BitbucketTool {
     }
 
     #[tool(description = "Update a bitbucket issue")]
-    pub async fn update_issue(&self, #[tool(param)] workspace: String, #[tool(param)] repo_slug: String, #[tool(param)] issue_id: String, #[tool(param)] body: serde_json::Value) -> Result<CallToolResult, McpError> {
-        let client = match super::bitbucket::BitbucketClient::from_env() {
-            Ok(c) => c,
-            Err(e) => {
-                tracing::error!("BitbucketClient::from_env error: {e}");
-                return Ok(CallToolResult::error(vec![Content::text(e.to_string())]))
-            },
-        };
-        match client.update_issue(&workspace, &repo_slug, &issue_id, body).await {
-            Ok(val) => Ok(CallToolResult::success(vec![Content::json(val)?])),
+    pub async fn update_issue(&self, #[tool(param)] workspace: String, #[tool(param)] repo_slug: String, #[tool(param)] issue_id: String, #[tool(param)] body: UpdateIssue) -> Result<CallToolResult, McpError> {
+        let body = tool_body(body)?;
+        match self.client.update_issue(&workspace, &repo_slug, &issue_id, body).await {
+            Ok(val) => Ok(CallToolResult::success(vec![redact::json_content(val)?])),
             Err(e) => {
                 tracing::error!("update_issue error: {e}");
                 Ok(CallToolResult::error(vec![Content::text(e.to_string())]))
@@ -1543,15 +1979,8 @@ impl BitbucketTool {
 
     #[tool(description = "Delete a bitbucket issue")]
     pub async fn delete_issue(&self, #[tool(param)] workspace: String, #[tool(param)] repo_slug: String, #[tool(param)] issue_id: String) -> Result<CallToolResult, McpError> {
-        let client = match super::bitbucket::BitbucketClient::from_env() {
-            Ok(c) => c,
-            Err(e) => {
-                tracing::error!("BitbucketClient::from_env error: {e}");
-                return Ok(CallToolResult::error(vec![Content::text(e.to_string())]))
-            },
-        };
-        match client.delete_issue(&workspace, &repo_slug, &issue_id).await {
-            Ok(val) => Ok(CallToolResult::success(vec![Content::json(val)?])),
+        match self.client.delete_issue(&workspace, &repo_slug, &issue_id).await {
+            Ok(val) => Ok(CallToolResult::success(vec![redact::json_content(val)?])),
             Err(e) => {
                 tracing::error!("delete_issue error: {e}");
                 Ok(CallToolResult::error(vec![Content::text(e.to_string())]))
@@ -1560,16 +1989,10 @@ impl BitbucketTool {
     }
 
     #[tool(description = "Trigger a bitbucket pipeline")]
-    pub async fn trigger_pipeline(&self, #[tool(param)] workspace: String, #[tool(param)] repo_slug: String, #[tool(param)] body: serde_json::Value) -> Result<CallToolResult, McpError> {
-        let client = match super::bitbucket::BitbucketClient::from_env() {
-            Ok(c) => c,
-            Err(e) => {
-                tracing::error!("BitbucketClient::from_env error: {e}");
-                return Ok(CallToolResult::error(vec![Content::text(e.to_string())]))
-            },
-        };
-        match client.trigger_pipeline(&workspace, &repo_slug, body).await {
-            Ok(val) => Ok(CallToolResult::success(vec![Content::json(val)?])),
+    pub async fn trigger_pipeline(&self, #[tool(param)] workspace: String, #[tool(param)] repo_slug: String, #[tool(param)] body: TriggerPipeline) -> Result<CallToolResult, McpError> {
+        let body = tool_body(body)?;
+        match self.client.trigger_pipeline(&workspace, &repo_slug, body).await {
+            Ok(val) => Ok(CallToolResult::success(vec![redact::json_content(val)?])),
             Err(e) => {
                 tracing::error!("trigger_pipeline error: {e}");
                 Ok(CallToolResult::error(vec![Content::text(e.to_string())]))
@@ -1579,15 +2002,8 @@ impl BitbucketTool {
 
     #[tool(description = "Create a bitbucket project in a workspace")]
     pub async fn create_project(&self, #[tool(param)] workspace: String, #[tool(param)] body: serde_json::Value) -> Result<CallToolResult, McpError> {
-        let client = match super::bitbucket::BitbucketClient::from_env() {
-            Ok(c) => c,
-            Err(e) => {
-                tracing::error!("BitbucketClient::from_env error: {e}");
-                return Ok(CallToolResult::error(vec![Content::text(e.to_string())]))
-            },
-        };
-        match client.create_project(&workspace, body).await {
-            Ok(val) => Ok(CallToolResult::success(vec![Content::json(val)?])),
+        match self.client.create_project(&workspace, body).await {
+            Ok(val) => Ok(CallToolResult::success(vec![redact::json_content(val)?])),
             Err(e) => {
                 tracing::error!("create_project error: {e}");
                 Ok(CallToolResult::error(vec![Content::text(e.to_string())]))
@@ -1597,15 +2013,8 @@ impl BitbucketTool {
 
     #[tool(description = "Update a bitbucket project in a workspace")]
     pub async fn update_project(&self, #[tool(param)] workspace: String, #[tool(param)] project_key: String, #[tool(param)] body: serde_json::Value) -> Result<CallToolResult, McpError> {
-        let client = match super::bitbucket::BitbucketClient::from_env() {
-            Ok(c) => c,
-            Err(e) => {
-                tracing::error!("BitbucketClient::from_env error: {e}");
-                return Ok(CallToolResult::error(vec![Content::text(e.to_string())]))
-            },
-        };
-        match client.update_project(&workspace, &project_key, body).await {
-            Ok(val) => Ok(CallToolResult::success(vec![Content::json(val)?])),
+        match self.client.update_project(&workspace, &project_key, body).await {
+            Ok(val) => Ok(CallToolResult::success(vec![redact::json_content(val)?])),
             Err(e) => {
                 tracing::error!("update_project error: {e}");
                 Ok(CallToolResult::error(vec![Content::text(e.to_string())]))
@@ -1615,15 +2024,8 @@ impl BitbucketTool {
 
     #[tool(description = "Delete a bitbucket project in a workspace")]
     pub async fn delete_project(&self, #[tool(param)] workspace: String, #[tool(param)] project_key: String) -> Result<CallToolResult, McpError> {
-        let client = match super::bitbucket::BitbucketClient::from_env() {
-            Ok(c) => c,
-            Err(e) => {
-                tracing::error!("BitbucketClient::from_env error: {e}");
-                return Ok(CallToolResult::error(vec![Content::text(e.to_string())]))
-            },
-        };
-        match client.delete_project(&workspace, &project_key).await {
-            Ok(val) => Ok(CallToolResult::success(vec![Content::json(val)?])),
+        match self.client.delete_project(&workspace, &project_key).await {
+            Ok(val) => Ok(CallToolResult::success(vec![redact::json_content(val)?])),
             Err(e) => {
                 tracing::error!("delete_project error: {e}");
                 Ok(CallToolResult::error(vec![Content::text(e.to_string())]))
@@ -1633,15 +2035,8 @@ impl BitbucketTool {
 
     #[tool(description = "Create a bitbucket snippet in a workspace")]
     pub async fn create_snippet(&self, #[tool(param)] workspace: String, #[tool(param)] body: serde_json::Value) -> Result<CallToolResult, McpError> {
-        let client = match super::bitbucket::BitbucketClient::from_env() {
-            Ok(c) => c,
-            Err(e) => {
-                tracing::error!("BitbucketClient::from_env error: {e}");
-                return Ok(CallToolResult::error(vec![Content::text(e.to_string())]))
-            },
-        };
-        match client.create_snippet(&workspace, body).await {
-            Ok(val) => Ok(CallToolResult::success(vec![Content::json(val)?])),
+        match self.client.create_snippet(&workspace, body).await {
+            Ok(val) => Ok(CallToolResult::success(vec![redact::json_content(val)?])),
             Err(e) => {
                 tracing::error!("create_snippet error: {e}");
                 Ok(CallToolResult::error(vec![Content::text(e.to_string())]))
@@ -1651,15 +2046,8 @@ impl BitbucketTool {
 
     #[tool(description = "Delete a bitbucket snippet in a workspace")]
     pub async fn delete_snippet(&self, #[tool(param)] workspace: String, #[tool(param)] snippet_id: String) -> Result<CallToolResult, McpError> {
-        let client = match super::bitbucket::BitbucketClient::from_env() {
-            Ok(c) => c,
-            Err(e) => {
-                tracing::error!("BitbucketClient::from_env error: {e}");
-                return Ok(CallToolResult::error(vec![Content::text(e.to_string())]))
-            },
-        };
-        match client.delete_snippet(&workspace, &snippet_id).await {
-            Ok(val) => Ok(CallToolResult::success(vec![Content::json(val)?])),
+        match self.client.delete_snippet(&workspace, &snippet_id).await {
+            Ok(val) => Ok(CallToolResult::success(vec![redact::json_content(val)?])),
             Err(e) => {
                 tracing::error!("delete_snippet error: {e}");
                 Ok(CallToolResult::error(vec![Content::text(e.to_string())]))
@@ -1669,15 +2057,8 @@ impl BitbucketTool {
 
     #[tool(description = "Get bitbucket file source from a repository")]
     pub async fn get_file_source(&self, #[tool(param)] workspace: String, #[tool(param)] repo_slug: String, #[tool(param)] commit: String, #[tool(param)] path: String) -> Result<CallToolResult, McpError> {
-        let client = match super::bitbucket::BitbucketClient::from_env() {
-            Ok(c) => c,
-            Err(e) => {
-                tracing::error!("BitbucketClient::from_env error: {e}");
-                return Ok(CallToolResult::error(vec![Content::text(e.to_string())]))
-            },
-        };
-        match client.get_file_source(&workspace, &repo_slug, &commit, &path).await {
-            Ok(val) => Ok(CallToolResult::success(vec![Content::json(val)?])),
+        match self.client.get_file_source(&workspace, &repo_slug, &commit, &path).await {
+            Ok(val) => Ok(CallToolResult::success(vec![Content::text(redact::redact_credentials(&val))])),
             Err(e) => {
                 tracing::error!("get_file_source error: {e}");
                 Ok(CallToolResult::error(vec![Content::text(e.to_string())]))
@@ -1686,13 +2067,23 @@ impl BitbucketTool {
     }
 }
 
-#[tool(tool_box)]
 impl ServerHandler for BitbucketTool {
     fn get_info(&self) -> ServerInfo {
         ServerInfo {
-            instructions: Some("Bitbucket MCP tool: interact with Bitbucket Cloud REST API. Set BITBUCKET_API_USERNAME and BITBUCKET_API_TOKEN env vars.".into()),
+            instructions: Some("Bitbucket MCP tool: interact with Bitbucket Cloud REST API. Authenticate with BITBUCKET_API_USERNAME and BITBUCKET_API_TOKEN, BITBUCKET_ACCESS_TOKEN, or BITBUCKET_OAUTH_CLIENT_ID and BITBUCKET_OAUTH_CLIENT_SECRET.".into()),
             capabilities: ServerCapabilities::builder().enable_tools().build(),
             ..Default::default()
         }
     }
+
+    async fn list_tools(&self, _: PaginatedRequestParam, _: RequestContext<RoleServer>) -> Result<ListToolsResult, McpError> {
+        Ok(ListToolsResult { next_cursor: None, tools: self.tools() })
+    }
+
+    async fn call_tool(&self, request: CallToolRequestParam, context: RequestContext<RoleServer>) -> Result<CallToolResult, McpError> {
+        if let Some(arguments) = &request.arguments {
+            tracing::debug!("{} called with {}", request.name, redact::redact_value(&serde_json::Value::Object(arguments.clone())));
+        }
+        self.dispatch(request, context).await.map(redact::redact_result)
+    }
 }
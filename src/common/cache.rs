@@ -0,0 +1,205 @@
+// In-memory cache for GET responses, decoded JSON or raw text (file contents, diffs). Entries are fresh for a TTL; after that, entries that came
+// with an `ETag` or `Last-Modified` header are revalidated with a conditional request, so an
+// unchanged resource costs a 304 instead of a full download. The least recently used entry is
+// evicted when the cache is full, and a mutating request drops every entry of the repository
+// (or workspace) it touched.
+
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::time::{Duration, Instant};
+use reqwest::header::{self, HeaderMap, HeaderValue};
+use serde_json::Value;
+
+pub const DEFAULT_CAPACITY: usize = 256;
+pub const DEFAULT_TTL: Duration = Duration::from_secs(60);
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct CacheConfig {
+    /// Maximum number of cached responses.
+    pub capacity: usize,
+    /// How long an entry is served without asking Bitbucket.
+    pub ttl: Duration,
+}
+
+impl Default for CacheConfig {
+    fn default() -> Self {
+        Self { capacity: DEFAULT_CAPACITY, ttl: DEFAULT_TTL }
+    }
+}
+
+/// A cached response body.
+#[derive(Debug, Clone, PartialEq)]
+pub enum Body {
+    Json(Value),
+    /// Raw text, returned as received rather than decoded.
+    Text(Arc<str>),
+}
+
+impl Body {
+    pub fn is_text(&self) -> bool {
+        matches!(self, Body::Text(_))
+    }
+
+    pub fn into_json(self) -> Value {
+        match self {
+            Body::Json(value) => value,
+            Body::Text(text) => Value::String(text.to_string()),
+        }
+    }
+
+    pub fn into_text(self) -> String {
+        match self {
+            Body::Json(value) => value.to_string(),
+            Body::Text(text) => text.to_string(),
+        }
+    }
+}
+
+/// Outcome of a cache lookup.
+#[derive(Debug)]
+pub enum Lookup {
+    Fresh(Body),
+    /// Expired, but can be revalidated with these conditional request headers.
+    Stale(HeaderMap),
+    Miss,
+}
+
+#[derive(Debug)]
+struct Entry {
+    body: Body,
+    etag: Option<HeaderValue>,
+    last_modified: Option<HeaderValue>,
+    workspace: Option<String>,
+    repo: Option<String>,
+    stored_at: Instant,
+    last_used: u64,
+}
+
+#[derive(Debug, Default)]
+struct State {
+    entries: HashMap<String, Entry>,
+    clock: u64,
+}
+
+#[derive(Debug, Default)]
+struct Counters {
+    hits: AtomicU64,
+    misses: AtomicU64,
+    revalidated: AtomicU64,
+    invalidated: AtomicU64,
+    evicted: AtomicU64,
+}
+
+#[derive(Debug)]
+pub struct ResponseCache {
+    config: CacheConfig,
+    state: Mutex<State>,
+    counters: Counters,
+}
+
+impl ResponseCache {
+    pub fn new(config: CacheConfig) -> Self {
+        Self { config, state: Mutex::new(State::default()), counters: Counters::default() }
+    }
+
+    pub fn config(&self) -> CacheConfig {
+        self.config
+    }
+
+    pub fn lookup(&self, url: &str) -> Lookup {
+        let mut state = self.state.lock().unwrap();
+        state.clock += 1;
+        let clock = state.clock;
+        let Some(entry) = state.entries.get_mut(url) else {
+            self.counters.misses.fetch_add(1, Ordering::Relaxed);
+            return Lookup::Miss;
+        };
+        entry.last_used = clock;
+        if entry.stored_at.elapsed() < self.config.ttl {
+            self.counters.hits.fetch_add(1, Ordering::Relaxed);
+            return Lookup::Fresh(entry.body.clone());
+        }
+        let mut conditions = HeaderMap::new();
+        if let Some(etag) = &entry.etag {
+            conditions.insert(header::IF_NONE_MATCH, etag.clone());
+        }
+        if let Some(last_modified) = &entry.last_modified {
+            conditions.insert(header::IF_MODIFIED_SINCE, last_modified.clone());
+        }
+        self.counters.misses.fetch_add(1, Ordering::Relaxed);
+        if conditions.is_empty() {
+            state.entries.remove(url);
+            return Lookup::Miss;
+        }
+        Lookup::Stale(conditions)
+    }
+
+    /// Marks a stale entry fresh again after a `304 Not Modified` and returns its body.
+    pub fn revalidated(&self, url: &str) -> Option<Body> {
+        let mut state = self.state.lock().unwrap();
+        let entry = state.entries.get_mut(url)?;
+        entry.stored_at = Instant::now();
+        self.counters.revalidated.fetch_add(1, Ordering::Relaxed);
+        Some(entry.body.clone())
+    }
+
+    /// Stores a response. `target` is the workspace and repository the URL addresses, used for invalidation.
+    pub fn store(&self, url: &str, body: Body, headers: &HeaderMap, (workspace, repo): (Option<String>, Option<String>)) {
+        if self.config.capacity == 0 {
+            return;
+        }
+        let mut state = self.state.lock().unwrap();
+        state.clock += 1;
+        let entry = Entry {
+            body,
+            etag: headers.get(header::ETAG).cloned(),
+            last_modified: headers.get(header::LAST_MODIFIED).cloned(),
+            workspace,
+            repo,
+            stored_at: Instant::now(),
+            last_used: state.clock,
+        };
+        state.entries.insert(url.to_string(), entry);
+        while state.entries.len() > self.config.capacity {
+            let Some(oldest) = state.entries.iter().min_by_key(|(_, e)| e.last_used).map(|(url, _)| url.clone()) else {
+                break;
+            };
+            state.entries.remove(&oldest);
+            self.counters.evicted.fetch_add(1, Ordering::Relaxed);
+        }
+    }
+
+    /// Drops what a mutating request may have changed: every entry of `repo` plus the listings of
+    /// its workspace, every entry of the workspace when there is no repository, and everything for
+    /// requests outside any workspace.
+    pub fn invalidate(&self, workspace: Option<&str>, repo: Option<&str>) {
+        let mut state = self.state.lock().unwrap();
+        let before = state.entries.len();
+        state.entries.retain(|_, entry| match (workspace, repo) {
+            (Some(ws), Some(repo)) => entry.workspace.as_deref() != Some(ws) || entry.repo.as_deref().is_some_and(|r| r != repo),
+            (Some(ws), None) => entry.workspace.as_deref() != Some(ws),
+            (None, _) => false,
+        });
+        self.counters.invalidated.fetch_add((before - state.entries.len()) as u64, Ordering::Relaxed);
+    }
+
+    pub fn clear(&self) {
+        self.state.lock().unwrap().entries.clear();
+    }
+
+    /// Size, settings and hit/miss counters for diagnostics.
+    pub fn stats(&self) -> Value {
+        let load = |counter: &AtomicU64| counter.load(Ordering::Relaxed);
+        serde_json::json!({
+            "entries": self.state.lock().unwrap().entries.len(),
+            "capacity": self.config.capacity,
+            "ttl_secs": self.config.ttl.as_secs(),
+            "hits": load(&self.counters.hits),
+            "misses": load(&self.counters.misses),
+            "revalidated": load(&self.counters.revalidated),
+            "invalidated": load(&self.counters.invalidated),
+            "evicted": load(&self.counters.evicted),
+        })
+    }
+}
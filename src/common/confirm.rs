@@ -0,0 +1,85 @@
+// Two-step confirmation for destructive tools. When enabled, the first call to such a tool only
+// returns a preview and a short-lived token; the action runs when the same tool is called again
+// with the same arguments plus that token. Tokens are single-use and live in the tool instance,
+// so in HTTP mode each session has its own.
+
+use std::collections::HashMap;
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+use serde_json::{Map, Value};
+use super::error::{BitbucketError, Result};
+
+/// Tools that delete data or cannot be undone.
+pub const DESTRUCTIVE_TOOLS: &[&str] = &[
+    "delete_repository",
+    "delete_branch",
+    "delete_issue",
+    "delete_project",
+    "delete_snippet",
+    "decline_pullrequest",
+    "merge_pullrequest",
+];
+
+/// Argument that carries the token on the confirming call.
+pub const TOKEN_PARAM: &str = "confirmation_token";
+pub const DEFAULT_TTL: Duration = Duration::from_secs(300);
+
+pub fn is_destructive(tool: &str) -> bool {
+    DESTRUCTIVE_TOOLS.contains(&tool)
+}
+
+#[derive(Debug)]
+struct Pending {
+    tool: String,
+    arguments: Map<String, Value>,
+    expires_at: Instant,
+}
+
+#[derive(Debug)]
+pub struct Confirmations {
+    ttl: Duration,
+    pending: Mutex<HashMap<String, Pending>>,
+}
+
+impl Default for Confirmations {
+    fn default() -> Self {
+        Self::new(DEFAULT_TTL)
+    }
+}
+
+impl Confirmations {
+    pub fn new(ttl: Duration) -> Self {
+        Self { ttl, pending: Mutex::new(HashMap::new()) }
+    }
+
+    pub fn ttl(&self) -> Duration {
+        self.ttl
+    }
+
+    /// Issues a token that authorizes one call of `tool` with exactly `arguments`.
+    pub fn issue(&self, tool: &str, arguments: &Map<String, Value>) -> String {
+        let token = format!("{:032x}", rand::random::<u128>());
+        let now = Instant::now();
+        let mut pending = self.pending.lock().unwrap();
+        pending.retain(|_, p| p.expires_at > now);
+        pending.insert(token.clone(), Pending { tool: tool.to_string(), arguments: arguments.clone(), expires_at: now + self.ttl });
+        token
+    }
+
+    /// Consumes `token` if it was issued for this call. A token is spent even when the check fails,
+    /// so a leaked or guessed token cannot be retried against other arguments.
+    pub fn redeem(&self, token: &str, tool: &str, arguments: &Map<String, Value>) -> Result<()> {
+        let pending = self.pending.lock().unwrap().remove(token);
+        match pending {
+            None => Err(BitbucketError::Denied(format!("confirmation token for {tool} is unknown or was already used; call {tool} without it to get a new one"))),
+            Some(p) if p.expires_at <= Instant::now() => {
+                Err(BitbucketError::Denied(format!("confirmation token for {tool} has expired; call {tool} without it to get a new one")))
+            }
+            Some(p) if p.tool != tool || &p.arguments != arguments => Err(BitbucketError::Denied(format!(
+                "confirmation token was issued for {} with different arguments; call {tool} without it to get a new one",
+                p.tool
+            ))),
+            Some(_) => Ok(()),
+        }
+    }
+}
@@ -0,0 +1,188 @@
+// Bitbucket Data Center / Server support.
+// Data Center serves `/rest/api/1.0/projects/{key}/repos/{slug}/...` instead of the Cloud 2.0 paths and
+// pages with `start`/`limit` and `isLastPage`/`nextPageStart` instead of `pagelen`/`page` and `next`.
+// `BitbucketClient` keeps one set of methods for both backends (the `workspace` argument is the project
+// key on Data Center) and uses these helpers to build requests and to turn Data Center pages into `Page`,
+// so cursors, `limit` and `max_pages` behave exactly as they do on Cloud.
+
+use std::str::FromStr;
+use serde::{Deserialize, Serialize};
+use serde_json::{Value, json};
+use super::bitbucket::BitbucketCommentPayload;
+use super::error::{BitbucketError, Result};
+use super::pagination::{Page, PageOptions};
+use super::query::Filter;
+
+/// Prefix of every Data Center REST path, relative to the server's base URL.
+pub const API_PATH: &str = "/rest/api/1.0";
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Backend {
+    /// Bitbucket Cloud REST API 2.0.
+    #[default]
+    Cloud,
+    /// Bitbucket Data Center / Server REST API 1.0.
+    #[serde(alias = "data_center", alias = "data-center", alias = "server")]
+    DataCenter,
+}
+
+impl FromStr for Backend {
+    type Err = BitbucketError;
+
+    fn from_str(s: &str) -> Result<Self> {
+        match s.trim().to_ascii_lowercase().as_str() {
+            "cloud" => Ok(Self::Cloud),
+            "datacenter" | "data_center" | "data-center" | "server" => Ok(Self::DataCenter),
+            other => Err(BitbucketError::Config(format!(
+                "BITBUCKET_BACKEND has an invalid value '{other}', expected cloud or datacenter"
+            ))),
+        }
+    }
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct DataCenterPage {
+    #[serde(default)]
+    values: Vec<Value>,
+    #[serde(default = "default_true")]
+    is_last_page: bool,
+    next_page_start: Option<u64>,
+}
+
+fn default_true() -> bool {
+    true
+}
+
+/// Query parameters for the first page: `limit` and a numeric `start` offset.
+pub(crate) fn page_params(page: &PageOptions) -> Result<Vec<(&'static str, String)>> {
+    let mut query = Vec::new();
+    if let Some(limit) = page.effective_pagelen() {
+        query.push(("limit", limit.to_string()));
+    }
+    if let Some(start) = &page.page {
+        let start: u64 = start.parse().map_err(|_| BitbucketError::Validation {
+            message: format!("Data Center pages start at a numeric offset, got '{start}'"),
+            fields: [("page".to_string(), vec!["must be a start offset or a next cursor".to_string()])].into(),
+        })?;
+        query.push(("start", start.to_string()));
+    }
+    Ok(query)
+}
+
+/// Converts a Data Center page fetched from `url` into a `Page` whose `next` is the same URL
+/// with `start` set to `nextPageStart`.
+pub(crate) fn into_page(url: &str, body: Value) -> Result<Page> {
+    let page: DataCenterPage = serde_json::from_value(body)?;
+    let next = match (page.is_last_page, page.next_page_start) {
+        (false, Some(start)) => Some(with_start(url, start)?),
+        _ => None,
+    };
+    Ok(Page { values: page.values, next })
+}
+
+fn with_start(url: &str, start: u64) -> Result<String> {
+    let mut parsed = reqwest::Url::parse(url).map_err(|e| BitbucketError::Config(format!("invalid URL '{url}': {e}")))?;
+    let kept: Vec<(String, String)> = parsed.query_pairs().into_owned().filter(|(key, _)| key != "start").collect();
+    parsed.query_pairs_mut().clear().extend_pairs(kept).append_pair("start", &start.to_string());
+    Ok(parsed.to_string())
+}
+
+/// Data Center has no query language; only a single pull request `state` can be filtered on.
+pub(crate) fn pull_request_params(filter: &Filter) -> Result<Vec<(&'static str, String)>> {
+    reject(filter.q.is_some(), "q", "pull requests")?;
+    reject(filter.sort.is_some(), "sort", "pull requests")?;
+    let Some(state) = filter.state.as_deref() else {
+        return Ok(Vec::new());
+    };
+    let states: Vec<&str> = state.split(',').map(str::trim).filter(|s| !s.is_empty()).collect();
+    match states.as_slice() {
+        [] => Ok(Vec::new()),
+        [state] => Ok(vec![("state", state.to_ascii_uppercase())]),
+        _ => Err(BitbucketError::Validation {
+            message: "Bitbucket Data Center filters on a single state (OPEN, MERGED, DECLINED or ALL)".to_string(),
+            fields: [("state".to_string(), vec!["only one state is supported".to_string()])].into(),
+        }),
+    }
+}
+
+/// Rejects every filter parameter for listings that Data Center cannot filter.
+pub(crate) fn reject_filter(filter: &Filter, listing: &str) -> Result<()> {
+    reject(filter.q.is_some(), "q", listing)?;
+    reject(filter.sort.is_some(), "sort", listing)?;
+    reject(filter.state.is_some(), "state", listing)
+}
+
+fn reject(present: bool, param: &str, listing: &str) -> Result<()> {
+    if !present {
+        return Ok(());
+    }
+    Err(BitbucketError::Validation {
+        message: format!("Filtering {listing} by '{param}' is not supported by Bitbucket Data Center"),
+        fields: [(param.to_string(), vec!["not supported on Data Center".to_string()])].into(),
+    })
+}
+
+/// Comment body in Data Center's shape. Inline comments anchor on the new side of the diff (`to`),
+/// or on the old side when only `from` is given.
+pub(crate) fn comment_body(payload: &BitbucketCommentPayload) -> Value {
+    let mut body = json!({ "text": payload.content.raw });
+    if let Some(inline) = &payload.inline {
+        let mut anchor = json!({ "path": inline.path });
+        match (inline.to, inline.from) {
+            (Some(line), _) => {
+                anchor["line"] = line.into();
+                anchor["lineType"] = "ADDED".into();
+                anchor["fileType"] = "TO".into();
+            }
+            (None, Some(line)) => {
+                anchor["line"] = line.into();
+                anchor["lineType"] = "REMOVED".into();
+                anchor["fileType"] = "FROM".into();
+            }
+            (None, None) => {}
+        }
+        body["anchor"] = anchor;
+    }
+    body
+}
+
+/// Data Center lists pull request comments as activities; keep the comments and their anchors.
+pub(crate) fn comments_from_activities(activities: Vec<Value>) -> Vec<Value> {
+    activities
+        .into_iter()
+        .filter(|activity| activity["action"] == "COMMENTED")
+        .filter_map(|mut activity| {
+            let mut comment = activity.get_mut("comment")?.take();
+            if let Some(anchor) = activity.get_mut("commentAnchor") {
+                comment["anchor"] = anchor.take();
+            }
+            Some(comment)
+        })
+        .collect()
+}
+
+/// Translates a Cloud merge body (`message`, `merge_strategy`) into Data Center's (`message`, `strategyId`).
+pub(crate) fn merge_body(body: Option<&Value>) -> Value {
+    let mut merged = json!({});
+    let Some(body) = body else {
+        return merged;
+    };
+    if let Some(message) = body.get("message").and_then(Value::as_str) {
+        merged["message"] = message.into();
+    }
+    let strategy = match body.get("merge_strategy").and_then(Value::as_str) {
+        Some("merge_commit") => Some("no-ff"),
+        Some("squash") => Some("squash"),
+        Some("fast_forward") => Some("ff-only"),
+        Some("squash_fast_forward") => Some("squash-ff-only"),
+        Some("rebase_fast_forward") => Some("rebase-ff-only"),
+        Some("rebase_merge") => Some("rebase-no-ff"),
+        _ => None,
+    };
+    if let Some(strategy) = strategy {
+        merged["strategyId"] = strategy.into();
+    }
+    merged
+}
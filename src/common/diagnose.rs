@@ -0,0 +1,165 @@
+// Token scope introspection for `diagnose_auth` and the startup self-check. Bitbucket Cloud reports
+// the scopes of OAuth and access tokens in the `X-OAuth-Scopes` response header; they are compared
+// with the scope each tool needs to tell which toolsets will work before a call fails with 403.
+
+use std::collections::BTreeMap;
+use serde::Serialize;
+use serde_json::Value;
+use super::policy::Toolset;
+
+/// Header carrying the scopes granted to the token.
+pub const SCOPES_HEADER: &str = "x-oauth-scopes";
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+enum Level {
+    Read,
+    Write,
+    Admin,
+}
+
+/// A Bitbucket scope such as `pullrequest:write`. Atlassian API token scopes
+/// (`write:pullrequest:bitbucket`) are read the same way.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+struct Scope<'a> {
+    resource: &'a str,
+    level: Level,
+}
+
+impl<'a> Scope<'a> {
+    fn parse(scope: &'a str) -> Option<Self> {
+        let level = |name: &str| match name {
+            "read" => Some(Level::Read),
+            "write" => Some(Level::Write),
+            "admin" | "delete" => Some(Level::Admin),
+            _ => None,
+        };
+        let parts: Vec<&str> = scope.trim().split(':').collect();
+        let (resource, level) = match parts.as_slice() {
+            [action, resource, "bitbucket"] => (*resource, level(action)?),
+            [resource, action] => (*resource, level(action)?),
+            [resource] if !resource.is_empty() => (*resource, Level::Read),
+            _ => return None,
+        };
+        Some(Self { resource, level })
+    }
+
+    /// Whether this granted scope covers `required`. A higher level implies the lower ones, and
+    /// pull request access implies the same access to repositories.
+    fn covers(&self, required: &Scope) -> bool {
+        let resource = self.resource == required.resource || (self.resource == "pullrequest" && required.resource == "repository");
+        resource && self.level >= required.level
+    }
+}
+
+/// Scope a tool needs on Bitbucket Cloud.
+pub fn required_scope(tool: &str) -> Option<&'static str> {
+    Some(match tool {
+        "get_user" | "list_workspaces" | "get_workspace" | "list_users" => "account",
+        "list_pullrequests" | "get_pullrequest" | "list_pullrequest_comments" | "list_pullrequest_activity"
+        | "get_pullrequest_diff" | "list_pullrequest_commits" | "list_pullrequest_tasks" | "get_pullrequest_diffstat" => "pullrequest",
+        "create_pullrequest" | "update_pullrequest" | "approve_pullrequest" | "unapprove_pullrequest" | "decline_pullrequest"
+        | "merge_pullrequest" | "add_pullrequest_comment" | "add_pullrequest_task" => "pullrequest:write",
+        "list_repositories" | "get_repository" | "list_branches" | "list_tags" | "list_commits" | "get_commit" | "get_file_source"
+        | "list_commit_statuses" | "list_downloads" | "get_branching_model" => "repository",
+        "create_branch" | "delete_branch" | "create_commit_status" => "repository:write",
+        "create_repository" | "update_repository" | "delete_repository" | "list_branch_restrictions" | "update_branching_model" => {
+            "repository:admin"
+        }
+        "list_webhooks" => "webhook",
+        "list_projects" => "project",
+        "create_project" | "update_project" | "delete_project" => "project:admin",
+        "list_pipelines" | "list_deployments" => "pipeline",
+        "trigger_pipeline" | "create_deployment" => "pipeline:write",
+        "list_issues" => "issue",
+        "create_issue" | "update_issue" | "delete_issue" => "issue:write",
+        "list_snippets" => "snippet",
+        "create_snippet" | "delete_snippet" => "snippet:write",
+        _ => return None,
+    })
+}
+
+/// Splits an `X-OAuth-Scopes` header value (`"account, pullrequest:write"`).
+pub fn parse_scopes_header(value: &str) -> Vec<String> {
+    value.split([',', ' ']).map(str::trim).filter(|s| !s.is_empty()).map(str::to_string).collect()
+}
+
+pub fn has_scope(granted: &[String], required: &str) -> bool {
+    let Some(required) = Scope::parse(required) else {
+        return true;
+    };
+    granted.iter().filter_map(|g| Scope::parse(g)).any(|g| g.covers(&required))
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ToolsetStatus {
+    /// Every tool of the toolset is covered by the token's scopes.
+    Ok,
+    /// Some tools need scopes the token lacks.
+    Partial,
+    /// No tool of the toolset will work.
+    Unavailable,
+    /// The token's scopes are not reported (e.g. basic auth), so nothing can be predicted.
+    Unknown,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct ToolsetReport {
+    pub status: ToolsetStatus,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub missing_scopes: Vec<&'static str>,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub failing_tools: Vec<String>,
+}
+
+/// Per-toolset report for `tools` (the enabled tool names) given the granted scopes, if known.
+pub fn toolset_report(tools: &[String], granted: Option<&[String]>) -> BTreeMap<Toolset, ToolsetReport> {
+    let mut report = BTreeMap::new();
+    for toolset in Toolset::ALL {
+        let members: Vec<&String> = tools.iter().filter(|tool| Toolset::of(tool) == Some(toolset)).collect();
+        if members.is_empty() {
+            continue;
+        }
+        let Some(granted) = granted else {
+            report.insert(toolset, ToolsetReport { status: ToolsetStatus::Unknown, missing_scopes: Vec::new(), failing_tools: Vec::new() });
+            continue;
+        };
+        let mut missing_scopes = Vec::new();
+        let mut failing_tools = Vec::new();
+        for tool in &members {
+            if let Some(scope) = required_scope(tool).filter(|scope| !has_scope(granted, scope)) {
+                if !missing_scopes.contains(&scope) {
+                    missing_scopes.push(scope);
+                }
+                failing_tools.push(tool.to_string());
+            }
+        }
+        let status = match failing_tools.len() {
+            0 => ToolsetStatus::Ok,
+            n if n == members.len() => ToolsetStatus::Unavailable,
+            _ => ToolsetStatus::Partial,
+        };
+        report.insert(toolset, ToolsetReport { status, missing_scopes, failing_tools });
+    }
+    report
+}
+
+/// One line per tool that needs a scope the token lacks.
+pub fn warnings(report: &BTreeMap<Toolset, ToolsetReport>) -> Vec<String> {
+    report
+        .values()
+        .flat_map(|toolset| &toolset.failing_tools)
+        .map(|tool| format!("{tool} needs the '{}' scope, which the token lacks", required_scope(tool).unwrap_or_default()))
+        .collect()
+}
+
+/// Identifying fields of the `/user` response.
+pub fn user_summary(user: &Value) -> Value {
+    let mut summary = serde_json::Map::new();
+    for key in ["display_name", "username", "nickname", "account_id", "uuid", "name"] {
+        if let Some(value) = user.get(key) {
+            summary.insert(key.to_string(), value.clone());
+        }
+    }
+    Value::Object(summary)
+}
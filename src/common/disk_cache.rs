@@ -0,0 +1,264 @@
+// On-disk cache for responses that can never change: file contents addressed by a full commit
+// hash. Each response body is stored as-is in one file named by a SHA-256 of the credentials and
+// URL, so entries survive restarts but are never shared between identities. Sizes
+// and recency are tracked in memory (seeded from the directory on startup) and file I/O runs on
+// the blocking thread pool; the least recently used files are removed once the cap is exceeded.
+
+use std::collections::HashMap;
+use std::fs::{self, File};
+use std::io::ErrorKind;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::SystemTime;
+use serde_json::Value;
+use super::error::{BitbucketError, Result};
+
+pub const DEFAULT_MAX_MB: u64 = 256;
+
+const EXTENSION: &str = "body";
+
+/// Whether `rev` is a full commit hash (SHA-1, or SHA-256 for repositories using it) rather than
+/// a branch, tag or abbreviated hash whose target can move.
+pub fn is_commit_hash(rev: &str) -> bool {
+    matches!(rev.len(), 40 | 64) && rev.bytes().all(|b| b.is_ascii_hexdigit())
+}
+
+#[derive(Debug)]
+struct Entry {
+    len: u64,
+    last_used: u64,
+}
+
+#[derive(Debug, Default)]
+struct Index {
+    entries: HashMap<String, Entry>,
+    bytes: u64,
+    clock: u64,
+}
+
+impl Index {
+    fn touch(&mut self, key: &str) -> bool {
+        self.clock += 1;
+        let clock = self.clock;
+        self.entries.get_mut(key).map(|entry| entry.last_used = clock).is_some()
+    }
+
+    fn insert(&mut self, key: String, len: u64) {
+        self.clock += 1;
+        let entry = Entry { len, last_used: self.clock };
+        if let Some(old) = self.entries.insert(key, entry) {
+            self.bytes -= old.len;
+        }
+        self.bytes += len;
+    }
+
+    fn remove(&mut self, key: &str) {
+        if let Some(old) = self.entries.remove(key) {
+            self.bytes -= old.len;
+        }
+    }
+
+    /// Drops least recently used entries until the total fits in `max_bytes` and returns their keys.
+    fn evict(&mut self, max_bytes: u64) -> Vec<String> {
+        let mut evicted = Vec::new();
+        while self.bytes > max_bytes {
+            let Some(oldest) = self.entries.iter().min_by_key(|(_, e)| e.last_used).map(|(key, _)| key.clone()) else {
+                break;
+            };
+            self.remove(&oldest);
+            evicted.push(oldest);
+        }
+        evicted
+    }
+}
+
+#[derive(Debug, Default)]
+struct Counters {
+    hits: AtomicU64,
+    misses: AtomicU64,
+    evicted: AtomicU64,
+}
+
+#[derive(Debug)]
+struct Inner {
+    dir: PathBuf,
+    max_bytes: u64,
+    index: Mutex<Index>,
+    counters: Counters,
+}
+
+impl Inner {
+    fn path(&self, key: &str) -> PathBuf {
+        self.dir.join(format!("{key}.{EXTENSION}"))
+    }
+}
+
+#[derive(Debug)]
+pub struct DiskCache {
+    inner: Arc<Inner>,
+}
+
+impl DiskCache {
+    /// Opens (creating if needed) the cache directory. Entries left by earlier runs are kept, oldest
+    /// modification time first in eviction order.
+    pub fn new(dir: impl Into<PathBuf>, max_bytes: u64) -> Result<Self> {
+        let dir = dir.into();
+        let error = |e: std::io::Error| BitbucketError::Config(format!("cannot open disk cache directory {}: {e}", dir.display()));
+        fs::create_dir_all(&dir).map_err(error)?;
+        let mut existing: Vec<(String, u64, SystemTime)> = fs::read_dir(&dir)
+            .map_err(error)?
+            .filter_map(|entry| {
+                let path = entry.ok()?.path();
+                if path.extension()? != EXTENSION {
+                    // Leftover temporary files from an interrupted write.
+                    if path.extension().is_some_and(|ext| ext == "tmp") {
+                        let _ = fs::remove_file(&path);
+                    }
+                    return None;
+                }
+                let meta = path.metadata().ok()?;
+                Some((path.file_stem()?.to_str()?.to_string(), meta.len(), meta.modified().unwrap_or(SystemTime::UNIX_EPOCH)))
+            })
+            .collect();
+        existing.sort_by_key(|(_, _, modified)| *modified);
+        let mut index = Index::default();
+        for (key, len, _) in existing {
+            index.insert(key, len);
+        }
+        let inner = Inner { dir, max_bytes, index: Mutex::new(index), counters: Counters::default() };
+        let cache = Self { inner: Arc::new(inner) };
+        // The cap may have been lowered since the last run.
+        let evicted = cache.inner.index.lock().unwrap().evict(max_bytes);
+        remove_files(&cache.inner, &evicted);
+        Ok(cache)
+    }
+
+    pub fn dir(&self) -> &Path {
+        &self.inner.dir
+    }
+
+    pub fn max_bytes(&self) -> u64 {
+        self.inner.max_bytes
+    }
+
+    /// Cache key for `url` fetched with the credentials identified by `identity`.
+    pub fn key(identity: &str, url: &str) -> String {
+        let mut context = ring::digest::Context::new(&ring::digest::SHA256);
+        context.update(identity.as_bytes());
+        context.update(b"\0");
+        context.update(url.as_bytes());
+        context.finish().as_ref().iter().map(|b| format!("{b:02x}")).collect()
+    }
+
+    /// The stored body for `key`. Entries whose file has gone missing count as misses.
+    pub async fn get(&self, key: &str) -> Option<Vec<u8>> {
+        let inner = &self.inner;
+        if !inner.index.lock().unwrap().touch(key) {
+            inner.counters.misses.fetch_add(1, Ordering::Relaxed);
+            return None;
+        }
+        let path = inner.path(key);
+        let read = tokio::task::spawn_blocking(move || {
+            let body = fs::read(&path)?;
+            // Persist recency for the next startup; failing to do so only affects eviction order.
+            let _ = File::options().append(true).open(&path).and_then(|file| file.set_modified(SystemTime::now()));
+            Ok::<_, std::io::Error>(body)
+        })
+        .await
+        .unwrap_or_else(|e| Err(std::io::Error::other(e)));
+        match read {
+            Ok(body) => {
+                inner.counters.hits.fetch_add(1, Ordering::Relaxed);
+                Some(body)
+            }
+            Err(e) => {
+                if e.kind() != ErrorKind::NotFound {
+                    tracing::warn!("cannot read disk cache entry {}: {e}", inner.path(key).display());
+                }
+                inner.index.lock().unwrap().remove(key);
+                inner.counters.misses.fetch_add(1, Ordering::Relaxed);
+                None
+            }
+        }
+    }
+
+    /// Stores a body, then evicts the least recently used entries while the cache is over its cap.
+    /// Write errors are logged rather than returned: the response is already in hand.
+    pub async fn put(&self, key: &str, body: Vec<u8>) {
+        let len = body.len() as u64;
+        if len > self.inner.max_bytes {
+            return;
+        }
+        let inner = Arc::clone(&self.inner);
+        let key = key.to_string();
+        let stored = tokio::task::spawn_blocking(move || {
+            let path = inner.path(&key);
+            // Write to a temporary file first so a crash never leaves a truncated entry behind.
+            let tmp = inner.dir.join(format!("{key}.{:016x}.tmp", rand::random::<u64>()));
+            if let Err(e) = fs::write(&tmp, &body).and_then(|_| fs::rename(&tmp, &path)) {
+                tracing::warn!("cannot write disk cache entry {}: {e}", path.display());
+                let _ = fs::remove_file(&tmp);
+                return;
+            }
+            let evicted = {
+                let mut index = inner.index.lock().unwrap();
+                index.insert(key, len);
+                index.evict(inner.max_bytes)
+            };
+            remove_files(&inner, &evicted);
+        })
+        .await;
+        if let Err(e) = stored {
+            tracing::warn!("disk cache write task failed: {e}");
+        }
+    }
+
+    /// Removes every entry.
+    pub async fn clear(&self) {
+        let inner = Arc::clone(&self.inner);
+        let _ = tokio::task::spawn_blocking(move || {
+            let keys: Vec<String> = {
+                let mut index = inner.index.lock().unwrap();
+                let keys = index.entries.keys().cloned().collect();
+                *index = Index::default();
+                keys
+            };
+            for key in keys {
+                let _ = fs::remove_file(inner.path(&key));
+            }
+        })
+        .await;
+    }
+
+    /// Size, settings and hit/miss counters for diagnostics.
+    pub fn stats(&self) -> Value {
+        let inner = &self.inner;
+        let (entries, bytes) = {
+            let index = inner.index.lock().unwrap();
+            (index.entries.len(), index.bytes)
+        };
+        let load = |counter: &AtomicU64| counter.load(Ordering::Relaxed);
+        serde_json::json!({
+            "dir": inner.dir,
+            "entries": entries,
+            "bytes": bytes,
+            "max_bytes": inner.max_bytes,
+            "hits": load(&inner.counters.hits),
+            "misses": load(&inner.counters.misses),
+            "evicted": load(&inner.counters.evicted),
+        })
+    }
+}
+
+fn remove_files(inner: &Inner, keys: &[String]) {
+    for key in keys {
+        match fs::remove_file(inner.path(key)) {
+            Ok(()) => {
+                inner.counters.evicted.fetch_add(1, Ordering::Relaxed);
+            }
+            Err(e) if e.kind() == ErrorKind::NotFound => {}
+            Err(e) => tracing::warn!("cannot evict disk cache entry {key}: {e}"),
+        }
+    }
+}
@@ -0,0 +1,195 @@
+// Typed errors for the Bitbucket client.
+// Every failed request goes through `BitbucketError::from_response`, so callers can match on
+// the failure kind instead of parsing "Bitbucket API error: ..." strings.
+
+use std::collections::BTreeMap;
+use std::fmt;
+use std::time::Duration;
+use reqwest::StatusCode;
+use super::redact;
+
+/// Maximum number of characters of a non-JSON error body kept in the error message.
+const MAX_BODY_CHARS: usize = 500;
+
+pub type Result<T, E = BitbucketError> = std::result::Result<T, E>;
+
+#[derive(Debug)]
+pub enum BitbucketError {
+    /// 404 - the workspace, repository or object does not exist or is not visible to the token.
+    NotFound { path: String, message: String },
+    /// 401 - the credentials were rejected.
+    Unauthorized { message: String },
+    /// 403 - the credentials are valid but lack permission or token scope.
+    Forbidden { path: String, message: String },
+    /// 429 - the request was throttled. `retry_after` comes from the `Retry-After` header.
+    RateLimited { retry_after: Option<Duration>, message: String },
+    /// 409 - the resource changed or is in a state that does not allow the operation.
+    Conflict { path: String, message: String },
+    /// 400/422 - Bitbucket rejected the request body. `fields` holds per-field errors.
+    Validation { message: String, fields: BTreeMap<String, Vec<String>> },
+    /// Any other non-success status.
+    Api { status: StatusCode, path: String, message: String },
+    /// The request could not be sent or the connection failed.
+    Transport(reqwest::Error),
+    /// The response body could not be decoded.
+    Decode(String),
+    /// Pagination did not terminate within the page limit.
+    Pagination(String),
+    /// The client is missing or has invalid configuration.
+    Config(String),
+    /// The operation is not available on the configured backend.
+    Unsupported(String),
+    /// The server's own policy (e.g. read-only mode) refused the operation before it reached Bitbucket.
+    Denied(String),
+}
+
+impl BitbucketError {
+    /// Builds the error for a non-success response from its status and raw body.
+    /// Secrets in the body are redacted, so the message is safe to log and return to the model.
+    pub fn from_response(status: StatusCode, path: &str, retry_after: Option<Duration>, body: &str) -> Self {
+        let (message, fields) = parse_error_body(&redact::redact_text(body));
+        let path = path.to_string();
+        match status {
+            StatusCode::NOT_FOUND => Self::NotFound { path, message },
+            StatusCode::UNAUTHORIZED => Self::Unauthorized { message },
+            StatusCode::FORBIDDEN => Self::Forbidden { path, message },
+            StatusCode::TOO_MANY_REQUESTS => Self::RateLimited { retry_after, message },
+            StatusCode::CONFLICT => Self::Conflict { path, message },
+            StatusCode::BAD_REQUEST | StatusCode::UNPROCESSABLE_ENTITY => Self::Validation { message, fields },
+            _ => Self::Api { status, path, message },
+        }
+    }
+
+    /// HTTP status of the failed response, if the error came from one.
+    pub fn status(&self) -> Option<StatusCode> {
+        match self {
+            Self::NotFound { .. } => Some(StatusCode::NOT_FOUND),
+            Self::Unauthorized { .. } => Some(StatusCode::UNAUTHORIZED),
+            Self::Forbidden { .. } => Some(StatusCode::FORBIDDEN),
+            Self::RateLimited { .. } => Some(StatusCode::TOO_MANY_REQUESTS),
+            Self::Conflict { .. } => Some(StatusCode::CONFLICT),
+            Self::Validation { .. } => Some(StatusCode::BAD_REQUEST),
+            Self::Api { status, .. } => Some(*status),
+            Self::Transport(e) => e.status(),
+            Self::Decode(_) | Self::Pagination(_) | Self::Config(_) | Self::Unsupported(_) | Self::Denied(_) => None,
+        }
+    }
+}
+
+impl fmt::Display for BitbucketError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::NotFound { path, message } => write!(
+                f,
+                "Not found (404) at {path}: {message}. Check that the workspace, repository slug and id are correct."
+            ),
+            Self::Unauthorized { message } => write!(
+                f,
+                "Unauthorized (401): {message}. Check the Bitbucket credentials (BITBUCKET_AUTH_METHOD and its variables, or the session Authorization header)."
+            ),
+            Self::Forbidden { path, message } => write!(
+                f,
+                "Forbidden (403) at {path}: {message}. The token may lack the scope or permission required for this operation."
+            ),
+            Self::RateLimited { retry_after, message } => {
+                write!(f, "Rate limited (429): {message}")?;
+                if let Some(delay) = retry_after {
+                    write!(f, ". Retry after {}s", delay.as_secs())?;
+                }
+                Ok(())
+            }
+            Self::Conflict { path, message } => write!(f, "Conflict (409) at {path}: {message}"),
+            Self::Validation { message, fields } => {
+                write!(f, "Validation failed (400): {message}")?;
+                if !fields.is_empty() {
+                    let details: Vec<String> = fields
+                        .iter()
+                        .map(|(field, errors)| format!("{field}: {}", errors.join(", ")))
+                        .collect();
+                    write!(f, " [{}]", details.join("; "))?;
+                }
+                Ok(())
+            }
+            Self::Api { status, path, message } => write!(f, "Bitbucket API error ({status}) at {path}: {message}"),
+            Self::Transport(e) => write!(f, "Failed to reach Bitbucket: {e}"),
+            Self::Decode(e) => write!(f, "Failed to decode Bitbucket response: {e}"),
+            Self::Pagination(e) => write!(f, "Pagination error: {e}"),
+            Self::Config(e) => write!(f, "Configuration error: {e}"),
+            Self::Unsupported(e) => write!(f, "Not supported: {e}"),
+            Self::Denied(e) => write!(f, "Denied: {e}"),
+        }
+    }
+}
+
+impl std::error::Error for BitbucketError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            Self::Transport(e) => Some(e),
+            _ => None,
+        }
+    }
+}
+
+impl From<reqwest::Error> for BitbucketError {
+    fn from(e: reqwest::Error) -> Self {
+        if e.is_decode() {
+            Self::Decode(e.to_string())
+        } else {
+            Self::Transport(e)
+        }
+    }
+}
+
+impl From<serde_json::Error> for BitbucketError {
+    fn from(e: serde_json::Error) -> Self {
+        Self::Decode(e.to_string())
+    }
+}
+
+/// Extracts `error.message` (plus `error.detail`) and `error.fields` from a Bitbucket error body.
+///
+/// Bitbucket Cloud returns `{"type": "error", "error": {"message": ..., "detail": ..., "fields": {...}}}`,
+/// but some endpoints return a plain string under `error` or a non-JSON body; those are passed through.
+fn parse_error_body(body: &str) -> (String, BTreeMap<String, Vec<String>>) {
+    let mut fields = BTreeMap::new();
+    let Ok(json) = serde_json::from_str::<serde_json::Value>(body) else {
+        return (truncate(body.trim()), fields);
+    };
+    let error = json.get("error").unwrap_or(&json);
+    if let Some(s) = error.as_str() {
+        return (s.to_string(), fields);
+    }
+    let mut message = error
+        .get("message")
+        .and_then(|v| v.as_str())
+        .map(str::to_string)
+        .unwrap_or_else(|| truncate(body.trim()));
+    if let Some(detail) = error.get("detail").and_then(|v| v.as_str()) {
+        message = format!("{message} - {detail}");
+    }
+    if let Some(map) = error.get("fields").and_then(|v| v.as_object()) {
+        for (field, errors) in map {
+            let errors = match errors {
+                serde_json::Value::Array(items) => items
+                    .iter()
+                    .map(|e| e.as_str().map(str::to_string).unwrap_or_else(|| e.to_string()))
+                    .collect(),
+                serde_json::Value::String(s) => vec![s.clone()],
+                other => vec![other.to_string()],
+            };
+            fields.insert(field.clone(), errors);
+        }
+    }
+    (message, fields)
+}
+
+fn truncate(text: &str) -> String {
+    if text.is_empty() {
+        return "no response body".to_string();
+    }
+    if text.chars().count() <= MAX_BODY_CHARS {
+        return text.to_string();
+    }
+    let truncated: String = text.chars().take(MAX_BODY_CHARS).collect();
+    format!("{truncated}...")
+}
@@ -0,0 +1,128 @@
+// Response shaping for read tools.
+// An explicit `fields` projection is forwarded to Bitbucket as its partial-response `fields=` parameter
+// (https://developer.atlassian.com/cloud/bitbucket/rest/intro/#partial-response). Without one, tools apply
+// a compact profile for the resource type, which strips links, avatars and rendered markup from the output.
+
+use rmcp::schemars;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use super::error::Result;
+use super::pagination;
+
+/// `fields` value that disables both projection and the compact profile.
+pub const FULL: &str = "*";
+
+/// Keys removed at any depth by every compact profile.
+const NOISE_KEYS: &[&str] = &["links", "avatar", "avatar_url", "rendered"];
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize, schemars::JsonSchema)]
+pub struct Projection {
+    /// Bitbucket partial-response projection, e.g. `values.id,values.title`. Omit for a compact
+    /// default that drops links and avatars; `*` returns the full payload.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub fields: Option<String>,
+}
+
+impl Projection {
+    pub fn fields(&self) -> Option<&str> {
+        self.fields.as_deref()
+    }
+}
+
+/// Resource types with their own compact profile.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Resource {
+    PullRequest,
+    Repository,
+    Commit,
+    Issue,
+    Pipeline,
+    Comment,
+    /// Anything else: only the shared noise is removed.
+    Other,
+}
+
+impl Resource {
+    /// Dotted paths dropped from each item on top of `NOISE_KEYS`.
+    fn noise_paths(self) -> &'static [&'static str] {
+        match self {
+            // `summary` repeats `description` as raw, markup and HTML.
+            Resource::PullRequest => &["summary"],
+            Resource::Repository => &["override_settings"],
+            // `summary` repeats `message`.
+            Resource::Commit => &["summary"],
+            Resource::Issue => &["content.html", "content.markup"],
+            // Every pipeline embeds the full repository it belongs to.
+            Resource::Pipeline => &["repository"],
+            Resource::Comment => &["content.html", "content.markup"],
+            Resource::Other => &[],
+        }
+    }
+}
+
+/// Adds a `fields=` projection to `url`. `*` and empty values send nothing.
+///
+/// Listings also need the `next` link to keep paging, so it is added to projections
+/// that would otherwise drop it.
+pub fn with_fields(url: &str, fields: Option<&str>, listing: bool) -> Result<String> {
+    let Some(fields) = fields.map(str::trim).filter(|f| !f.is_empty() && *f != FULL) else {
+        return Ok(url.to_string());
+    };
+    let keeps_next = fields.split(',').map(str::trim).any(|f| f == "next" || f.starts_with('-') || f.starts_with('+'));
+    let fields = if listing && !keeps_next { format!("{fields},next") } else { fields.to_string() };
+    pagination::append_query(url, &[("fields", fields)])
+}
+
+/// Shapes a tool response: projected and full responses are returned as they are,
+/// anything else goes through the compact profile for `resource`.
+pub fn present(value: Value, resource: Resource, fields: Option<&str>) -> Value {
+    if fields.is_some_and(|f| !f.trim().is_empty()) {
+        return value;
+    }
+    compact(value, resource)
+}
+
+/// Applies the compact profile to a single resource or to every item of a listing.
+pub fn compact(mut value: Value, resource: Resource) -> Value {
+    match value.get_mut("values").and_then(Value::as_array_mut) {
+        Some(values) => values.iter_mut().for_each(|item| compact_item(item, resource)),
+        None => compact_item(&mut value, resource),
+    }
+    value
+}
+
+fn compact_item(item: &mut Value, resource: Resource) {
+    strip_noise(item);
+    for path in resource.noise_paths() {
+        remove_path(item, path);
+    }
+}
+
+fn strip_noise(value: &mut Value) {
+    match value {
+        Value::Object(map) => {
+            map.retain(|key, _| !NOISE_KEYS.contains(&key.as_str()));
+            map.values_mut().for_each(strip_noise);
+        }
+        Value::Array(items) => items.iter_mut().for_each(strip_noise),
+        _ => {}
+    }
+}
+
+/// Removes a dotted path, descending into arrays along the way.
+fn remove_path(value: &mut Value, path: &str) {
+    match value {
+        Value::Array(items) => items.iter_mut().for_each(|item| remove_path(item, path)),
+        Value::Object(map) => match path.split_once('.') {
+            Some((head, rest)) => {
+                if let Some(child) = map.get_mut(head) {
+                    remove_path(child, rest);
+                }
+            }
+            None => {
+                map.remove(path);
+            }
+        },
+        _ => {}
+    }
+}
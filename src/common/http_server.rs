@@ -0,0 +1,148 @@
+// HTTP/SSE transport with one MCP session per connection.
+// `GET /sse` opens a session and announces `/message?sessionId=...`, where the client posts its messages.
+// Every session gets its own `BitbucketClient`, built from the `Authorization` header of the SSE request
+// or, when the header is absent, from the server's fallback credentials.
+
+use std::collections::HashMap;
+use std::io;
+use std::sync::Arc;
+use axum::extract::{Json, Query, State};
+use axum::http::header::{AUTHORIZATION, WWW_AUTHENTICATE};
+use axum::http::{HeaderMap, HeaderValue, StatusCode};
+use axum::response::sse::{Event, Sse};
+use axum::response::{IntoResponse, Response};
+use axum::routing::{get, post};
+use axum::Router;
+use futures::{SinkExt, Stream, StreamExt, stream};
+use rmcp::ServiceExt;
+use rmcp::model::ClientJsonRpcMessage;
+use serde::Deserialize;
+use tokio::net::TcpListener;
+use tokio::sync::{RwLock, mpsc};
+use tokio_stream::wrappers::ReceiverStream;
+use tokio_util::sync::{CancellationToken, PollSender};
+use super::auth::Credentials;
+use super::bitbucket::{BitbucketClient, BitbucketTool};
+
+pub const SSE_PATH: &str = "/sse";
+pub const MESSAGE_PATH: &str = "/message";
+
+type SessionId = Arc<str>;
+
+#[derive(Clone)]
+struct Session {
+    tx: mpsc::Sender<ClientJsonRpcMessage>,
+    /// Header the session was opened with; messages must repeat it so a leaked session id is not enough.
+    authorization: Option<HeaderValue>,
+}
+
+#[derive(Clone)]
+struct AppState {
+    base: BitbucketClient,
+    sessions: Arc<RwLock<HashMap<SessionId, Session>>>,
+    ct: CancellationToken,
+}
+
+/// Builds the router. `base` provides the settings and shared rate limiter for every session,
+/// and its credentials (if any) are used for sessions that do not send an `Authorization` header.
+pub fn router(base: BitbucketClient, ct: CancellationToken) -> Router {
+    let state = AppState { base, sessions: Default::default(), ct };
+    Router::new()
+        .route(SSE_PATH, get(sse_handler))
+        .route(MESSAGE_PATH, post(message_handler))
+        .with_state(state)
+}
+
+/// Serves MCP sessions on `listener` until `ct` is cancelled.
+pub async fn serve(listener: TcpListener, base: BitbucketClient, ct: CancellationToken) -> io::Result<()> {
+    let shutdown = ct.clone();
+    axum::serve(listener, router(base, ct))
+        .with_graceful_shutdown(async move { shutdown.cancelled().await })
+        .await
+}
+
+async fn sse_handler(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+) -> Result<Sse<impl Stream<Item = Result<Event, io::Error>>>, Response> {
+    let authorization = headers.get(AUTHORIZATION).cloned();
+    let credentials = match &authorization {
+        Some(value) => {
+            let value = value.to_str().map_err(|_| unauthorized("Authorization header is not valid ASCII"))?;
+            Some(Credentials::from_authorization_header(value).map_err(|e| unauthorized(&e.to_string()))?)
+        }
+        None => state.base.credentials.clone(),
+    };
+    let Some(credentials) = credentials else {
+        return Err(unauthorized("Send Bitbucket credentials in the Authorization header (Basic or Bearer)"));
+    };
+    let client = state.base.clone().with_credentials(Some(credentials));
+
+    let session: SessionId = format!("{:032x}", rand::random::<u128>()).into();
+    let (from_client_tx, from_client_rx) = mpsc::channel(64);
+    let (to_client_tx, to_client_rx) = mpsc::channel(64);
+    state.sessions.write().await.insert(session.clone(), Session { tx: from_client_tx, authorization });
+    tracing::info!(%session, "session opened");
+
+    let ct = state.ct.child_token();
+    let transport = (PollSender::new(to_client_tx).sink_map_err(io::Error::other), ReceiverStream::new(from_client_rx));
+    tokio::spawn({
+        let sessions = state.sessions.clone();
+        let session = session.clone();
+        let ct = ct.clone();
+        async move {
+            match BitbucketTool::new(client).serve_with_ct(transport, ct).await {
+                Ok(service) => {
+                    if let Err(e) = service.waiting().await {
+                        tracing::error!(%session, "session failed: {e}");
+                    }
+                }
+                Err(e) => tracing::error!(%session, "session failed to start: {e}"),
+            }
+            sessions.write().await.remove(&session);
+            tracing::info!(%session, "session closed");
+        }
+    });
+
+    // Dropping the response stream (client disconnect) cancels the session.
+    let guard = ct.drop_guard();
+    let endpoint = Event::default().event("endpoint").data(format!("{MESSAGE_PATH}?sessionId={session}"));
+    let messages = ReceiverStream::new(to_client_rx).map(move |message| {
+        let _ = &guard;
+        let data = serde_json::to_string(&message).map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))?;
+        Ok(Event::default().event("message").data(data))
+    });
+    Ok(Sse::new(stream::once(async { Ok(endpoint) }).chain(messages)))
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct MessageQuery {
+    session_id: String,
+}
+
+async fn message_handler(
+    State(state): State<AppState>,
+    Query(MessageQuery { session_id }): Query<MessageQuery>,
+    headers: HeaderMap,
+    Json(message): Json<ClientJsonRpcMessage>,
+) -> StatusCode {
+    let Some(session) = state.sessions.read().await.get(session_id.as_str()).cloned() else {
+        return StatusCode::NOT_FOUND;
+    };
+    if let Some(expected) = &session.authorization
+        && headers.get(AUTHORIZATION) != Some(expected)
+    {
+        return StatusCode::UNAUTHORIZED;
+    }
+    match session.tx.send(message).await {
+        Ok(()) => StatusCode::ACCEPTED,
+        Err(_) => StatusCode::GONE,
+    }
+}
+
+fn unauthorized(message: &str) -> Response {
+    let mut response = (StatusCode::UNAUTHORIZED, message.to_string()).into_response();
+    response.headers_mut().insert(WWW_AUTHENTICATE, HeaderValue::from_static("Basic realm=\"bitbucket-mcp\""));
+    response
+}
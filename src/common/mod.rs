@@ -1 +1,20 @@
+pub mod audit;
+pub mod auth;
 pub mod bitbucket;
+pub mod cache;
+pub mod config;
+pub mod confirm;
+pub mod datacenter;
+pub mod diagnose;
+pub mod disk_cache;
+pub mod error;
+pub mod fields;
+pub mod http_server;
+pub mod models;
+pub mod pagination;
+pub mod policy;
+pub mod query;
+pub mod rate_limit;
+pub mod redact;
+pub mod retry;
+pub mod scope;
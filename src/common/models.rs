@@ -0,0 +1,499 @@
+// Typed Bitbucket Cloud resources and request bodies.
+// Responses are decoded leniently: every field Bitbucket may omit (or hide behind `fields=`) is optional.
+// Request bodies derive `JsonSchema` so the MCP tools that take them publish the accepted fields.
+
+use rmcp::schemars;
+use serde::{Deserialize, Serialize};
+
+// --- Shared references ---
+
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize, schemars::JsonSchema)]
+#[serde(default)]
+pub struct Account {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub uuid: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub account_id: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub display_name: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub nickname: Option<String>,
+}
+
+/// Identifies a user in a request body, by `uuid` (e.g. `{1234-...}`) or Atlassian `account_id`.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize, schemars::JsonSchema)]
+pub struct AccountRef {
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub uuid: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub account_id: Option<String>,
+}
+
+/// Entry of `/workspaces/{workspace}/members`.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize, schemars::JsonSchema)]
+#[serde(default)]
+pub struct WorkspaceMembership {
+    pub user: Account,
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize, schemars::JsonSchema)]
+pub struct BranchRef {
+    pub name: String,
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize, schemars::JsonSchema)]
+pub struct CommitRef {
+    pub hash: String,
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize, schemars::JsonSchema)]
+#[serde(default)]
+pub struct RepositoryRef {
+    /// `workspace/repo_slug`.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub full_name: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub name: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub uuid: Option<String>,
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize, schemars::JsonSchema)]
+pub struct ProjectRef {
+    pub key: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub name: Option<String>,
+}
+
+/// Text in Bitbucket's rendered-content shape. Only `raw` is needed when sending.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize, schemars::JsonSchema)]
+#[serde(default)]
+pub struct Text {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub raw: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub markup: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub html: Option<String>,
+}
+
+impl Text {
+    pub fn raw(text: impl Into<String>) -> Self {
+        Self { raw: Some(text.into()), ..Self::default() }
+    }
+}
+
+// --- Pull requests ---
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, schemars::JsonSchema)]
+#[serde(rename_all = "SCREAMING_SNAKE_CASE")]
+pub enum PullRequestState {
+    Open,
+    Merged,
+    Declined,
+    Superseded,
+    #[serde(other)]
+    Other,
+}
+
+/// Source or destination of a pull request.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize, schemars::JsonSchema)]
+#[serde(default)]
+pub struct PullRequestEndpoint {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub branch: Option<BranchRef>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub commit: Option<CommitRef>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub repository: Option<RepositoryRef>,
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize, schemars::JsonSchema)]
+#[serde(default)]
+pub struct PullRequest {
+    pub id: u64,
+    pub title: String,
+    pub description: Option<String>,
+    pub state: Option<PullRequestState>,
+    pub draft: Option<bool>,
+    pub author: Option<Account>,
+    pub source: PullRequestEndpoint,
+    pub destination: PullRequestEndpoint,
+    pub merge_commit: Option<CommitRef>,
+    pub reviewers: Vec<Account>,
+    pub close_source_branch: Option<bool>,
+    pub comment_count: Option<u64>,
+    pub task_count: Option<u64>,
+    pub created_on: Option<String>,
+    pub updated_on: Option<String>,
+}
+
+/// Branch side of a pull request in a request body. `repository` is only needed for forks.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize, schemars::JsonSchema)]
+pub struct BranchEndpoint {
+    pub branch: BranchRef,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub repository: Option<RepositoryRef>,
+}
+
+impl BranchEndpoint {
+    pub fn branch(name: impl Into<String>) -> Self {
+        Self { branch: BranchRef { name: name.into() }, repository: None }
+    }
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize, schemars::JsonSchema)]
+pub struct CreatePullRequest {
+    pub title: String,
+    pub source: BranchEndpoint,
+    /// Defaults to the repository's main branch.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub destination: Option<BranchEndpoint>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub description: Option<String>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub reviewers: Vec<AccountRef>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub close_source_branch: Option<bool>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub draft: Option<bool>,
+}
+
+/// Fields to change on a pull request; omitted fields are left as they are.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize, schemars::JsonSchema)]
+pub struct UpdatePullRequest {
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub title: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub description: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub destination: Option<BranchEndpoint>,
+    /// Replaces the full reviewer list.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub reviewers: Option<Vec<AccountRef>>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub close_source_branch: Option<bool>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub draft: Option<bool>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, schemars::JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum MergeStrategy {
+    MergeCommit,
+    Squash,
+    FastForward,
+    SquashFastForward,
+    RebaseFastForward,
+    RebaseMerge,
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize, schemars::JsonSchema)]
+pub struct MergePullRequest {
+    /// Merge commit message.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub message: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub close_source_branch: Option<bool>,
+    /// Defaults to the repository's configured strategy.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub merge_strategy: Option<MergeStrategy>,
+}
+
+// --- Repositories ---
+
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize, schemars::JsonSchema)]
+#[serde(default)]
+pub struct Repository {
+    pub uuid: Option<String>,
+    pub slug: Option<String>,
+    pub name: Option<String>,
+    pub full_name: Option<String>,
+    pub description: Option<String>,
+    pub is_private: Option<bool>,
+    pub scm: Option<String>,
+    pub language: Option<String>,
+    pub mainbranch: Option<BranchRef>,
+    pub project: Option<ProjectRef>,
+    pub owner: Option<Account>,
+    pub size: Option<u64>,
+    pub has_issues: Option<bool>,
+    pub has_wiki: Option<bool>,
+    pub fork_policy: Option<String>,
+    pub created_on: Option<String>,
+    pub updated_on: Option<String>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, schemars::JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum ForkPolicy {
+    AllowForks,
+    NoPublicForks,
+    NoForks,
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize, schemars::JsonSchema)]
+pub struct CreateRepository {
+    /// Defaults to `git`, the only SCM Bitbucket Cloud supports.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub scm: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub is_private: Option<bool>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub description: Option<String>,
+    /// Project to create the repository in. Defaults to the workspace's oldest project.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub project: Option<ProjectRef>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub fork_policy: Option<ForkPolicy>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub language: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub has_issues: Option<bool>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub has_wiki: Option<bool>,
+}
+
+// --- Commits ---
+
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize, schemars::JsonSchema)]
+#[serde(default)]
+pub struct CommitAuthor {
+    /// `Name <email>` as recorded in the commit.
+    pub raw: Option<String>,
+    /// Bitbucket account the author email maps to, if any.
+    pub user: Option<Account>,
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize, schemars::JsonSchema)]
+#[serde(default)]
+pub struct Commit {
+    pub hash: String,
+    pub message: Option<String>,
+    pub date: Option<String>,
+    pub author: Option<CommitAuthor>,
+    pub parents: Vec<CommitRef>,
+}
+
+// --- Issues ---
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, schemars::JsonSchema)]
+#[serde(rename_all = "lowercase")]
+pub enum IssueKind {
+    Bug,
+    Enhancement,
+    Proposal,
+    Task,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, schemars::JsonSchema)]
+#[serde(rename_all = "lowercase")]
+pub enum IssuePriority {
+    Trivial,
+    Minor,
+    Major,
+    Critical,
+    Blocker,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, schemars::JsonSchema)]
+#[serde(rename_all = "lowercase")]
+pub enum IssueState {
+    New,
+    Open,
+    Resolved,
+    #[serde(rename = "on hold")]
+    OnHold,
+    Invalid,
+    Duplicate,
+    Wontfix,
+    Closed,
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize, schemars::JsonSchema)]
+#[serde(default)]
+pub struct Issue {
+    pub id: u64,
+    pub title: String,
+    pub content: Option<Text>,
+    pub state: Option<String>,
+    pub kind: Option<String>,
+    pub priority: Option<String>,
+    pub reporter: Option<Account>,
+    pub assignee: Option<Account>,
+    pub votes: Option<u64>,
+    pub created_on: Option<String>,
+    pub updated_on: Option<String>,
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize, schemars::JsonSchema)]
+pub struct CreateIssue {
+    pub title: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub content: Option<Text>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub kind: Option<IssueKind>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub priority: Option<IssuePriority>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub assignee: Option<AccountRef>,
+}
+
+/// Fields to change on an issue; omitted fields are left as they are.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize, schemars::JsonSchema)]
+pub struct UpdateIssue {
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub title: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub content: Option<Text>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub state: Option<IssueState>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub kind: Option<IssueKind>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub priority: Option<IssuePriority>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub assignee: Option<AccountRef>,
+}
+
+// --- Pipelines ---
+
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize, schemars::JsonSchema)]
+#[serde(default)]
+pub struct PipelineStateResult {
+    /// e.g. `SUCCESSFUL`, `FAILED`, `STOPPED`.
+    pub name: Option<String>,
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize, schemars::JsonSchema)]
+#[serde(default)]
+pub struct PipelineState {
+    /// `PENDING`, `IN_PROGRESS` or `COMPLETED`.
+    pub name: Option<String>,
+    /// Set once the pipeline has completed.
+    pub result: Option<PipelineStateResult>,
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize, schemars::JsonSchema)]
+#[serde(default)]
+pub struct PipelineTarget {
+    pub ref_type: Option<String>,
+    pub ref_name: Option<String>,
+    pub commit: Option<CommitRef>,
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize, schemars::JsonSchema)]
+#[serde(default)]
+pub struct Pipeline {
+    pub uuid: Option<String>,
+    pub build_number: Option<u64>,
+    pub state: Option<PipelineState>,
+    pub target: Option<PipelineTarget>,
+    pub creator: Option<Account>,
+    pub created_on: Option<String>,
+    pub completed_on: Option<String>,
+    pub duration_in_seconds: Option<u64>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, schemars::JsonSchema)]
+#[serde(rename_all = "lowercase")]
+pub enum RefType {
+    Branch,
+    Tag,
+}
+
+/// Custom pipeline to run instead of the default one for the ref.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize, schemars::JsonSchema)]
+pub struct PipelineSelector {
+    /// `custom`, `branches`, `tags`, `bookmarks` or `pull-requests`.
+    #[serde(rename = "type")]
+    pub selector_type: String,
+    pub pattern: String,
+}
+
+/// Runs the pipeline for the head of a branch or tag.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, schemars::JsonSchema)]
+pub struct PipelineRefTarget {
+    pub ref_type: RefType,
+    pub ref_name: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub selector: Option<PipelineSelector>,
+}
+
+impl PipelineRefTarget {
+    pub fn branch(name: impl Into<String>) -> Self {
+        Self { ref_type: RefType::Branch, ref_name: name.into(), selector: None }
+    }
+}
+
+/// Runs the pipeline for a specific commit; usually combined with a custom `selector`.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, schemars::JsonSchema)]
+pub struct PipelineCommitTarget {
+    pub commit: CommitRef,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub selector: Option<PipelineSelector>,
+}
+
+/// Runs the pull request pipeline for `source` merged into `destination`.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, schemars::JsonSchema)]
+pub struct PipelinePullRequestTarget {
+    /// Source branch.
+    pub source: String,
+    /// Destination branch.
+    pub destination: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub destination_commit: Option<CommitRef>,
+    /// Head commit of the source branch.
+    pub commit: CommitRef,
+    pub pullrequest: PipelinePullRequestId,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub selector: Option<PipelineSelector>,
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize, schemars::JsonSchema)]
+pub struct PipelinePullRequestId {
+    pub id: String,
+}
+
+/// What a triggered pipeline runs against, tagged by Bitbucket's `type`.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, schemars::JsonSchema)]
+#[serde(tag = "type")]
+pub enum PipelineTriggerTarget {
+    #[serde(rename = "pipeline_ref_target")]
+    Ref(PipelineRefTarget),
+    #[serde(rename = "pipeline_commit_target")]
+    Commit(PipelineCommitTarget),
+    #[serde(rename = "pipeline_pullrequest_target")]
+    PullRequest(PipelinePullRequestTarget),
+}
+
+impl From<PipelineRefTarget> for PipelineTriggerTarget {
+    fn from(target: PipelineRefTarget) -> Self {
+        Self::Ref(target)
+    }
+}
+
+/// Reads a trigger target, treating one without `type` as a ref target.
+fn deserialize_trigger_target<'de, D: serde::Deserializer<'de>>(deserializer: D) -> Result<PipelineTriggerTarget, D::Error> {
+    let mut target = serde_json::Value::deserialize(deserializer)?;
+    if let Some(map) = target.as_object_mut() {
+        map.entry("type").or_insert_with(|| "pipeline_ref_target".into());
+    }
+    PipelineTriggerTarget::deserialize(target).map_err(serde::de::Error::custom)
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize, schemars::JsonSchema)]
+pub struct PipelineVariable {
+    pub key: String,
+    pub value: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub secured: Option<bool>,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, schemars::JsonSchema)]
+pub struct TriggerPipeline {
+    #[serde(deserialize_with = "deserialize_trigger_target")]
+    pub target: PipelineTriggerTarget,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub variables: Vec<PipelineVariable>,
+}
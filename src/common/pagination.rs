@@ -0,0 +1,70 @@
+// Pagination options shared by every list_* method and tool.
+// Bitbucket pages carry a `next` link; `BitbucketClient::paginate_pages` follows it lazily and
+// `fetch_paginated` aggregates the pages until the listing ends or one of these bounds is hit,
+// returning the link as a cursor so the caller can resume.
+
+use rmcp::schemars;
+use serde::{Deserialize, Serialize};
+use super::error::{BitbucketError, Result};
+
+/// Largest page size accepted by most Bitbucket Cloud endpoints.
+pub const MAX_PAGELEN: u32 = 100;
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize, schemars::JsonSchema)]
+pub struct PageOptions {
+    /// Stop requesting further pages once at least this many values have been collected.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub limit: Option<usize>,
+    /// Number of values per page (Bitbucket `pagelen`, max 100). Defaults to `limit` when it is 100 or less.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub pagelen: Option<u32>,
+    /// Where to start: a page number, or the `next` cursor returned by a previous call.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub page: Option<String>,
+    /// Maximum number of pages to fetch in this call. A `next` cursor is returned if more remain.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub max_pages: Option<usize>,
+}
+
+impl PageOptions {
+    /// Page size to request, if any.
+    pub fn effective_pagelen(&self) -> Option<u32> {
+        let pagelen = self.pagelen.or_else(|| {
+            self.limit
+                .map(|limit| u32::try_from(limit).unwrap_or(MAX_PAGELEN).min(MAX_PAGELEN))
+        })?;
+        Some(pagelen.clamp(1, MAX_PAGELEN))
+    }
+
+    /// Whether `page` is a `next` link rather than a page number.
+    pub fn is_cursor(&self) -> bool {
+        self.page
+            .as_deref()
+            .is_some_and(|page| page.starts_with("http://") || page.starts_with("https://"))
+    }
+}
+
+/// One page of a Bitbucket listing.
+#[derive(Debug, Clone, Deserialize)]
+pub struct Page<T = serde_json::Value> {
+    #[serde(default = "Vec::new")]
+    pub values: Vec<T>,
+    /// Link to the following page, absent on the last one.
+    #[serde(default)]
+    pub next: Option<String>,
+}
+
+/// Appends query parameters to `url`, keeping any that are already present.
+pub fn append_query(url: &str, pairs: &[(&str, String)]) -> Result<String> {
+    if pairs.is_empty() {
+        return Ok(url.to_string());
+    }
+    let mut parsed = reqwest::Url::parse(url).map_err(|e| BitbucketError::Config(format!("invalid URL '{url}': {e}")))?;
+    {
+        let mut query = parsed.query_pairs_mut();
+        for (key, value) in pairs {
+            query.append_pair(key, value);
+        }
+    }
+    Ok(parsed.to_string())
+}
@@ -0,0 +1,168 @@
+// Which tools `BitbucketTool` advertises and accepts.
+// Tools are grouped into toolsets that can be enabled as a whole, with explicit allow and deny lists
+// on top. In read-only mode every tool that changes Bitbucket state is hidden from `list_tools` and
+// rejected by `call_tool`; the client also refuses non-GET requests on its own as a second line of defense.
+
+use std::collections::BTreeSet;
+use std::fmt;
+use std::str::FromStr;
+use serde::{Deserialize, Serialize};
+use super::config::ClientConfig;
+use super::error::{BitbucketError, Result};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Toolset {
+    /// Current user, workspaces and server diagnostics. Always enabled.
+    Core,
+    PullRequests,
+    Repos,
+    Pipelines,
+    Issues,
+    /// Repository and project administration, webhooks, branch restrictions and members.
+    Admin,
+    Snippets,
+}
+
+impl Toolset {
+    pub const ALL: [Toolset; 7] = [Self::Core, Self::PullRequests, Self::Repos, Self::Pipelines, Self::Issues, Self::Admin, Self::Snippets];
+
+    /// The toolset a tool belongs to, or `None` for an unknown tool name.
+    pub fn of(tool: &str) -> Option<Self> {
+        Some(match tool {
+            "get_user" | "list_workspaces" | "get_workspace" | "get_rate_limit_status" | "get_diagnostics"
+            | "read_audit_log" | "diagnose_auth" => Self::Core,
+            "list_pullrequests" | "create_pullrequest" | "get_pullrequest" | "update_pullrequest" | "approve_pullrequest"
+            | "unapprove_pullrequest" | "decline_pullrequest" | "merge_pullrequest" | "list_pullrequest_comments"
+            | "add_pullrequest_comment" | "list_pullrequest_activity" | "get_pullrequest_diff" | "list_pullrequest_commits"
+            | "list_pullrequest_tasks" | "add_pullrequest_task" | "get_pullrequest_diffstat" => Self::PullRequests,
+            "list_repositories" | "get_repository" | "list_branches" | "create_branch" | "delete_branch" | "list_tags"
+            | "list_commits" | "get_commit" | "get_file_source" | "list_commit_statuses" | "create_commit_status"
+            | "list_downloads" => Self::Repos,
+            "list_pipelines" | "trigger_pipeline" | "list_deployments" | "create_deployment" => Self::Pipelines,
+            "list_issues" | "create_issue" | "update_issue" | "delete_issue" => Self::Issues,
+            "create_repository" | "update_repository" | "delete_repository" | "list_webhooks" | "list_branch_restrictions"
+            | "get_branching_model" | "update_branching_model" | "list_projects" | "create_project" | "update_project"
+            | "delete_project" | "list_users" => Self::Admin,
+            "list_snippets" | "create_snippet" | "delete_snippet" => Self::Snippets,
+            _ => return None,
+        })
+    }
+}
+
+impl FromStr for Toolset {
+    type Err = BitbucketError;
+
+    fn from_str(s: &str) -> Result<Self> {
+        let name = s.trim().to_ascii_lowercase();
+        Self::ALL
+            .into_iter()
+            .find(|toolset| toolset.to_string() == name)
+            .ok_or_else(|| BitbucketError::Config(format!(
+                "unknown toolset '{name}', expected one of core, pullrequests, repos, pipelines, issues, admin, snippets"
+            )))
+    }
+}
+
+impl fmt::Display for Toolset {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let name = match self {
+            Self::Core => "core",
+            Self::PullRequests => "pullrequests",
+            Self::Repos => "repos",
+            Self::Pipelines => "pipelines",
+            Self::Issues => "issues",
+            Self::Admin => "admin",
+            Self::Snippets => "snippets",
+        };
+        f.write_str(name)
+    }
+}
+
+/// Tools that create, change or delete Bitbucket state.
+pub const MUTATING_TOOLS: &[&str] = &[
+    "create_pullrequest",
+    "update_pullrequest",
+    "approve_pullrequest",
+    "unapprove_pullrequest",
+    "decline_pullrequest",
+    "merge_pullrequest",
+    "add_pullrequest_comment",
+    "add_pullrequest_task",
+    "create_repository",
+    "update_repository",
+    "delete_repository",
+    "create_branch",
+    "delete_branch",
+    "update_branching_model",
+    "create_commit_status",
+    "create_deployment",
+    "create_issue",
+    "update_issue",
+    "delete_issue",
+    "trigger_pipeline",
+    "create_project",
+    "update_project",
+    "delete_project",
+    "create_snippet",
+    "delete_snippet",
+];
+
+pub fn is_mutating(tool: &str) -> bool {
+    MUTATING_TOOLS.contains(&tool)
+}
+
+/// Decides which tools are enabled. The deny list and read-only mode always win; otherwise a tool
+/// is enabled when its toolset is, when it is on the allow list, or when it is a `core` tool.
+/// Without `toolsets`, every toolset is enabled unless an allow list is given, in which case
+/// only the allowed (and `core`) tools are.
+#[derive(Debug, Clone, Default)]
+pub struct ToolPolicy {
+    /// Only expose tools that read from Bitbucket.
+    pub read_only: bool,
+    /// Enabled toolsets; `None` enables all of them.
+    pub toolsets: Option<BTreeSet<Toolset>>,
+    pub allow: BTreeSet<String>,
+    pub deny: BTreeSet<String>,
+}
+
+impl ToolPolicy {
+    pub fn read_only() -> Self {
+        Self { read_only: true, ..Self::default() }
+    }
+
+    /// Policy described by a validated config.
+    pub fn from_config(config: &ClientConfig) -> Self {
+        Self {
+            read_only: config.read_only,
+            toolsets: config.toolsets.as_ref().map(|sets| sets.iter().copied().collect()),
+            allow: config.allow_tools.iter().cloned().collect(),
+            deny: config.deny_tools.iter().cloned().collect(),
+        }
+    }
+
+    /// Returns the reason `tool` is disabled, if it is.
+    pub fn check(&self, tool: &str) -> Result<()> {
+        if self.deny.contains(tool) {
+            return Err(BitbucketError::Denied(format!("tool '{tool}' is disabled by the deny list")));
+        }
+        if self.read_only && is_mutating(tool) {
+            return Err(BitbucketError::Denied(format!("tool '{tool}' is disabled because the server is in read-only mode")));
+        }
+        let toolset = Toolset::of(tool);
+        let toolset_enabled = match &self.toolsets {
+            _ if toolset == Some(Toolset::Core) => true,
+            None => self.allow.is_empty(),
+            Some(enabled) => toolset.is_some_and(|t| enabled.contains(&t)),
+        };
+        if !toolset_enabled && !self.allow.contains(tool) {
+            let hint = toolset.map_or(String::new(), |t| format!("; enable the '{t}' toolset or add it to the allow list"));
+            return Err(BitbucketError::Denied(format!("tool '{tool}' is not enabled{hint}")));
+        }
+        Ok(())
+    }
+
+    pub fn allows(&self, tool: &str) -> bool {
+        self.check(tool).is_ok()
+    }
+}
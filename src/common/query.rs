@@ -0,0 +1,247 @@
+// Filtering and sorting for list endpoints.
+// `Filter` carries the optional `q`, `sort` and `state` tool parameters; `Query` builds
+// Bitbucket query language expressions (https://developer.atlassian.com/cloud/bitbucket/rest/intro/#filtering)
+// with values quoted and escaped, e.g. `Query::field("state").eq("OPEN").and(Query::field("title").contains("fix"))`.
+
+use std::fmt;
+use rmcp::schemars;
+use serde::{Deserialize, Serialize};
+use super::error::{BitbucketError, Result};
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize, schemars::JsonSchema)]
+pub struct Filter {
+    /// Bitbucket query language filter, e.g. `title ~ "fix" AND author.nickname = "jane"`.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub q: Option<String>,
+    /// Field to sort by, prefixed with `-` for descending order, e.g. `-updated_on`.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub sort: Option<String>,
+    /// Only return items in this state. Several states may be separated by commas, e.g. `OPEN,MERGED`.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub state: Option<String>,
+}
+
+/// How an endpoint accepts the `state` filter.
+#[derive(Debug, Clone, Copy)]
+pub enum StateFilter {
+    /// Repeated `state=` query parameters (pull requests).
+    Param,
+    /// Folded into `q` as a comparison on this field, e.g. `state` for issues.
+    Field(&'static str),
+    /// The endpoint has no state; `q` must be used instead.
+    Unsupported(&'static str),
+}
+
+impl Filter {
+    /// Filter with `q` taken from a built query.
+    pub fn query(query: Query) -> Self {
+        Self { q: Some(query.to_string()), ..Self::default() }
+    }
+
+    /// Query parameters for this filter, in the order they are sent.
+    pub fn query_pairs(&self, state: StateFilter) -> Result<Vec<(&'static str, String)>> {
+        let states: Vec<&str> = self
+            .state
+            .iter()
+            .flat_map(|s| s.split(','))
+            .map(str::trim)
+            .filter(|s| !s.is_empty())
+            .collect();
+        let mut q = self.q.as_deref().map(str::trim).filter(|q| !q.is_empty()).map(Query::raw);
+        let mut pairs = Vec::new();
+        if !states.is_empty() {
+            match state {
+                StateFilter::Param => pairs.extend(states.iter().map(|s| ("state", s.to_string()))),
+                StateFilter::Field(field) => {
+                    let by_state = states
+                        .iter()
+                        .map(|s| Query::field(field).eq(*s))
+                        .reduce(Query::or)
+                        .expect("states is not empty");
+                    q = Some(match q {
+                        Some(q) => q.and(by_state),
+                        None => by_state,
+                    });
+                }
+                StateFilter::Unsupported(resource) => {
+                    return Err(BitbucketError::Validation {
+                        message: format!("`state` is not supported when listing {resource}; use `q` instead"),
+                        fields: Default::default(),
+                    });
+                }
+            }
+        }
+        if let Some(q) = q {
+            pairs.push(("q", q.to_string()));
+        }
+        if let Some(sort) = self.sort.as_deref().map(str::trim).filter(|s| !s.is_empty()) {
+            pairs.push(("sort", sort.to_string()));
+        }
+        Ok(pairs)
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Combinator {
+    And,
+    Or,
+    /// Caller-supplied text whose precedence is unknown.
+    Raw,
+}
+
+/// A Bitbucket query language expression.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Query {
+    expr: String,
+    combinator: Option<Combinator>,
+}
+
+/// Left-hand side of a comparison; turned into a `Query` by one of its operators.
+#[derive(Debug, Clone)]
+pub struct Field(String);
+
+/// A literal on the right-hand side of a comparison.
+#[derive(Debug, Clone, PartialEq)]
+pub enum QueryValue {
+    String(String),
+    Number(String),
+    Bool(bool),
+    Null,
+}
+
+impl Query {
+    pub fn field(name: impl Into<String>) -> Field {
+        Field(name.into())
+    }
+
+    /// Wraps an expression written by hand. It is parenthesised when combined with others.
+    pub fn raw(expr: impl Into<String>) -> Self {
+        Self { expr: expr.into(), combinator: Some(Combinator::Raw) }
+    }
+
+    pub fn and(self, other: Query) -> Self {
+        self.combine(other, Combinator::And)
+    }
+
+    pub fn or(self, other: Query) -> Self {
+        self.combine(other, Combinator::Or)
+    }
+
+    fn combine(self, other: Query, combinator: Combinator) -> Self {
+        let keyword = if combinator == Combinator::And { "AND" } else { "OR" };
+        Self {
+            expr: format!("{} {keyword} {}", self.operand(combinator), other.operand(combinator)),
+            combinator: Some(combinator),
+        }
+    }
+
+    fn operand(self, parent: Combinator) -> String {
+        match self.combinator {
+            Some(own) if own != parent || own == Combinator::Raw => format!("({})", self.expr),
+            _ => self.expr,
+        }
+    }
+}
+
+impl fmt::Display for Query {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.expr)
+    }
+}
+
+impl Field {
+    fn compare(self, op: &str, value: impl Into<QueryValue>) -> Query {
+        Query { expr: format!("{} {op} {}", self.0, value.into()), combinator: None }
+    }
+
+    pub fn eq(self, value: impl Into<QueryValue>) -> Query {
+        self.compare("=", value)
+    }
+
+    pub fn ne(self, value: impl Into<QueryValue>) -> Query {
+        self.compare("!=", value)
+    }
+
+    /// Case-insensitive substring match (`~`).
+    pub fn contains(self, value: impl Into<String>) -> Query {
+        self.compare("~", QueryValue::String(value.into()))
+    }
+
+    pub fn not_contains(self, value: impl Into<String>) -> Query {
+        self.compare("!~", QueryValue::String(value.into()))
+    }
+
+    pub fn gt(self, value: impl Into<QueryValue>) -> Query {
+        self.compare(">", value)
+    }
+
+    pub fn gte(self, value: impl Into<QueryValue>) -> Query {
+        self.compare(">=", value)
+    }
+
+    pub fn lt(self, value: impl Into<QueryValue>) -> Query {
+        self.compare("<", value)
+    }
+
+    pub fn lte(self, value: impl Into<QueryValue>) -> Query {
+        self.compare("<=", value)
+    }
+
+    pub fn is_null(self) -> Query {
+        self.compare("=", QueryValue::Null)
+    }
+}
+
+impl fmt::Display for QueryValue {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            QueryValue::String(s) => {
+                f.write_str("\"")?;
+                for c in s.chars() {
+                    match c {
+                        '"' => f.write_str("\\\"")?,
+                        '\\' => f.write_str("\\\\")?,
+                        '\n' => f.write_str("\\n")?,
+                        '\r' => f.write_str("\\r")?,
+                        '\t' => f.write_str("\\t")?,
+                        c => write!(f, "{c}")?,
+                    }
+                }
+                f.write_str("\"")
+            }
+            QueryValue::Number(n) => f.write_str(n),
+            QueryValue::Bool(b) => write!(f, "{b}"),
+            QueryValue::Null => f.write_str("null"),
+        }
+    }
+}
+
+impl From<&str> for QueryValue {
+    fn from(value: &str) -> Self {
+        QueryValue::String(value.to_string())
+    }
+}
+
+impl From<String> for QueryValue {
+    fn from(value: String) -> Self {
+        QueryValue::String(value)
+    }
+}
+
+impl From<bool> for QueryValue {
+    fn from(value: bool) -> Self {
+        QueryValue::Bool(value)
+    }
+}
+
+macro_rules! number_query_value {
+    ($($t:ty),*) => {
+        $(impl From<$t> for QueryValue {
+            fn from(value: $t) -> Self {
+                QueryValue::Number(value.to_string())
+            }
+        })*
+    };
+}
+
+number_query_value!(i32, i64, u32, u64, usize, f64);
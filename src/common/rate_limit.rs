@@ -0,0 +1,191 @@
+// Client-side rate limiting shared by every clone of a `BitbucketClient`.
+// A token bucket spaces requests out, a semaphore caps the number of requests in flight,
+// and the latest `X-RateLimit-*` headers from Bitbucket are kept for the `get_rate_limit_status` tool.
+
+use std::sync::Mutex;
+use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
+use reqwest::header::HeaderMap;
+use serde::Serialize;
+use tokio::sync::{Semaphore, SemaphorePermit};
+use super::error::Result;
+use super::retry::env_parse;
+
+#[derive(Debug, Clone, Serialize)]
+pub struct RateLimitConfig {
+    /// Sustained request rate. `None` disables the token bucket.
+    pub requests_per_second: Option<f64>,
+    /// Number of requests that may be sent back to back before the rate applies.
+    pub burst: u32,
+    /// Maximum number of requests in flight at once.
+    pub max_concurrent: usize,
+}
+
+impl Default for RateLimitConfig {
+    fn default() -> Self {
+        Self {
+            requests_per_second: Some(10.0),
+            burst: 20,
+            max_concurrent: 8,
+        }
+    }
+}
+
+impl RateLimitConfig {
+    /// No token bucket and effectively no concurrency cap.
+    pub fn unlimited() -> Self {
+        Self {
+            requests_per_second: None,
+            burst: 1,
+            max_concurrent: Semaphore::MAX_PERMITS,
+        }
+    }
+
+    /// Reads `BITBUCKET_RATE_LIMIT_PER_SECOND` (`0` disables the bucket), `BITBUCKET_RATE_LIMIT_BURST`
+    /// and `BITBUCKET_MAX_CONCURRENT_REQUESTS`, falling back to the defaults.
+    pub fn from_env() -> Result<Self> {
+        let default = Self::default();
+        let requests_per_second = match env_parse::<f64>("BITBUCKET_RATE_LIMIT_PER_SECOND")? {
+            Some(rate) if rate > 0.0 => Some(rate),
+            Some(_) => None,
+            None => default.requests_per_second,
+        };
+        Ok(Self {
+            requests_per_second,
+            burst: env_parse("BITBUCKET_RATE_LIMIT_BURST")?.unwrap_or(default.burst).max(1),
+            max_concurrent: env_parse("BITBUCKET_MAX_CONCURRENT_REQUESTS")?.unwrap_or(default.max_concurrent).max(1),
+        })
+    }
+}
+
+/// Rate-limit budget reported by Bitbucket in the most recent response.
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct RateLimitStatus {
+    pub limit: Option<u64>,
+    pub remaining: Option<u64>,
+    /// Epoch seconds at which the budget resets.
+    pub reset: Option<u64>,
+    pub resource: Option<String>,
+    pub near_limit: Option<bool>,
+    /// Epoch seconds at which these headers were observed.
+    pub observed_at: u64,
+}
+
+impl RateLimitStatus {
+    /// Parses the `X-RateLimit-*` headers, returning `None` when the response carried none.
+    pub fn from_headers(headers: &HeaderMap) -> Option<Self> {
+        let text = |name: &str| headers.get(name).and_then(|v| v.to_str().ok()).map(|v| v.trim().to_string());
+        let number = |name: &str| text(name).and_then(|v| v.parse::<u64>().ok());
+        let status = Self {
+            limit: number("x-ratelimit-limit"),
+            remaining: number("x-ratelimit-remaining"),
+            reset: number("x-ratelimit-reset"),
+            resource: text("x-ratelimit-resource"),
+            near_limit: text("x-ratelimit-nearlimit").map(|v| v.eq_ignore_ascii_case("true")),
+            observed_at: SystemTime::now().duration_since(UNIX_EPOCH).map(|d| d.as_secs()).unwrap_or_default(),
+        };
+        let any = status.limit.is_some()
+            || status.remaining.is_some()
+            || status.reset.is_some()
+            || status.resource.is_some()
+            || status.near_limit.is_some();
+        any.then_some(status)
+    }
+}
+
+struct TokenBucket {
+    rate: f64,
+    capacity: f64,
+    tokens: f64,
+    last_refill: Instant,
+}
+
+impl TokenBucket {
+    /// Takes a token if one is available, otherwise returns how long until one will be.
+    fn try_take(&mut self) -> Option<Duration> {
+        let now = Instant::now();
+        let elapsed = now.duration_since(self.last_refill).as_secs_f64();
+        self.tokens = (self.tokens + elapsed * self.rate).min(self.capacity);
+        self.last_refill = now;
+        if self.tokens >= 1.0 {
+            self.tokens -= 1.0;
+            None
+        } else {
+            Some(Duration::from_secs_f64((1.0 - self.tokens) / self.rate))
+        }
+    }
+
+    fn available(&self) -> f64 {
+        let elapsed = self.last_refill.elapsed().as_secs_f64();
+        (self.tokens + elapsed * self.rate).min(self.capacity)
+    }
+}
+
+pub struct RateLimiter {
+    config: RateLimitConfig,
+    bucket: Option<Mutex<TokenBucket>>,
+    in_flight: Semaphore,
+    last_status: Mutex<Option<RateLimitStatus>>,
+}
+
+impl RateLimiter {
+    pub fn new(config: RateLimitConfig) -> Self {
+        let bucket = config.requests_per_second.map(|rate| {
+            Mutex::new(TokenBucket {
+                rate,
+                capacity: f64::from(config.burst),
+                tokens: f64::from(config.burst),
+                last_refill: Instant::now(),
+            })
+        });
+        Self {
+            in_flight: Semaphore::new(config.max_concurrent),
+            config,
+            bucket,
+            last_status: Mutex::new(None),
+        }
+    }
+
+    /// Waits for a token and a concurrency slot. The request may be sent while the permit is held.
+    pub async fn acquire(&self) -> SemaphorePermit<'_> {
+        if let Some(bucket) = &self.bucket {
+            loop {
+                let wait = bucket.lock().expect("rate limiter lock poisoned").try_take();
+                match wait {
+                    None => break,
+                    Some(delay) => tokio::time::sleep(delay).await,
+                }
+            }
+        }
+        self.in_flight.acquire().await.expect("rate limiter semaphore closed")
+    }
+
+    /// Records the `X-RateLimit-*` headers of a response, if present.
+    pub fn observe(&self, headers: &HeaderMap) {
+        if let Some(status) = RateLimitStatus::from_headers(headers) {
+            *self.last_status.lock().expect("rate limiter lock poisoned") = Some(status);
+        }
+    }
+
+    /// Latest budget reported by Bitbucket.
+    pub fn last_status(&self) -> Option<RateLimitStatus> {
+        self.last_status.lock().expect("rate limiter lock poisoned").clone()
+    }
+
+    /// Client-side limiter state together with the last server-reported budget.
+    pub fn snapshot(&self) -> serde_json::Value {
+        let available_tokens = self
+            .bucket
+            .as_ref()
+            .map(|b| b.lock().expect("rate limiter lock poisoned").available().floor());
+        serde_json::json!({
+            "client": {
+                "requests_per_second": self.config.requests_per_second,
+                "burst": self.config.burst,
+                "available_tokens": available_tokens,
+                "max_concurrent": self.config.max_concurrent,
+                "in_flight": self.config.max_concurrent - self.in_flight.available_permits(),
+            },
+            "server": self.last_status(),
+        })
+    }
+}
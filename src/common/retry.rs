@@ -0,0 +1,135 @@
+// Retry policy for transient Bitbucket failures.
+// `BitbucketClient::execute` consults this policy for 429 and 5xx responses and for
+// connection errors, waiting for `Retry-After` / `X-RateLimit-Reset` when Bitbucket provides them.
+
+use std::env;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+use reqwest::header::{HeaderMap, RETRY_AFTER};
+use reqwest::{Method, StatusCode};
+use super::error::{BitbucketError, Result};
+
+#[derive(Debug, Clone)]
+pub struct RetryPolicy {
+    /// Number of retries after the first attempt. `0` disables retries.
+    pub max_retries: u32,
+    /// Delay before the first retry; doubled on every further attempt.
+    pub base_delay: Duration,
+    /// Upper bound for any single wait, including server-provided `Retry-After` values.
+    pub max_delay: Duration,
+    /// Also retry POST and PATCH requests, which may not be safe to repeat.
+    pub retry_non_idempotent: bool,
+}
+
+impl Default for RetryPolicy {
+    fn default() -> Self {
+        Self {
+            max_retries: 3,
+            base_delay: Duration::from_millis(500),
+            max_delay: Duration::from_secs(30),
+            retry_non_idempotent: false,
+        }
+    }
+}
+
+impl RetryPolicy {
+    /// A policy that never retries.
+    pub fn none() -> Self {
+        Self { max_retries: 0, ..Self::default() }
+    }
+
+    /// Reads the policy from environment variables, falling back to the defaults:
+    /// `BITBUCKET_MAX_RETRIES`, `BITBUCKET_RETRY_BASE_DELAY_MS`, `BITBUCKET_RETRY_MAX_DELAY_MS`
+    /// and `BITBUCKET_RETRY_NON_IDEMPOTENT`.
+    pub fn from_env() -> Result<Self> {
+        let default = Self::default();
+        Ok(Self {
+            max_retries: env_parse("BITBUCKET_MAX_RETRIES")?.unwrap_or(default.max_retries),
+            base_delay: env_parse("BITBUCKET_RETRY_BASE_DELAY_MS")?.map(Duration::from_millis).unwrap_or(default.base_delay),
+            max_delay: env_parse("BITBUCKET_RETRY_MAX_DELAY_MS")?.map(Duration::from_millis).unwrap_or(default.max_delay),
+            retry_non_idempotent: env_flag("BITBUCKET_RETRY_NON_IDEMPOTENT"),
+        })
+    }
+
+    /// Whether a request with this method may be sent again.
+    pub fn allows_method(&self, method: &Method) -> bool {
+        is_idempotent(method) || self.retry_non_idempotent
+    }
+
+    /// Whether a response status is worth retrying.
+    pub fn is_retryable_status(status: StatusCode) -> bool {
+        matches!(
+            status,
+            StatusCode::TOO_MANY_REQUESTS
+                | StatusCode::INTERNAL_SERVER_ERROR
+                | StatusCode::BAD_GATEWAY
+                | StatusCode::SERVICE_UNAVAILABLE
+                | StatusCode::GATEWAY_TIMEOUT
+        )
+    }
+
+    /// Whether a transport error is worth retrying (the request never got a response).
+    pub fn is_retryable_error(error: &reqwest::Error) -> bool {
+        error.is_connect() || error.is_timeout()
+    }
+
+    /// How long to wait before retry number `attempt` (0-based).
+    ///
+    /// Server hints win: `Retry-After`, then `X-RateLimit-Reset` when `X-RateLimit-Remaining`
+    /// is exhausted. Otherwise jittered exponential backoff is used. The result never exceeds `max_delay`.
+    pub fn delay_for(&self, attempt: u32, headers: Option<&HeaderMap>) -> Duration {
+        let hinted = headers.and_then(|h| retry_after(h).or_else(|| rate_limit_reset(h)));
+        hinted.unwrap_or_else(|| self.backoff(attempt)).min(self.max_delay)
+    }
+
+    /// Exponential backoff with jitter: a random delay between half and all of `base_delay * 2^attempt`.
+    pub fn backoff(&self, attempt: u32) -> Duration {
+        let exp = self.base_delay.saturating_mul(2u32.saturating_pow(attempt)).min(self.max_delay);
+        let half = exp / 2;
+        let jitter_range = (exp - half).as_millis() as u64;
+        if jitter_range == 0 {
+            return exp;
+        }
+        half + Duration::from_millis(rand::random_range(0..=jitter_range))
+    }
+}
+
+/// Parses `Retry-After` given in seconds.
+pub fn retry_after(headers: &HeaderMap) -> Option<Duration> {
+    headers
+        .get(RETRY_AFTER)
+        .and_then(|v| v.to_str().ok())
+        .and_then(|v| v.trim().parse::<u64>().ok())
+        .map(Duration::from_secs)
+}
+
+/// Time until `X-RateLimit-Reset` (epoch seconds) when `X-RateLimit-Remaining` is zero.
+fn rate_limit_reset(headers: &HeaderMap) -> Option<Duration> {
+    let header = |name: &str| headers.get(name).and_then(|v| v.to_str().ok()).and_then(|v| v.trim().parse::<u64>().ok());
+    if header("x-ratelimit-remaining")? != 0 {
+        return None;
+    }
+    let reset = header("x-ratelimit-reset")?;
+    let now = SystemTime::now().duration_since(UNIX_EPOCH).ok()?.as_secs();
+    Some(Duration::from_secs(reset.saturating_sub(now)))
+}
+
+fn is_idempotent(method: &Method) -> bool {
+    matches!(*method, Method::GET | Method::HEAD | Method::OPTIONS | Method::PUT | Method::DELETE)
+}
+
+pub(crate) fn env_parse<T: std::str::FromStr>(name: &str) -> Result<Option<T>> {
+    match env::var(name) {
+        Ok(v) => v
+            .trim()
+            .parse()
+            .map(Some)
+            .map_err(|_| BitbucketError::Config(format!("{name} has an invalid value '{v}'"))),
+        Err(_) => Ok(None),
+    }
+}
+
+pub(crate) fn env_flag(name: &str) -> bool {
+    env::var(name)
+        .map(|v| matches!(v.trim().to_ascii_lowercase().as_str(), "1" | "true" | "yes" | "on"))
+        .unwrap_or(false)
+}
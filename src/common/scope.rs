@@ -0,0 +1,257 @@
+// Workspace and repository allowlist. Patterns such as `acme/payments-*` pin the server to
+// specific repositories; every request URL is checked in `BitbucketClient::execute` before it is
+// sent, so a mistyped or invented workspace never reaches Bitbucket, whichever tool built the URL.
+
+use std::fmt;
+use reqwest::{Method, Url};
+use serde_json::Value;
+use super::datacenter;
+use super::error::{BitbucketError, Result};
+
+/// One `workspace/repo` glob pair. `*` matches any run of characters and `?` a single one;
+/// matching ignores case. A bare `workspace` pattern allows every repository in it.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ScopePattern {
+    pub workspace: String,
+    pub repo: String,
+}
+
+impl ScopePattern {
+    pub fn parse(pattern: &str) -> Result<Self> {
+        let pattern = pattern.trim();
+        let (workspace, repo) = pattern.split_once('/').unwrap_or((pattern, "*"));
+        if workspace.is_empty() || repo.is_empty() || repo.contains('/') {
+            return Err(BitbucketError::Config(format!("scope pattern '{pattern}' must look like 'workspace' or 'workspace/repo'")));
+        }
+        Ok(Self { workspace: workspace.to_string(), repo: repo.to_string() })
+    }
+
+    fn matches_workspace(&self, workspace: &str) -> bool {
+        glob_match(&self.workspace, workspace)
+    }
+
+    fn matches_repo(&self, workspace: &str, repo: &str) -> bool {
+        self.matches_workspace(workspace) && glob_match(&self.repo, repo)
+    }
+
+    fn matches_every_repo(&self, workspace: &str) -> bool {
+        self.matches_workspace(workspace) && self.repo.chars().all(|c| c == '*')
+    }
+}
+
+impl fmt::Display for ScopePattern {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}/{}", self.workspace, self.repo)
+    }
+}
+
+/// The workspaces and repositories a client may touch. An empty scope allows everything.
+#[derive(Debug, Clone, Default)]
+pub struct RepoScope {
+    patterns: Vec<ScopePattern>,
+}
+
+/// What a request URL addresses, as far as the scope is concerned.
+#[derive(Debug, PartialEq, Eq)]
+enum Target<'a> {
+    Workspace(&'a str),
+    Repository(&'a str, &'a str),
+    /// Not tied to a workspace, e.g. `/user` or `/workspaces`.
+    Global,
+}
+
+impl RepoScope {
+    pub fn new(patterns: &[String]) -> Result<Self> {
+        Ok(Self { patterns: patterns.iter().map(|p| ScopePattern::parse(p)).collect::<Result<_>>()? })
+    }
+
+    pub fn patterns(&self) -> &[ScopePattern] {
+        &self.patterns
+    }
+
+    pub fn is_unrestricted(&self) -> bool {
+        self.patterns.is_empty()
+    }
+
+    /// Whether some repository in `workspace` may be touched.
+    pub fn allows_workspace(&self, workspace: &str) -> bool {
+        self.is_unrestricted() || self.patterns.iter().any(|p| p.matches_workspace(workspace))
+    }
+
+    /// Whether every repository in `workspace` may be touched, which workspace-level changes
+    /// (projects, snippets) and unfiltered listings require.
+    pub fn grants_workspace(&self, workspace: &str) -> bool {
+        self.is_unrestricted() || self.patterns.iter().any(|p| p.matches_every_repo(workspace))
+    }
+
+    pub fn allows_repo(&self, workspace: &str, repo: &str) -> bool {
+        self.is_unrestricted() || self.patterns.iter().any(|p| p.matches_repo(workspace, repo))
+    }
+
+    /// Checks the workspace or repository addressed by `url`, a request URL under `base_url`.
+    /// URLs elsewhere are refused outright once a scope is set. Workspace-level reads are allowed
+    /// when only some of the workspace's repositories are in scope (see `filter_listing`), but
+    /// workspace-level changes are not.
+    pub fn check_url(&self, method: &Method, base_url: &str, url: &str) -> Result<()> {
+        if self.is_unrestricted() {
+            return Ok(());
+        }
+        let segments = relative_segments(base_url, url).ok_or_else(|| self.out_of_scope(format!("URL '{url}' is outside {base_url}")))?;
+        let segments: Vec<&str> = segments.iter().map(String::as_str).collect();
+        match target(&segments).0 {
+            Target::Repository(workspace, repo) if !self.allows_repo(workspace, repo) => {
+                Err(self.out_of_scope(format!("repository '{workspace}/{repo}'")))
+            }
+            Target::Workspace(workspace) if !self.allows_workspace(workspace) => Err(self.out_of_scope(format!("workspace '{workspace}'"))),
+            Target::Workspace(workspace) if !matches!(*method, Method::GET | Method::HEAD) && !self.grants_workspace(workspace) => {
+                Err(self.out_of_scope(format!("{method} on workspace '{workspace}' (only some of its repositories are allowed)")))
+            }
+            _ => Ok(()),
+        }
+    }
+
+    /// Drops the items of a workspace-level listing at `url` that belong to repositories outside
+    /// the scope, unless a pattern grants the whole workspace. In a repository listing, items
+    /// whose repository cannot be told (e.g. `fields` left out the slug) are dropped as well;
+    /// items of other listings that name no repository, such as members, are kept.
+    pub fn filter_listing(&self, base_url: &str, url: &str, values: &mut Vec<Value>) {
+        if self.is_unrestricted() {
+            return;
+        }
+        let Some(segments) = relative_segments(base_url, url) else {
+            return;
+        };
+        let segments: Vec<&str> = segments.iter().map(String::as_str).collect();
+        let (Target::Workspace(workspace), rest) = target(&segments) else {
+            return;
+        };
+        if self.grants_workspace(workspace) {
+            return;
+        }
+        let lists_repositories = matches!(segments.as_slice(), ["repositories", _]) || rest == ["repos"];
+        values.retain(|item| {
+            if lists_repositories {
+                return item["slug"].as_str().is_some_and(|repo| self.allows_repo(workspace, repo));
+            }
+            match item.get("repository") {
+                Some(repo) => repository_name(repo).is_some_and(|(workspace, repo)| self.allows_repo(workspace, repo)),
+                None => true,
+            }
+        });
+    }
+
+    fn out_of_scope(&self, what: String) -> BitbucketError {
+        let allowed: Vec<String> = self.patterns.iter().map(ScopePattern::to_string).collect();
+        BitbucketError::Denied(format!("{what} is out of scope for this server (allowed: {})", allowed.join(", ")))
+    }
+}
+
+/// Whether `url` lies below `base_url`: same scheme, host and port, and a path under the base path
+/// once `.` and `..` are resolved.
+pub(crate) fn is_under(base_url: &str, url: &str) -> bool {
+    relative_segments(base_url, url).is_some()
+}
+
+/// Decoded path segments of `url` below the path of `base_url`, after resolving `.` and `..`.
+fn relative_segments(base_url: &str, url: &str) -> Option<Vec<String>> {
+    let base = Url::parse(base_url).ok()?;
+    let url = Url::parse(url).ok()?;
+    if url.origin() != base.origin() {
+        return None;
+    }
+    let base_path = base.path().trim_end_matches('/');
+    let rest = url.path().strip_prefix(base_path)?;
+    if !rest.is_empty() && !rest.starts_with('/') {
+        return None;
+    }
+    rest.split('/')
+        .filter(|s| !s.is_empty())
+        .map(percent_decode)
+        .collect()
+}
+
+/// `workspace`/`repo` of a repository object: Cloud's `full_name`, or Data Center's project key
+/// and slug.
+fn repository_name(repo: &Value) -> Option<(&str, &str)> {
+    match repo["full_name"].as_str() {
+        Some(full_name) => full_name.split_once('/'),
+        None => Some((repo["project"]["key"].as_str()?, repo["slug"].as_str()?)),
+    }
+}
+
+fn percent_decode(segment: &str) -> Option<String> {
+    let mut bytes = Vec::with_capacity(segment.len());
+    let mut iter = segment.bytes();
+    while let Some(b) = iter.next() {
+        if b == b'%' {
+            let hex = [iter.next()?, iter.next()?];
+            bytes.push(u8::from_str_radix(std::str::from_utf8(&hex).ok()?, 16).ok()?);
+        } else {
+            bytes.push(b);
+        }
+    }
+    String::from_utf8(bytes).ok()
+}
+
+/// The target of a request and the path segments below it.
+fn target<'s, 'a>(segments: &'s [&'a str]) -> (Target<'a>, &'s [&'a str]) {
+    // Data Center paths sit below the REST API root; the project key plays the workspace role.
+    let api: Vec<&str> = datacenter::API_PATH.split('/').filter(|s| !s.is_empty()).collect();
+    if let Some(rest) = segments.strip_prefix(api.as_slice()) {
+        return match rest {
+            ["projects", project, "repos", repo, rest @ ..] => (Target::Repository(project, repo), rest),
+            ["projects", project, rest @ ..] => (Target::Workspace(project), rest),
+            _ => (Target::Global, rest),
+        };
+    }
+    match segments {
+        ["repositories", workspace, repo, rest @ ..] => (Target::Repository(workspace, repo), rest),
+        ["repositories", workspace] => (Target::Workspace(workspace), &[]),
+        ["workspaces" | "snippets", workspace, rest @ ..] => (Target::Workspace(workspace), rest),
+        _ => (Target::Global, segments),
+    }
+}
+
+/// Workspace, repository and remaining path (e.g. `pullrequests/5/merge`) a request URL addresses.
+pub(crate) fn describe(base_url: &str, url: &str) -> (Option<String>, Option<String>, Option<String>) {
+    let Some(segments) = relative_segments(base_url, url) else {
+        return (None, None, None);
+    };
+    let segments: Vec<&str> = segments.iter().map(String::as_str).collect();
+    let (target, rest) = target(&segments);
+    let rest = (!rest.is_empty()).then(|| rest.join("/"));
+    match target {
+        Target::Repository(workspace, repo) => (Some(workspace.to_string()), Some(repo.to_string()), rest),
+        Target::Workspace(workspace) => (Some(workspace.to_string()), None, rest),
+        Target::Global => (None, None, rest),
+    }
+}
+
+/// Case-insensitive glob match supporting `*` and `?`.
+pub fn glob_match(pattern: &str, text: &str) -> bool {
+    let pattern: Vec<char> = pattern.to_lowercase().chars().collect();
+    let text: Vec<char> = text.to_lowercase().chars().collect();
+    let (mut p, mut t) = (0, 0);
+    let mut backtrack = None;
+    while t < text.len() {
+        match pattern.get(p) {
+            Some('*') => {
+                backtrack = Some((p, t));
+                p += 1;
+            }
+            Some(&c) if c == '?' || c == text[t] => {
+                p += 1;
+                t += 1;
+            }
+            _ => match backtrack {
+                Some((star, matched)) => {
+                    p = star + 1;
+                    t = matched + 1;
+                    backtrack = Some((star, matched + 1));
+                }
+                None => return false,
+            },
+        }
+    }
+    pattern[p..].iter().all(|&c| c == '*')
+}
@@ -0,0 +1,78 @@
+mod common;
+
+use std::path::PathBuf;
+use bitbucket_mcp::common::audit::{self, AuditLog};
+use common::make_client;
+use serde_json::json;
+
+fn temp_log(name: &str) -> PathBuf {
+    let path = std::env::temp_dir().join(format!("bitbucket-mcp-audit-{name}-{}.jsonl", std::process::id()));
+    let _ = std::fs::remove_file(&path);
+    path
+}
+
+#[tokio::test]
+async fn test_mutating_calls_are_appended_to_audit_log() {
+    let _merge = mockito::mock("POST", "/2.0/repositories/ws/audited/pullrequests/5/merge")
+        .with_status(200)
+        .with_body(r#"{"id": 5, "state": "MERGED"}"#)
+        .create();
+    let _missing = mockito::mock("DELETE", "/2.0/repositories/ws/audited/refs/branches/gone")
+        .with_status(404)
+        .with_body(r#"{"error": {"message": "Branch not found"}}"#)
+        .create();
+    let _get = mockito::mock("GET", "/2.0/repositories/ws/audited").with_status(200).with_body("{}").create();
+
+    let path = temp_log("calls");
+    let client = make_client(&mockito::server_url()).with_audit_log(Some(AuditLog::new(&path)));
+
+    audit::with_tool("merge_pullrequest", client.merge_pullrequest("ws", "audited", "5", Some(json!({"message": "Ship it"})))).await.unwrap();
+    client.delete_branch("ws", "audited", "gone").await.unwrap_err();
+    client.get_repository("ws", "audited", None).await.unwrap();
+
+    let entries = client.audit_log().unwrap().read_recent(10, None).unwrap();
+    assert_eq!(entries.len(), 2, "GET requests are not audited");
+
+    let merge = &entries[0];
+    assert_eq!(merge.tool.as_deref(), Some("merge_pullrequest"));
+    assert_eq!(merge.method, "POST");
+    assert_eq!((merge.workspace.as_deref(), merge.repo.as_deref()), (Some("ws"), Some("audited")));
+    assert_eq!(merge.target.as_deref(), Some("pullrequests/5/merge"));
+    assert_eq!(merge.status, Some(200));
+    assert_eq!(merge.body, Some(json!({"message": "Ship it"})));
+    assert!(chrono::DateTime::parse_from_rfc3339(&merge.timestamp).is_ok(), "{}", merge.timestamp);
+
+    let delete = &entries[1];
+    assert_eq!(delete.tool, None);
+    assert_eq!(delete.status, Some(404));
+    assert!(delete.error.as_deref().unwrap().contains("Branch not found"));
+
+    let _ = std::fs::remove_file(path);
+}
+
+#[tokio::test]
+async fn test_audit_log_redacts_secrets_and_filters_entries() {
+    let _hook = mockito::mock("POST", "/2.0/repositories/ws/hooked/pullrequests")
+        .with_status(201)
+        .with_body("{}")
+        .create();
+
+    let path = temp_log("redact");
+    let client = make_client(&mockito::server_url()).with_audit_log(Some(AuditLog::new(&path)));
+    for title in ["one", "two", "three"] {
+        let body = json!({"title": title, "auth": {"password": "hunter2", "api_token": "tok"}});
+        audit::with_tool("create_pullrequest", client.create_pullrequest("ws", "hooked", body)).await.unwrap();
+    }
+
+    let text = std::fs::read_to_string(&path).unwrap();
+    assert!(!text.contains("hunter2") && !text.contains("\"tok\""), "{text}");
+
+    let log = client.audit_log().unwrap();
+    let recent = log.read_recent(2, Some("create_pullrequest")).unwrap();
+    assert_eq!(recent.len(), 2);
+    assert_eq!(recent[1].body.as_ref().unwrap()["title"], "three");
+    assert_eq!(recent[1].body.as_ref().unwrap()["auth"]["password"], "***");
+    assert!(log.read_recent(10, Some("delete_repository")).unwrap().is_empty());
+
+    let _ = std::fs::remove_file(path);
+}
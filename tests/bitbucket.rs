@@ -5,7 +5,7 @@ async fn test_get_repository_success() {
         .with_body(r#"{"slug": "repo"}"#)
         .create();
     let client = make_client(&mockito::server_url());
-    let result = client.get_repository("ws", "repo").await.unwrap();
+    let result = client.get_repository("ws", "repo", None).await.unwrap();
     assert_eq!(result["slug"], "repo");
 }
 
@@ -16,7 +16,7 @@ async fn test_get_repository_error() {
         .with_body(r#"{"error": "Not found"}"#)
         .create();
     let client = make_client(&mockito::server_url());
-    let result = client.get_repository("ws", "repo").await;
+    let result = client.get_repository("ws", "repo", None).await;
     assert!(result.is_err());
 }
 #[tokio::test]
@@ -26,7 +26,7 @@ async fn test_get_workspace_success() {
         .with_body(r#"{"slug": "ws"}"#)
         .create();
     let client = make_client(&mockito::server_url());
-    let result = client.get_workspace("ws").await.unwrap();
+    let result = client.get_workspace("ws", None).await.unwrap();
     assert_eq!(result["slug"], "ws");
 }
 
@@ -37,7 +37,7 @@ async fn test_get_workspace_error() {
         .with_body(r#"{"error": "Not found"}"#)
         .create();
     let client = make_client(&mockito::server_url());
-    let result = client.get_workspace("ws").await;
+    let result = client.get_workspace("ws", None).await;
     assert!(result.is_err());
 }
 #[tokio::test]
@@ -47,7 +47,7 @@ async fn test_list_pullrequests_success() {
         .with_body(r#"{"values": ["pr1", "pr2"]}"#)
         .create();
     let client = make_client(&mockito::server_url());
-    let result = client.list_pullrequests("ws", "repo").await.unwrap();
+    let result = client.list_pullrequests("ws", "repo", &Filter::default(), &PageOptions::default(), None).await.unwrap();
     assert!(result["values"].is_array());
 }
 
@@ -58,7 +58,7 @@ async fn test_list_pullrequests_error() {
         .with_body(r#"{"error": "Not found"}"#)
         .create();
     let client = make_client(&mockito::server_url());
-    let result = client.list_pullrequests("ws", "repo").await;
+    let result = client.list_pullrequests("ws", "repo", &Filter::default(), &PageOptions::default(), None).await;
     assert!(result.is_err());
 }
 
@@ -69,7 +69,7 @@ async fn test_list_issues_success() {
         .with_body(r#"{"values": ["issue1", "issue2"]}"#)
         .create();
     let client = make_client(&mockito::server_url());
-    let result = client.list_issues("ws", "repo").await.unwrap();
+    let result = client.list_issues("ws", "repo", &Filter::default(), &PageOptions::default(), None).await.unwrap();
     assert!(result["values"].is_array());
 }
 
@@ -80,7 +80,7 @@ async fn test_list_issues_error() {
         .with_body(r#"{"error": "Server error"}"#)
         .create();
     let client = make_client(&mockito::server_url());
-    let result = client.list_issues("ws", "repo").await;
+    let result = client.list_issues("ws", "repo", &Filter::default(), &PageOptions::default(), None).await;
     assert!(result.is_err());
 }
 #[tokio::test]
@@ -90,7 +90,7 @@ async fn test_get_user_success() {
         .with_body(r#"{"username": "testuser"}"#)
         .create();
     let client = make_client(&mockito::server_url());
-    let result = client.get_user().await.unwrap();
+    let result = client.get_user(None).await.unwrap();
     assert_eq!(result["username"], "testuser");
 }
 
@@ -101,7 +101,7 @@ async fn test_get_user_error() {
         .with_body(r#"{"error": "Unauthorized"}"#)
         .create();
     let client = make_client(&mockito::server_url());
-    let result = client.get_user().await;
+    let result = client.get_user(None).await;
     assert!(result.is_err());
 }
 
@@ -112,7 +112,7 @@ async fn test_list_workspaces_success() {
         .with_body(r#"{"values": ["ws1", "ws2"]}"#)
         .create();
     let client = make_client(&mockito::server_url());
-    let result = client.list_workspaces().await.unwrap();
+    let result = client.list_workspaces(&PageOptions::default(), None).await.unwrap();
     assert!(result["values"].is_array());
 }
 
@@ -123,7 +123,7 @@ async fn test_list_workspaces_error() {
         .with_body(r#"{"error": "Server error"}"#)
         .create();
     let client = make_client(&mockito::server_url());
-    let result = client.list_workspaces().await;
+    let result = client.list_workspaces(&PageOptions::default(), None).await;
     assert!(result.is_err());
 }
 
@@ -134,7 +134,7 @@ async fn test_list_repositories_success() {
         .with_body(r#"{"values": ["repo1", "repo2"]}"#)
         .create();
     let client = make_client(&mockito::server_url());
-    let result = client.list_repositories("ws").await.unwrap();
+    let result = client.list_repositories("ws", &Filter::default(), &PageOptions::default(), None).await.unwrap();
     assert!(result["values"].is_array());
 }
 
@@ -145,7 +145,7 @@ async fn test_list_repositories_error() {
         .with_body(r#"{"error": "Not found"}"#)
         .create();
     let client = make_client(&mockito::server_url());
-    let result = client.list_repositories("ws").await;
+    let result = client.list_repositories("ws", &Filter::default(), &PageOptions::default(), None).await;
     assert!(result.is_err());
 }
 #[tokio::test]
@@ -155,7 +155,7 @@ async fn test_list_pullrequest_tasks_success() {
         .with_body(r#"{"values": []}"#)
         .create();
     let client = make_client(&mockito::server_url());
-    let result = client.list_pullrequest_tasks("ws", "repo", "1").await.unwrap();
+    let result = client.list_pullrequest_tasks("ws", "repo", "1", &PageOptions::default(), None).await.unwrap();
     assert!(result["values"].is_array());
 }
 
@@ -166,7 +166,7 @@ async fn test_list_pullrequest_tasks_error() {
         .with_body(r#"{"error": "Not found"}"#)
         .create();
     let client = make_client(&mockito::server_url());
-    let result = client.list_pullrequest_tasks("ws", "repo", "1").await;
+    let result = client.list_pullrequest_tasks("ws", "repo", "1", &PageOptions::default(), None).await;
     assert!(result.is_err());
 }
 
@@ -201,7 +201,7 @@ async fn test_get_pullrequest_diffstat_success() {
         .with_body(r#"{"values": []}"#)
         .create();
     let client = make_client(&mockito::server_url());
-    let result = client.get_pullrequest_diffstat("ws", "repo", "1").await.unwrap();
+    let result = client.get_pullrequest_diffstat("ws", "repo", "1", None).await.unwrap();
     assert!(result["values"].is_array());
 }
 
@@ -212,7 +212,7 @@ async fn test_get_pullrequest_diffstat_error() {
         .with_body(r#"{"error": "Not found"}"#)
         .create();
     let client = make_client(&mockito::server_url());
-    let result = client.get_pullrequest_diffstat("ws", "repo", "1").await;
+    let result = client.get_pullrequest_diffstat("ws", "repo", "1", None).await;
     assert!(result.is_err());
 }
 #[tokio::test]
@@ -244,7 +244,7 @@ async fn test_list_pullrequest_comments_success() {
         .with_body(r#"{"values": []}"#)
         .create();
     let client = make_client(&mockito::server_url());
-    let result = client.list_pullrequest_comments("ws", "repo", "1").await.unwrap();
+    let result = client.list_pullrequest_comments("ws", "repo", "1", &PageOptions::default(), None).await.unwrap();
     assert!(result["values"].is_array());
 }
 
@@ -255,7 +255,7 @@ async fn test_list_pullrequest_comments_error() {
         .with_body(r#"{"error": "Server error"}"#)
         .create();
     let client = make_client(&mockito::server_url());
-    let result = client.list_pullrequest_comments("ws", "repo", "1").await;
+    let result = client.list_pullrequest_comments("ws", "repo", "1", &PageOptions::default(), None).await;
     assert!(result.is_err());
 }
 
@@ -292,7 +292,7 @@ async fn test_list_pullrequest_activity_success() {
         .with_body(r#"{"values": []}"#)
         .create();
     let client = make_client(&mockito::server_url());
-    let result = client.list_pullrequest_activity("ws", "repo", "1").await.unwrap();
+    let result = client.list_pullrequest_activity("ws", "repo", "1", &PageOptions::default(), None).await.unwrap();
     assert!(result["values"].is_array());
 }
 
@@ -303,7 +303,7 @@ async fn test_list_pullrequest_activity_error() {
         .with_body(r#"{"error": "Not found"}"#)
         .create();
     let client = make_client(&mockito::server_url());
-    let result = client.list_pullrequest_activity("ws", "repo", "1").await;
+    let result = client.list_pullrequest_activity("ws", "repo", "1", &PageOptions::default(), None).await;
     assert!(result.is_err());
 }
 
@@ -395,6 +395,9 @@ async fn test_decline_pullrequest_error() {
 }
 
 use bitbucket_mcp::common::bitbucket::BitbucketClient;
+use bitbucket_mcp::common::pagination::PageOptions;
+use bitbucket_mcp::common::query::Filter;
+use bitbucket_mcp::common::retry::RetryPolicy;
 use mockito::mock;
 use serde_json::json;
 
@@ -514,12 +517,12 @@ fn make_client(base_url: &str) -> BitbucketClient {
     } else {
         format!("{}/2.0", base_url.trim_end_matches('/'))
     };
-    BitbucketClient {
-        api_username: "user".to_string(),
-        api_token: "pass".to_string(),
-        client: reqwest::Client::new(),
-        base_url,
-    }
+    let retry = RetryPolicy {
+        base_delay: std::time::Duration::from_millis(1),
+        max_delay: std::time::Duration::from_millis(10),
+        ..RetryPolicy::default()
+    };
+    BitbucketClient::new("user", "pass", base_url).with_retry_policy(retry)
 }
 
 #[tokio::test]
@@ -543,7 +546,7 @@ async fn test_get_pullrequest_not_found() {
         .create();
     let url = &mockito::server_url();
     let client = make_client(url);
-    let result = client.get_pullrequest("ws", "repo", "42").await;
+    let result = client.get_pullrequest("ws", "repo", "42", None).await;
     assert!(result.is_err());
 }
 
@@ -0,0 +1,153 @@
+mod common;
+
+use std::time::Duration;
+use bitbucket_mcp::common::auth::Credentials;
+use bitbucket_mcp::common::cache::CacheConfig;
+use bitbucket_mcp::common::config::ClientConfig;
+use common::make_client;
+use mockito::Matcher;
+
+fn cached_client(config: CacheConfig) -> bitbucket_mcp::common::bitbucket::BitbucketClient {
+    make_client(&mockito::server_url()).with_cache(Some(config))
+}
+
+#[tokio::test]
+async fn test_fresh_entries_are_served_from_cache() {
+    let get = mockito::mock("GET", "/2.0/repositories/cache/fresh")
+        .with_status(200)
+        .with_body(r#"{"slug": "fresh"}"#)
+        .expect(1)
+        .create();
+
+    let client = cached_client(CacheConfig::default());
+    for _ in 0..3 {
+        assert_eq!(client.get_repository("cache", "fresh", None).await.unwrap()["slug"], "fresh");
+    }
+
+    get.assert();
+    let stats = &client.diagnostics()["cache"];
+    assert_eq!((stats["hits"].as_u64(), stats["misses"].as_u64(), stats["entries"].as_u64()), (Some(2), Some(1), Some(1)));
+}
+
+#[tokio::test]
+async fn test_stale_entries_are_revalidated_with_etag() {
+    let full = mockito::mock("GET", "/2.0/repositories/cache/etag")
+        .match_header("if-none-match", Matcher::Missing)
+        .with_status(200)
+        .with_header("etag", "\"v1\"")
+        .with_body(r#"{"slug": "etag"}"#)
+        .expect(1)
+        .create();
+    let not_modified = mockito::mock("GET", "/2.0/repositories/cache/etag")
+        .match_header("if-none-match", "\"v1\"")
+        .with_status(304)
+        .expect(2)
+        .create();
+
+    // With a zero TTL every lookup after the first one revalidates.
+    let client = cached_client(CacheConfig { ttl: Duration::ZERO, ..CacheConfig::default() });
+    for _ in 0..3 {
+        assert_eq!(client.get_repository("cache", "etag", None).await.unwrap()["slug"], "etag");
+    }
+
+    full.assert();
+    not_modified.assert();
+    assert_eq!(client.diagnostics()["cache"]["revalidated"], 2);
+}
+
+#[tokio::test]
+async fn test_file_source_and_diffs_are_cached_as_text() {
+    let source = "{\n  \"b\": 1,\n  \"a\": 2\n}\n";
+    let full = mockito::mock("GET", "/2.0/repositories/cache/text/src/main/config.json")
+        .match_header("if-none-match", Matcher::Missing)
+        .with_status(200)
+        .with_header("etag", "\"v1\"")
+        .with_body(source)
+        .expect(1)
+        .create();
+    let not_modified = mockito::mock("GET", "/2.0/repositories/cache/text/src/main/config.json")
+        .match_header("if-none-match", "\"v1\"")
+        .with_status(304)
+        .expect(1)
+        .create();
+    let diff = mockito::mock("GET", "/2.0/repositories/cache/text/pullrequests/1/diff")
+        .with_status(200)
+        .with_body("diff --git a/x b/x\n")
+        .expect(1)
+        .create();
+
+    let client = cached_client(CacheConfig { ttl: Duration::ZERO, ..CacheConfig::default() });
+    for _ in 0..2 {
+        assert_eq!(client.get_file_source("cache", "text", "main", "config.json").await.unwrap(), source);
+    }
+    // Without a validator an expired entry is fetched again, so use a TTL for the diff.
+    let client = cached_client(CacheConfig::default());
+    for _ in 0..2 {
+        assert_eq!(client.get_pullrequest_diff("cache", "text", "1").await.unwrap(), "diff --git a/x b/x\n");
+    }
+
+    full.assert();
+    not_modified.assert();
+    diff.assert();
+}
+
+#[tokio::test]
+async fn test_mutations_invalidate_the_touched_repository() {
+    let pr = mockito::mock("GET", "/2.0/repositories/cache/touched/pullrequests/1")
+        .with_status(200)
+        .with_body(r#"{"id": 1}"#)
+        .expect(2)
+        .create();
+    let other = mockito::mock("GET", "/2.0/repositories/cache/untouched")
+        .with_status(200)
+        .with_body(r#"{"slug": "untouched"}"#)
+        .expect(1)
+        .create();
+    let _approve = mockito::mock("POST", "/2.0/repositories/cache/touched/pullrequests/1/approve")
+        .with_status(200)
+        .with_body("{}")
+        .create();
+
+    let client = cached_client(CacheConfig::default());
+    client.get_pullrequest("cache", "touched", "1", None).await.unwrap();
+    client.get_repository("cache", "untouched", None).await.unwrap();
+    client.approve_pullrequest("cache", "touched", "1").await.unwrap();
+    client.get_pullrequest("cache", "touched", "1", None).await.unwrap();
+    client.get_repository("cache", "untouched", None).await.unwrap();
+
+    pr.assert();
+    other.assert();
+}
+
+#[tokio::test]
+async fn test_least_recently_used_entry_is_evicted() {
+    let mock = |slug: &str, hits: usize| {
+        mockito::mock("GET", format!("/2.0/repositories/lru/{slug}").as_str()).with_status(200).with_body("{}").expect(hits).create()
+    };
+    let (a, b, c) = (mock("a", 1), mock("b", 2), mock("c", 1));
+
+    let client = cached_client(CacheConfig { capacity: 2, ..CacheConfig::default() });
+    for slug in ["a", "b", "a", "c", "a", "b"] {
+        client.get_repository("lru", slug, None).await.unwrap();
+    }
+
+    a.assert();
+    b.assert();
+    c.assert();
+    assert_eq!(client.diagnostics()["cache"]["evicted"], 2);
+}
+
+#[tokio::test]
+async fn test_cache_is_per_identity_and_off_by_default() {
+    let get = mockito::mock("GET", "/2.0/repositories/cache/identity").with_status(200).with_body("{}").expect(2).create();
+
+    let client = cached_client(CacheConfig::default());
+    client.get_repository("cache", "identity", None).await.unwrap();
+    let other = client.clone().with_credentials(Some(Credentials::bearer("someone-else")));
+    other.get_repository("cache", "identity", None).await.unwrap();
+    get.assert();
+
+    assert_eq!(ClientConfig::default().cache(), None);
+    let config = ClientConfig::from_toml_str("cache_ttl_secs = 30").unwrap();
+    assert_eq!(config.cache(), Some(CacheConfig { capacity: 256, ttl: Duration::from_secs(30) }));
+}
@@ -1,8 +1,13 @@
 // Shared test utilities
+use std::time::Duration;
 use bitbucket_mcp::common::bitbucket::BitbucketClient;
-use reqwest::Client;
+use bitbucket_mcp::common::datacenter::Backend;
+use bitbucket_mcp::common::retry::RetryPolicy;
 
 /// Helper to create a BitbucketClient for unit tests with custom base_url
+///
+/// Retries stay enabled but with millisecond delays so error tests remain fast.
+#[allow(dead_code)]
 pub fn make_client(base_url: &str) -> BitbucketClient {
     let base_url = if base_url.ends_with("/2.0") {
         base_url.to_string()
@@ -10,10 +15,23 @@ pub fn make_client(base_url: &str) -> BitbucketClient {
         format!("{}/2.0", base_url)
     };
     
-    BitbucketClient {
-        api_username: "testuser".to_string(),
-        api_token: "testpass".to_string(),
-        client: Client::new(),
-        base_url,
+    BitbucketClient::new("testuser", "testpass", base_url).with_retry_policy(fast_retry_policy())
+}
+
+/// Helper to create a Data Center BitbucketClient; `base_url` is the server URL without `/rest/api/1.0`.
+#[allow(dead_code)]
+pub fn make_dc_client(base_url: &str) -> BitbucketClient {
+    BitbucketClient::new("testuser", "testpass", base_url)
+        .with_backend(Backend::DataCenter)
+        .with_retry_policy(fast_retry_policy())
+}
+
+/// Retry policy with tiny delays for tests.
+#[allow(dead_code)]
+pub fn fast_retry_policy() -> RetryPolicy {
+    RetryPolicy {
+        base_delay: Duration::from_millis(1),
+        max_delay: Duration::from_millis(10),
+        ..RetryPolicy::default()
     }
 }
@@ -0,0 +1,149 @@
+mod common;
+
+use std::time::Duration;
+use bitbucket_mcp::common::bitbucket::BitbucketTool;
+use bitbucket_mcp::common::confirm::{self, Confirmations};
+use common::make_client;
+use rmcp::model::CallToolResult;
+use rmcp::ServiceExt;
+use serde_json::{json, Map, Value};
+use tokio::io::{AsyncBufReadExt, AsyncWriteExt, BufReader};
+
+fn args(value: Value) -> Option<Map<String, Value>> {
+    value.as_object().cloned()
+}
+
+/// Minimal MCP client speaking newline-delimited JSON-RPC to a server on an in-memory pipe.
+struct McpPipe {
+    reader: tokio::io::Lines<BufReader<tokio::io::ReadHalf<tokio::io::DuplexStream>>>,
+    writer: tokio::io::WriteHalf<tokio::io::DuplexStream>,
+    next_id: u64,
+}
+
+impl McpPipe {
+    async fn start(tool: BitbucketTool) -> Self {
+        let (client, server) = tokio::io::duplex(1 << 16);
+        tokio::spawn(async move {
+            let service = tool.serve(tokio::io::split(server)).await.unwrap();
+            let _ = service.waiting().await;
+        });
+        let (reader, writer) = tokio::io::split(client);
+        let mut pipe = Self { reader: BufReader::new(reader).lines(), writer, next_id: 0 };
+        pipe.request("initialize", json!({"protocolVersion": "2024-11-05", "capabilities": {}, "clientInfo": {"name": "test", "version": "1"}}))
+            .await;
+        pipe.send(json!({"jsonrpc": "2.0", "method": "notifications/initialized"})).await;
+        pipe
+    }
+
+    async fn send(&mut self, message: Value) {
+        self.writer.write_all(format!("{message}\n").as_bytes()).await.unwrap();
+    }
+
+    async fn request(&mut self, method: &str, params: Value) -> Value {
+        self.next_id += 1;
+        let id = self.next_id;
+        self.send(json!({"jsonrpc": "2.0", "id": id, "method": method, "params": params})).await;
+        loop {
+            let line = self.reader.next_line().await.unwrap().expect("server closed the pipe");
+            let message: Value = serde_json::from_str(&line).unwrap();
+            if message["id"] == id {
+                return message["result"].clone();
+            }
+        }
+    }
+
+    /// Calls a tool and returns the JSON (or text) of its first content item.
+    async fn call_tool(&mut self, name: &str, arguments: Value) -> Value {
+        let result = self.request("tools/call", json!({"name": name, "arguments": arguments})).await;
+        let text = result["content"][0]["text"].as_str().unwrap().to_string();
+        serde_json::from_str(&text).unwrap_or(Value::String(text))
+    }
+}
+
+fn result_json(result: &CallToolResult) -> Value {
+    let text = result.content[0].as_text().unwrap().text.clone();
+    serde_json::from_str(&text).unwrap_or(Value::String(text))
+}
+
+#[tokio::test]
+async fn test_destructive_tool_needs_token_from_preview() {
+    let _get = mockito::mock("GET", "/2.0/repositories/ws/doomed")
+        .with_status(200)
+        .with_body(r#"{"full_name": "ws/doomed", "size": 1024}"#)
+        .create();
+
+    let tool = BitbucketTool::new(make_client(&mockito::server_url())).with_confirmations(Some(Confirmations::default()));
+
+    let mut first = args(json!({"workspace": "ws", "repo_slug": "doomed"}));
+    let preview = tool.confirm("delete_repository", &mut first).await.unwrap().expect("preview instead of running");
+    let preview = result_json(&preview);
+    assert_eq!(preview["confirmation_required"], true);
+    assert_eq!(preview["target"]["full_name"], "ws/doomed");
+    let token = preview["confirmation_token"].as_str().unwrap().to_string();
+
+    // A token only authorizes the exact call it was issued for.
+    let mut other = args(json!({"workspace": "ws", "repo_slug": "other", "confirmation_token": token}));
+    let rejected = tool.confirm("delete_repository", &mut other).await.unwrap().expect("rejected");
+    assert_eq!(rejected.is_error, Some(true));
+
+    let mut first = args(json!({"workspace": "ws", "repo_slug": "doomed"}));
+    let preview = result_json(&tool.confirm("delete_repository", &mut first).await.unwrap().unwrap());
+    let token = preview["confirmation_token"].as_str().unwrap().to_string();
+    let mut second = args(json!({"workspace": "ws", "repo_slug": "doomed", "confirmation_token": token}));
+    assert!(tool.confirm("delete_repository", &mut second).await.unwrap().is_none());
+    assert!(!second.unwrap().contains_key(confirm::TOKEN_PARAM));
+
+    // Tokens are single-use.
+    let mut replay = args(json!({"workspace": "ws", "repo_slug": "doomed", "confirmation_token": token}));
+    assert!(tool.confirm("delete_repository", &mut replay).await.unwrap().is_some());
+}
+
+#[tokio::test]
+async fn test_confirmation_tokens_expire() {
+    let confirmations = Confirmations::new(Duration::from_millis(10));
+    let arguments = args(json!({"workspace": "ws", "snippet_id": "abc"})).unwrap();
+    let token = confirmations.issue("delete_snippet", &arguments);
+
+    tokio::time::sleep(Duration::from_millis(30)).await;
+    let err = confirmations.redeem(&token, "delete_snippet", &arguments).unwrap_err();
+    assert!(err.to_string().contains("expired"), "{err}");
+}
+
+#[tokio::test]
+async fn test_confirmation_is_off_by_default_and_skips_other_tools() {
+    let tool = BitbucketTool::new(make_client("http://localhost"));
+    let mut arguments = args(json!({"workspace": "ws", "repo_slug": "repo"}));
+    assert!(tool.confirm("delete_repository", &mut arguments).await.unwrap().is_none());
+
+    let tool = tool.with_confirmations(Some(Confirmations::default()));
+    assert!(tool.confirm("list_branches", &mut arguments).await.unwrap().is_none());
+
+    let tools = tool.tools();
+    let schema = |name: &str| tools.iter().find(|t| t.name == name).unwrap().input_schema.clone();
+    assert!(schema("merge_pullrequest")["properties"].get(confirm::TOKEN_PARAM).is_some());
+    assert!(schema("list_branches")["properties"].get(confirm::TOKEN_PARAM).is_none());
+}
+
+#[tokio::test]
+async fn test_confirmation_round_trip_through_call_tool() {
+    let _get = mockito::mock("GET", "/2.0/repositories/ws/e2e/refs/branches/stale")
+        .with_status(200)
+        .with_body(r#"{"name": "stale", "target": {"hash": "abc123"}}"#)
+        .create();
+    let delete = mockito::mock("DELETE", "/2.0/repositories/ws/e2e/refs/branches/stale").with_status(204).expect(1).create();
+
+    let tool = BitbucketTool::new(make_client(&mockito::server_url())).with_confirmations(Some(Confirmations::default()));
+    let mut mcp = McpPipe::start(tool).await;
+    let arguments = json!({"workspace": "ws", "repo_slug": "e2e", "branch": "stale"});
+
+    let preview = mcp.call_tool("delete_branch", arguments.clone()).await;
+    assert_eq!(preview["confirmation_required"], true, "{preview}");
+    let token = preview["confirmation_token"].as_str().unwrap().to_string();
+    assert_eq!(token.len(), 32, "{token}");
+
+    let mut confirmed = arguments;
+    confirmed[confirm::TOKEN_PARAM] = token.into();
+    let result = mcp.call_tool("delete_branch", confirmed).await;
+    assert!(result.get("confirmation_required").is_none(), "{result}");
+    delete.assert();
+}
@@ -0,0 +1,105 @@
+mod common;
+
+use bitbucket_mcp::common::error::BitbucketError;
+use bitbucket_mcp::common::models::{AccountRef, BranchEndpoint, CreatePullRequest};
+use common::make_client;
+use mockito::Matcher;
+use serde_json::json;
+
+fn pull_request(destination: Option<&str>) -> CreatePullRequest {
+    CreatePullRequest {
+        title: "Add feature".to_string(),
+        source: BranchEndpoint::branch("feature/login"),
+        destination: destination.map(BranchEndpoint::branch),
+        ..CreatePullRequest::default()
+    }
+}
+
+#[tokio::test]
+async fn test_create_pullrequest_checked_submits_when_branches_exist() {
+    let _source = mockito::mock("GET", "/2.0/repositories/ws/repo/refs/branches/feature/login")
+        .with_status(200)
+        .with_body(r#"{"name": "feature/login"}"#)
+        .create();
+    let _destination = mockito::mock("GET", "/2.0/repositories/ws/repo/refs/branches/main")
+        .with_status(200)
+        .with_body(r#"{"name": "main"}"#)
+        .create();
+    let create = mockito::mock("POST", "/2.0/repositories/ws/repo/pullrequests")
+        .match_body(Matcher::Json(json!({
+            "title": "Add feature",
+            "source": {"branch": {"name": "feature/login"}},
+            "destination": {"branch": {"name": "main"}}
+        })))
+        .with_status(201)
+        .with_body(r#"{"id": 12}"#)
+        .create();
+
+    let client = make_client(&mockito::server_url());
+    let result = client.create_pullrequest_checked("ws", "repo", &pull_request(Some("main"))).await.unwrap();
+
+    assert_eq!(result["id"], 12);
+    create.assert();
+}
+
+#[tokio::test]
+async fn test_create_pullrequest_checked_rejects_missing_source_branch() {
+    let _source = mockito::mock("GET", "/2.0/repositories/ws/repo/refs/branches/feature/login")
+        .with_status(404)
+        .with_body(r#"{"type": "error", "error": {"message": "Branch not found"}}"#)
+        .create();
+    let create = mockito::mock("POST", "/2.0/repositories/ws/repo/pullrequests")
+        .with_status(201)
+        .expect(0)
+        .create();
+
+    let client = make_client(&mockito::server_url());
+    let err = client.create_pullrequest_checked("ws", "repo", &pull_request(None)).await.unwrap_err();
+
+    match err {
+        BitbucketError::Validation { message, fields } => {
+            assert!(message.contains("feature/login"), "{message}");
+            assert!(fields.contains_key("source_branch"));
+        }
+        other => panic!("expected Validation, got {other:?}"),
+    }
+    create.assert();
+}
+
+#[tokio::test]
+async fn test_resolve_account_accepts_uuid_and_account_id_without_lookup() {
+    let members = mockito::mock("GET", Matcher::Regex("^/2.0/workspaces/ws/members".to_string()))
+        .expect(0)
+        .create();
+
+    let client = make_client(&mockito::server_url());
+    assert_eq!(
+        client.resolve_account("ws", "{8b4f8a5e-0000-0000-0000-000000000000}").await.unwrap(),
+        AccountRef { uuid: Some("{8b4f8a5e-0000-0000-0000-000000000000}".to_string()), account_id: None }
+    );
+    assert_eq!(
+        client.resolve_account("ws", "557058:f00d").await.unwrap(),
+        AccountRef { uuid: None, account_id: Some("557058:f00d".to_string()) }
+    );
+    members.assert();
+}
+
+#[tokio::test]
+async fn test_resolve_account_looks_up_member_nickname() {
+    let _m = mockito::mock("GET", "/2.0/workspaces/ws/members")
+        .with_status(200)
+        .with_body(json!({
+            "values": [
+                {"user": {"nickname": "sam", "uuid": "{u1}", "account_id": "a1"}},
+                {"user": {"nickname": "Jane", "display_name": "Jane Doe", "uuid": "{u2}", "account_id": "a2"}}
+            ]
+        }).to_string())
+        .create();
+
+    let client = make_client(&mockito::server_url());
+    let account = client.resolve_account("ws", "jane").await.unwrap();
+    assert_eq!(account.uuid.as_deref(), Some("{u2}"));
+
+    let err = client.resolve_account("ws", "nobody").await.unwrap_err();
+    assert!(matches!(err, BitbucketError::Validation { .. }));
+}
@@ -0,0 +1,170 @@
+mod common;
+
+use bitbucket_mcp::common::bitbucket::{BitbucketCommentContent, BitbucketCommentPayload, BitbucketInline};
+use bitbucket_mcp::common::error::BitbucketError;
+use bitbucket_mcp::common::pagination::PageOptions;
+use bitbucket_mcp::common::query::Filter;
+use common::make_dc_client;
+use mockito::Matcher;
+use serde_json::json;
+
+const REPO: &str = "/rest/api/1.0/projects/PROJ/repos/repo";
+
+#[tokio::test]
+async fn test_list_repositories_follows_next_page_start() {
+    let _first = mockito::mock("GET", "/rest/api/1.0/projects/PAGED/repos")
+        .with_status(200)
+        .with_body(json!({"values": [{"slug": "one"}], "isLastPage": false, "nextPageStart": 1, "start": 0, "limit": 1}).to_string())
+        .create();
+    let _second = mockito::mock("GET", "/rest/api/1.0/projects/PAGED/repos?start=1")
+        .with_status(200)
+        .with_body(json!({"values": [{"slug": "two"}], "isLastPage": true, "start": 1, "limit": 1}).to_string())
+        .create();
+
+    let client = make_dc_client(&mockito::server_url());
+    let result = client.list_repositories("PAGED", &Filter::default(), &PageOptions::default(), None).await.unwrap();
+
+    assert_eq!(result["size"], 2);
+    assert_eq!(result["values"][1]["slug"], "two");
+    assert!(result.get("next").is_none());
+}
+
+#[tokio::test]
+async fn test_list_repositories_returns_resumable_cursor_at_limit() {
+    let server_url = mockito::server_url();
+    let _first = mockito::mock("GET", "/rest/api/1.0/projects/LIMIT/repos?limit=1")
+        .with_status(200)
+        .with_body(json!({"values": [{"slug": "one"}], "isLastPage": false, "nextPageStart": 1}).to_string())
+        .create();
+    let second = mockito::mock("GET", "/rest/api/1.0/projects/LIMIT/repos?limit=1&start=1")
+        .with_status(200)
+        .with_body(json!({"values": [{"slug": "two"}], "isLastPage": true}).to_string())
+        .create();
+
+    let client = make_dc_client(&server_url);
+    let page = PageOptions { limit: Some(1), ..PageOptions::default() };
+    let result = client.list_repositories("LIMIT", &Filter::default(), &page, None).await.unwrap();
+    let next = result["next"].as_str().unwrap().to_string();
+    assert_eq!(next, format!("{server_url}/rest/api/1.0/projects/LIMIT/repos?limit=1&start=1"));
+
+    let resume = PageOptions { page: Some(next), ..PageOptions::default() };
+    let result = client.list_repositories("LIMIT", &Filter::default(), &resume, None).await.unwrap();
+    assert_eq!(result["values"][0]["slug"], "two");
+    second.assert();
+}
+
+#[tokio::test]
+async fn test_list_pullrequests_maps_state_and_rejects_query() {
+    let _m = mockito::mock("GET", Matcher::Regex(format!("^{REPO}/pull-requests$")))
+        .match_query(Matcher::UrlEncoded("state".to_string(), "MERGED".to_string()))
+        .with_status(200)
+        .with_body(json!({"values": [{"id": 3, "state": "MERGED"}], "isLastPage": true}).to_string())
+        .create();
+
+    let client = make_dc_client(&mockito::server_url());
+    let filter = Filter { state: Some("merged".to_string()), ..Filter::default() };
+    let result = client.list_pullrequests("PROJ", "repo", &filter, &PageOptions::default(), None).await.unwrap();
+    assert_eq!(result["values"][0]["id"], 3);
+
+    let filter = Filter { q: Some("title ~ \"fix\"".to_string()), ..Filter::default() };
+    let err = client.list_pullrequests("PROJ", "repo", &filter, &PageOptions::default(), None).await.unwrap_err();
+    assert!(matches!(err, BitbucketError::Validation { .. }), "{err:?}");
+}
+
+#[tokio::test]
+async fn test_merge_pullrequest_sends_current_version_and_strategy() {
+    let _get = mockito::mock("GET", format!("{REPO}/pull-requests/5").as_str())
+        .with_status(200)
+        .with_body(r#"{"id": 5, "version": 3, "state": "OPEN"}"#)
+        .create();
+    let merge = mockito::mock("POST", format!("{REPO}/pull-requests/5/merge?version=3").as_str())
+        .match_body(Matcher::Json(json!({"message": "Ship it", "strategyId": "squash"})))
+        .with_status(200)
+        .with_body(r#"{"id": 5, "state": "MERGED"}"#)
+        .create();
+
+    let client = make_dc_client(&mockito::server_url());
+    let body = json!({"message": "Ship it", "merge_strategy": "squash"});
+    let result = client.merge_pullrequest("PROJ", "repo", "5", Some(body)).await.unwrap();
+
+    assert_eq!(result["state"], "MERGED");
+    merge.assert();
+}
+
+#[tokio::test]
+async fn test_state_change_without_version_is_not_sent() {
+    let _get = mockito::mock("GET", format!("{REPO}/pull-requests/7").as_str())
+        .with_status(200)
+        .with_body(r#"{"id": 7, "state": "OPEN"}"#)
+        .create();
+    let decline = mockito::mock("POST", Matcher::Regex(r"/pull-requests/7/decline".to_string())).expect(0).create();
+
+    let client = make_dc_client(&mockito::server_url());
+    let err = client.decline_pullrequest("PROJ", "repo", "7").await.unwrap_err();
+
+    assert!(matches!(err, BitbucketError::Decode(_)), "{err:?}");
+    decline.assert();
+}
+
+#[tokio::test]
+async fn test_pullrequest_comments_use_data_center_shapes() {
+    let add = mockito::mock("POST", format!("{REPO}/pull-requests/6/comments").as_str())
+        .match_body(Matcher::Json(json!({
+            "text": "Nit",
+            "anchor": {"path": "src/lib.rs", "line": 12, "lineType": "ADDED", "fileType": "TO"}
+        })))
+        .with_status(201)
+        .with_body(r#"{"id": 100, "text": "Nit"}"#)
+        .create();
+    let _activities = mockito::mock("GET", format!("{REPO}/pull-requests/6/activities").as_str())
+        .with_status(200)
+        .with_body(json!({
+            "values": [
+                {"action": "APPROVED", "user": {"name": "sam"}},
+                {"action": "COMMENTED", "comment": {"id": 100, "text": "Nit"}, "commentAnchor": {"path": "src/lib.rs", "line": 12}}
+            ],
+            "isLastPage": true
+        }).to_string())
+        .create();
+
+    let client = make_dc_client(&mockito::server_url());
+    let payload = BitbucketCommentPayload {
+        content: BitbucketCommentContent { raw: "Nit".to_string() },
+        inline: Some(BitbucketInline { from: None, to: Some(12), path: "src/lib.rs".to_string() }),
+    };
+    client.add_pullrequest_comment("PROJ", "repo", "6", payload).await.unwrap();
+    add.assert();
+
+    let comments = client.list_pullrequest_comments("PROJ", "repo", "6", &PageOptions::default(), None).await.unwrap();
+    assert_eq!(comments["size"], 1);
+    assert_eq!(comments["values"][0]["text"], "Nit");
+    assert_eq!(comments["values"][0]["anchor"]["line"], 12);
+}
+
+#[tokio::test]
+async fn test_diff_and_approve_use_data_center_paths() {
+    let _diff = mockito::mock("GET", format!("{REPO}/pull-requests/7.diff").as_str())
+        .with_status(200)
+        .with_body("diff --git a/x b/x\n")
+        .create();
+    let approve = mockito::mock("POST", format!("{REPO}/pull-requests/7/approve").as_str())
+        .with_status(200)
+        .with_body(r#"{"approved": true}"#)
+        .create();
+
+    let client = make_dc_client(&mockito::server_url());
+    assert!(client.get_pullrequest_diff("PROJ", "repo", "7").await.unwrap().starts_with("diff --git"));
+    client.approve_pullrequest("PROJ", "repo", "7").await.unwrap();
+    approve.assert();
+}
+
+#[tokio::test]
+async fn test_cloud_only_methods_are_unsupported_on_data_center() {
+    let any = mockito::mock("GET", Matcher::Regex("^/repositories/".to_string())).expect(0).create();
+
+    let client = make_dc_client(&mockito::server_url());
+    let err = client.list_issues("PROJ", "repo", &Filter::default(), &PageOptions::default(), None).await.unwrap_err();
+
+    assert!(matches!(err, BitbucketError::Unsupported(_)), "{err:?}");
+    any.assert();
+}
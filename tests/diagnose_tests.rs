@@ -0,0 +1,85 @@
+mod common;
+
+use bitbucket_mcp::common::bitbucket::BitbucketTool;
+use bitbucket_mcp::common::diagnose::{has_scope, parse_scopes_header, required_scope};
+use common::{make_client, make_dc_client};
+
+#[test]
+fn test_scope_matching() {
+    let granted = parse_scopes_header("account, pullrequest:write, repository:admin");
+
+    assert!(has_scope(&granted, "pullrequest"));
+    assert!(has_scope(&granted, "repository:write"));
+    assert!(!has_scope(&granted, "issue"));
+    assert!(!has_scope(&granted, "pipeline:write"));
+
+    // Pull request access implies the same access to repositories.
+    assert!(has_scope(&parse_scopes_header("pullrequest"), "repository"));
+    assert!(!has_scope(&parse_scopes_header("pullrequest"), "repository:write"));
+
+    let api_token = parse_scopes_header("read:account:bitbucket write:issue:bitbucket");
+    assert!(has_scope(&api_token, "issue"));
+    assert!(has_scope(&api_token, "issue:write"));
+    assert!(!has_scope(&api_token, "snippet"));
+}
+
+#[test]
+fn test_every_tool_has_a_required_scope() {
+    for tool in BitbucketTool::new(make_client("http://localhost")).tools() {
+        let exempt = ["get_rate_limit_status", "get_diagnostics", "read_audit_log", "diagnose_auth"];
+        assert!(exempt.contains(&&*tool.name) || required_scope(&tool.name).is_some(), "{} has no required scope", tool.name);
+    }
+}
+
+#[tokio::test]
+async fn test_diagnose_auth_reports_toolsets_from_scopes() {
+    let _user = mockito::mock("GET", "/scoped/2.0/user")
+        .with_status(200)
+        .with_header("x-oauth-scopes", "account, repository, pullrequest")
+        .with_body(r#"{"display_name": "Jane", "account_id": "123", "links": {}}"#)
+        .create();
+
+    let tool = BitbucketTool::new(make_client(&format!("{}/scoped", mockito::server_url())));
+    let report = tool.auth_report().await;
+
+    assert_eq!(report["authenticated"], true);
+    assert_eq!(report["user"], serde_json::json!({"display_name": "Jane", "account_id": "123"}));
+    assert_eq!(report["toolsets"]["core"]["status"], "ok");
+    assert_eq!(report["toolsets"]["pullrequests"]["status"], "partial");
+    assert_eq!(report["toolsets"]["pullrequests"]["missing_scopes"], serde_json::json!(["pullrequest:write"]));
+    assert_eq!(report["toolsets"]["issues"]["status"], "unavailable");
+    let warnings = report["warnings"].to_string();
+    assert!(warnings.contains("merge_pullrequest needs the 'pullrequest:write' scope"), "{warnings}");
+    assert!(!warnings.contains("get_pullrequest "), "{warnings}");
+}
+
+#[tokio::test]
+async fn test_diagnose_auth_without_scope_header_or_credentials() {
+    let _user = mockito::mock("GET", "/basic/2.0/user").with_status(200).with_body(r#"{"display_name": "Jane"}"#).create();
+    let _denied = mockito::mock("GET", "/denied/2.0/user")
+        .with_status(401)
+        .with_body(r#"{"error": {"message": "Invalid credentials"}}"#)
+        .create();
+
+    let report = BitbucketTool::new(make_client(&format!("{}/basic", mockito::server_url()))).auth_report().await;
+    assert_eq!(report["toolsets"]["repos"]["status"], "unknown");
+    assert!(report["note"].is_string());
+
+    let report = BitbucketTool::new(make_client(&format!("{}/denied", mockito::server_url()))).auth_report().await;
+    assert_eq!(report["authenticated"], false);
+    assert!(report["error"].as_str().unwrap().contains("Invalid credentials"));
+}
+
+#[tokio::test]
+async fn test_diagnose_auth_on_data_center_reads_username_header() {
+    let _props = mockito::mock("GET", "/rest/api/1.0/application-properties")
+        .with_status(200)
+        .with_header("x-ausername", "jdoe")
+        .with_body(r#"{"version": "8.19.0"}"#)
+        .create();
+
+    let report = BitbucketTool::new(make_dc_client(&mockito::server_url())).auth_report().await;
+    assert_eq!(report["authenticated"], true);
+    assert_eq!(report["user"]["name"], "jdoe");
+    assert_eq!(report["backend"], "datacenter");
+}
@@ -0,0 +1,114 @@
+mod common;
+
+use std::path::PathBuf;
+use bitbucket_mcp::common::disk_cache::{self, DiskCache};
+use common::make_client;
+
+const HASH: &str = "0123456789abcdef0123456789abcdef01234567";
+
+fn temp_dir(name: &str) -> PathBuf {
+    let dir = std::env::temp_dir().join(format!("bitbucket-mcp-disk-cache-{name}-{}", std::process::id()));
+    let _ = std::fs::remove_dir_all(&dir);
+    dir
+}
+
+#[test]
+fn test_only_full_hashes_are_immutable() {
+    assert!(disk_cache::is_commit_hash(HASH));
+    assert!(disk_cache::is_commit_hash(&"a".repeat(64)));
+    assert!(!disk_cache::is_commit_hash("main"));
+    assert!(!disk_cache::is_commit_hash("0123456"));
+    assert!(!disk_cache::is_commit_hash(&"g".repeat(40)));
+}
+
+#[tokio::test]
+async fn test_file_blobs_at_a_hash_are_stored_raw_and_survive_a_restart() {
+    let dir = temp_dir("blob");
+    let path = format!("/2.0/repositories/disk/blob/src/{HASH}/README.md");
+    let get = mockito::mock("GET", path.as_str())
+        .with_status(200)
+        .with_header("content-type", "text/plain")
+        .with_body("# README\n")
+        .expect(1)
+        .create();
+
+    for _ in 0..2 {
+        // A new client per iteration stands in for a server restart.
+        let client = make_client(&mockito::server_url()).with_disk_cache(Some(DiskCache::new(&dir, 1 << 20).unwrap()));
+        assert_eq!(client.get_file_source("disk", "blob", HASH, "README.md").await.unwrap(), "# README\n");
+    }
+
+    get.assert();
+    // Another identity does not see the entry.
+    let other = bitbucket_mcp::common::bitbucket::BitbucketClient::new("other", "secret", format!("{}/2.0", mockito::server_url()))
+        .with_disk_cache(Some(DiskCache::new(&dir, 1 << 20).unwrap()));
+    let refetch = mockito::mock("GET", path.as_str()).with_status(200).with_body("# README\n").expect(1).create();
+    other.get_file_source("disk", "blob", HASH, "README.md").await.unwrap();
+    refetch.assert();
+    let _ = std::fs::remove_dir_all(&dir);
+}
+
+#[tokio::test]
+async fn test_commits_are_not_cached_on_disk() {
+    let dir = temp_dir("commit");
+    let path = format!("/2.0/repositories/disk/commit/commit/{HASH}");
+    let client = make_client(&mockito::server_url()).with_disk_cache(Some(DiskCache::new(&dir, 1 << 20).unwrap()));
+
+    let unapproved = mockito::mock("GET", path.as_str())
+        .with_status(200)
+        .with_body(format!(r#"{{"hash": "{HASH}", "participants": []}}"#))
+        .create();
+    assert_eq!(client.get_commit("disk", "commit", HASH, None).await.unwrap()["participants"], serde_json::json!([]));
+    drop(unapproved);
+
+    // Approvals given after the first call are visible on the next one.
+    let _approved = mockito::mock("GET", path.as_str())
+        .with_status(200)
+        .with_body(format!(r#"{{"hash": "{HASH}", "participants": [{{"approved": true}}]}}"#))
+        .create();
+    assert_eq!(client.get_commit("disk", "commit", HASH, None).await.unwrap()["participants"][0]["approved"], true);
+    assert_eq!(client.diagnostics()["disk_cache"]["entries"], 0);
+    let _ = std::fs::remove_dir_all(&dir);
+}
+
+#[tokio::test]
+async fn test_branch_names_bypass_the_disk_cache() {
+    let dir = temp_dir("branch");
+    let get = mockito::mock("GET", "/2.0/repositories/disk/branch/src/main/README.md")
+        .with_status(200)
+        .with_body("# README\n")
+        .expect(2)
+        .create();
+
+    let client = make_client(&mockito::server_url()).with_disk_cache(Some(DiskCache::new(&dir, 1 << 20).unwrap()));
+    for _ in 0..2 {
+        assert_eq!(client.get_file_source("disk", "branch", "main", "README.md").await.unwrap(), "# README\n");
+    }
+
+    get.assert();
+    assert_eq!(client.diagnostics()["disk_cache"]["entries"], 0);
+    let _ = std::fs::remove_dir_all(&dir);
+}
+
+#[tokio::test]
+async fn test_least_recently_used_entries_are_evicted_over_the_cap() {
+    let dir = temp_dir("evict");
+    let body = vec![b'x'; 100];
+    let cache = DiskCache::new(&dir, 200).unwrap();
+
+    cache.put("a", body.clone()).await;
+    cache.put("b", body.clone()).await;
+    assert!(cache.get("a").await.is_some());
+    cache.put("c", body.clone()).await;
+
+    assert!(cache.get("a").await.is_some());
+    assert!(cache.get("b").await.is_none());
+    assert!(cache.get("c").await.is_some());
+    let stats = cache.stats();
+    assert_eq!((stats["entries"].as_u64(), stats["bytes"].as_u64(), stats["evicted"].as_u64()), (Some(2), Some(200), Some(1)));
+
+    // Sizes are read back from the directory on startup, and a lower cap evicts immediately.
+    let reopened = DiskCache::new(&dir, 100).unwrap();
+    assert_eq!((reopened.stats()["entries"].as_u64(), reopened.stats()["bytes"].as_u64()), (Some(1), Some(100)));
+    let _ = std::fs::remove_dir_all(&dir);
+}
@@ -0,0 +1,62 @@
+mod common;
+
+use bitbucket_mcp::common::bitbucket::{BitbucketTool, DRY_RUN_PARAM};
+use bitbucket_mcp::common::error::BitbucketError;
+use bitbucket_mcp::common::scope::RepoScope;
+use common::{make_client, make_dc_client};
+use mockito::Matcher;
+use serde_json::json;
+
+#[tokio::test]
+async fn test_dry_run_renders_request_without_sending() {
+    let server_url = mockito::server_url();
+    let post = mockito::mock("POST", Matcher::Regex("^/2.0/repositories/ws/dry/".to_string())).expect(0).create();
+    let delete = mockito::mock("DELETE", "/2.0/repositories/ws/dry").expect(0).create();
+
+    let client = make_client(&server_url).with_dry_run(true);
+    let body = json!({"title": "Bump deps", "source": {"branch": {"name": "deps"}}});
+    let rendered = client.create_pullrequest("ws", "dry", body.clone()).await.unwrap();
+
+    assert_eq!(rendered["dry_run"], true);
+    assert_eq!(rendered["method"], "POST");
+    assert_eq!(rendered["url"], format!("{server_url}/2.0/repositories/ws/dry/pullrequests"));
+    assert_eq!(rendered["body"], body);
+    assert_eq!(rendered["headers"]["Authorization"], "Basic ***");
+    assert!(!rendered.to_string().contains("testpass"));
+
+    let rendered = client.delete_repository("ws", "dry").await.unwrap();
+    assert_eq!(rendered["method"], "DELETE");
+    assert!(rendered["body"].is_null());
+    post.assert();
+    delete.assert();
+}
+
+#[tokio::test]
+async fn test_dry_run_still_reads_and_enforces_policy() {
+    let _get = mockito::mock("GET", "/rest/api/1.0/projects/PROJ/repos/repo/pull-requests/9")
+        .with_status(200)
+        .with_body(r#"{"id": 9, "version": 4}"#)
+        .create();
+    let merge = mockito::mock("POST", Matcher::Regex("/merge".to_string())).expect(0).create();
+
+    let client = make_dc_client(&mockito::server_url()).with_dry_run(true);
+    let rendered = client.merge_pullrequest("PROJ", "repo", "9", None).await.unwrap();
+    assert!(rendered["url"].as_str().unwrap().ends_with("/pull-requests/9/merge?version=4"), "{rendered}");
+    merge.assert();
+
+    let scoped = make_client(&mockito::server_url()).with_dry_run(true).with_scope(RepoScope::new(&["ws/allowed".to_string()]).unwrap());
+    let err = scoped.delete_repository("ws", "other").await.unwrap_err();
+    assert!(matches!(err, BitbucketError::Denied(_)), "{err:?}");
+    let err = scoped.with_read_only(true).delete_repository("ws", "allowed").await.unwrap_err();
+    assert!(matches!(err, BitbucketError::Denied(_)), "{err:?}");
+}
+
+#[test]
+fn test_mutating_tools_advertise_dry_run() {
+    let tools = BitbucketTool::new(make_client("http://localhost")).tools();
+    let has_param = |name: &str| tools.iter().find(|t| t.name == name).unwrap().input_schema["properties"].get(DRY_RUN_PARAM).is_some();
+
+    assert!(has_param("merge_pullrequest"));
+    assert!(has_param("update_issue"));
+    assert!(!has_param("get_repository"));
+}
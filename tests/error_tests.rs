@@ -0,0 +1,112 @@
+mod common;
+
+use bitbucket_mcp::common::error::BitbucketError;
+use bitbucket_mcp::common::pagination::PageOptions;
+use bitbucket_mcp::common::query::Filter;
+use common::make_client;
+use reqwest::StatusCode;
+
+#[tokio::test]
+async fn test_not_found_maps_to_typed_error() {
+    let _m = mockito::mock("GET", "/2.0/repositories/ws/missing")
+        .with_status(404)
+        .with_body(r#"{"type": "error", "error": {"message": "Repository ws/missing not found"}}"#)
+        .create();
+
+    let client = make_client(&mockito::server_url());
+    let err = client.get_repository("ws", "missing", None).await.unwrap_err();
+
+    match &err {
+        BitbucketError::NotFound { path, message } => {
+            assert_eq!(path, "/repositories/ws/missing");
+            assert_eq!(message, "Repository ws/missing not found");
+        }
+        other => panic!("expected NotFound, got {other:?}"),
+    }
+    assert_eq!(err.status(), Some(StatusCode::NOT_FOUND));
+    assert!(err.to_string().contains("repository slug"));
+}
+
+#[tokio::test]
+async fn test_forbidden_mentions_scope() {
+    let _m = mockito::mock("GET", "/2.0/repositories/ws/repo/pipelines/")
+        .with_status(403)
+        .with_body(r#"{"type": "error", "error": {"message": "Your credentials lack one or more required privilege scopes."}}"#)
+        .create();
+
+    let client = make_client(&mockito::server_url());
+    let err = client.list_pipelines("ws", "repo", &Filter::default(), &PageOptions::default(), None).await.unwrap_err();
+
+    assert!(matches!(err, BitbucketError::Forbidden { .. }));
+    assert!(err.to_string().contains("scope"));
+}
+
+#[tokio::test]
+async fn test_validation_error_includes_fields() {
+    let _m = mockito::mock("POST", "/2.0/repositories/ws/repo/pullrequests")
+        .with_status(400)
+        .with_body(r#"{"type": "error", "error": {"message": "Bad request", "fields": {"source": ["This field is required."]}}}"#)
+        .create();
+
+    let client = make_client(&mockito::server_url());
+    let err = client
+        .create_pullrequest("ws", "repo", serde_json::json!({"title": "PR"}))
+        .await
+        .unwrap_err();
+
+    match &err {
+        BitbucketError::Validation { message, fields } => {
+            assert_eq!(message, "Bad request");
+            assert_eq!(fields["source"], vec!["This field is required.".to_string()]);
+        }
+        other => panic!("expected Validation, got {other:?}"),
+    }
+    assert!(err.to_string().contains("source: This field is required."));
+}
+
+#[tokio::test]
+async fn test_conflict_and_unauthorized() {
+    let _m1 = mockito::mock("POST", "/2.0/repositories/ws/repo/pullrequests/1/merge")
+        .with_status(409)
+        .with_body(r#"{"type": "error", "error": {"message": "Pull request is already merged"}}"#)
+        .create();
+    let _m2 = mockito::mock("GET", "/2.0/user")
+        .with_status(401)
+        .with_body("Unauthorized")
+        .create();
+
+    let client = make_client(&mockito::server_url());
+    let merge = client.merge_pullrequest("ws", "repo", "1", None).await.unwrap_err();
+    assert!(matches!(merge, BitbucketError::Conflict { .. }));
+
+    let user = client.get_user(None).await.unwrap_err();
+    match user {
+        BitbucketError::Unauthorized { message } => assert_eq!(message, "Unauthorized"),
+        other => panic!("expected Unauthorized, got {other:?}"),
+    }
+}
+
+#[tokio::test]
+async fn test_unexpected_status_maps_to_api_error() {
+    let _m = mockito::mock("GET", "/2.0/workspaces/ws")
+        .with_status(418)
+        .with_body("")
+        .create();
+
+    let client = make_client(&mockito::server_url());
+    let err = client.get_workspace("ws", None).await.unwrap_err();
+
+    assert_eq!(err.status(), Some(StatusCode::IM_A_TEAPOT));
+    assert!(err.to_string().contains("no response body"));
+}
+
+#[tokio::test]
+async fn test_no_content_returns_empty_object() {
+    let _m = mockito::mock("DELETE", "/2.0/repositories/ws/repo/pullrequests/1/approve")
+        .with_status(204)
+        .create();
+
+    let client = make_client(&mockito::server_url());
+    let result = client.unapprove_pullrequest("ws", "repo", "1").await.unwrap();
+    assert_eq!(result, serde_json::json!({}));
+}
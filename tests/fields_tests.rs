@@ -0,0 +1,100 @@
+mod common;
+
+use bitbucket_mcp::common::fields::{self, Resource};
+use bitbucket_mcp::common::pagination::PageOptions;
+use bitbucket_mcp::common::query::Filter;
+use common::make_client;
+use mockito::Matcher;
+use serde_json::json;
+
+#[tokio::test]
+async fn test_get_forwards_fields_projection() {
+    let _m = mockito::mock("GET", "/2.0/repositories/ws/repo?fields=slug%2Cfull_name")
+        .with_status(200)
+        .with_body(r#"{"slug": "repo", "full_name": "ws/repo"}"#)
+        .create();
+
+    let client = make_client(&mockito::server_url());
+    let result = client.get_repository("ws", "repo", Some("slug,full_name")).await.unwrap();
+
+    assert_eq!(result["full_name"], "ws/repo");
+}
+
+#[tokio::test]
+async fn test_list_projection_keeps_next_link() {
+    let _m = mockito::mock("GET", Matcher::Regex(r"^/2\.0/repositories/ws/repo/pullrequests$".to_string()))
+        .match_query(Matcher::UrlEncoded("fields".into(), "values.id,next".into()))
+        .with_status(200)
+        .with_body(r#"{"values": [{"id": 1}]}"#)
+        .create();
+
+    let client = make_client(&mockito::server_url());
+    let result = client
+        .list_pullrequests("ws", "repo", &Filter::default(), &PageOptions::default(), Some("values.id"))
+        .await
+        .unwrap();
+
+    assert_eq!(result["size"], 1);
+}
+
+#[tokio::test]
+async fn test_full_projection_sends_no_fields_parameter() {
+    let _m = mockito::mock("GET", "/2.0/workspaces/ws")
+        .with_status(200)
+        .with_body(r#"{"slug": "ws"}"#)
+        .create();
+
+    let client = make_client(&mockito::server_url());
+    let result = client.get_workspace("ws", Some(fields::FULL)).await.unwrap();
+
+    assert_eq!(result["slug"], "ws");
+}
+
+#[test]
+fn test_compact_profile_strips_links_and_avatars_at_any_depth() {
+    let pr = json!({
+        "id": 1,
+        "title": "Fix",
+        "summary": {"raw": "body", "html": "<p>body</p>"},
+        "links": {"self": {"href": "https://api.bitbucket.org/..."}},
+        "author": {"display_name": "Jane", "links": {"avatar": {"href": "https://avatar"}}},
+        "reviewers": [{"display_name": "Sam", "links": {}}],
+    });
+
+    let compact = fields::compact(pr, Resource::PullRequest);
+
+    assert_eq!(
+        compact,
+        json!({
+            "id": 1,
+            "title": "Fix",
+            "author": {"display_name": "Jane"},
+            "reviewers": [{"display_name": "Sam"}],
+        })
+    );
+}
+
+#[test]
+fn test_compact_profile_applies_to_every_listing_item() {
+    let listing = json!({
+        "values": [
+            {"hash": "a", "message": "m", "summary": {"raw": "m"}, "links": {}},
+            {"hash": "b", "message": "n", "rendered": {"message": {}}},
+        ],
+        "size": 2,
+        "next": "https://api.bitbucket.org/2.0/repositories/ws/repo/commits?page=2",
+    });
+
+    let compact = fields::compact(listing, Resource::Commit);
+
+    assert_eq!(compact["values"], json!([{"hash": "a", "message": "m"}, {"hash": "b", "message": "n"}]));
+    assert_eq!(compact["next"], "https://api.bitbucket.org/2.0/repositories/ws/repo/commits?page=2");
+}
+
+#[test]
+fn test_explicit_projection_is_returned_unchanged() {
+    let value = json!({"links": {"html": {"href": "https://bitbucket.org/ws/repo"}}});
+    assert_eq!(fields::present(value.clone(), Resource::Repository, Some("links.html")), value);
+    assert_eq!(fields::present(value.clone(), Resource::Repository, Some(fields::FULL)), value);
+    assert_eq!(fields::present(value, Resource::Repository, None), json!({}));
+}
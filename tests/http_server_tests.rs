@@ -0,0 +1,173 @@
+mod common;
+
+use bitbucket_mcp::common::auth::Credentials;
+use bitbucket_mcp::common::bitbucket::BitbucketClient;
+use bitbucket_mcp::common::error::BitbucketError;
+use bitbucket_mcp::common::http_server;
+use common::make_client;
+use serde_json::json;
+use tokio::net::TcpListener;
+use tokio_util::sync::CancellationToken;
+
+async fn start_server(base: BitbucketClient) -> (String, CancellationToken) {
+    let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let url = format!("http://{}", listener.local_addr().unwrap());
+    let ct = CancellationToken::new();
+    tokio::spawn(http_server::serve(listener, base, ct.clone()));
+    (url, ct)
+}
+
+/// Reads the next server-sent event and returns its `data` field.
+async fn next_event(resp: &mut reqwest::Response, buffer: &mut String) -> String {
+    loop {
+        if let Some(end) = buffer.find("\n\n") {
+            let event: String = buffer.drain(..end + 2).collect();
+            return event
+                .lines()
+                .filter_map(|line| line.strip_prefix("data:"))
+                .map(str::trim)
+                .collect();
+        }
+        let chunk = resp.chunk().await.unwrap().expect("event stream ended");
+        buffer.push_str(&String::from_utf8_lossy(&chunk));
+    }
+}
+
+/// An initialized MCP session over SSE.
+struct Session {
+    http: reqwest::Client,
+    sse: reqwest::Response,
+    buffer: String,
+    /// URL the session posts its JSON-RPC messages to.
+    endpoint: String,
+    bearer: Option<String>,
+}
+
+impl Session {
+    async fn open(url: &str, bearer: Option<&str>) -> Self {
+        let http = reqwest::Client::new();
+        let mut request = http.get(format!("{url}/sse"));
+        if let Some(token) = bearer {
+            request = request.bearer_auth(token);
+        }
+        let mut sse = request.send().await.unwrap();
+        assert_eq!(sse.status(), 200);
+        let mut buffer = String::new();
+        let endpoint = format!("{url}{}", next_event(&mut sse, &mut buffer).await);
+        let mut session = Self { http, sse, buffer, endpoint, bearer: bearer.map(str::to_string) };
+
+        let initialize = json!({
+            "jsonrpc": "2.0", "id": 1, "method": "initialize",
+            "params": {"protocolVersion": "2024-11-05", "capabilities": {}, "clientInfo": {"name": "test", "version": "1"}}
+        });
+        assert_eq!(session.post(initialize).await.status(), 202);
+        session.next_event().await;
+        session.post(json!({"jsonrpc": "2.0", "method": "notifications/initialized"})).await;
+        session
+    }
+
+    async fn post(&self, body: serde_json::Value) -> reqwest::Response {
+        let mut request = self.http.post(&self.endpoint).json(&body);
+        if let Some(token) = &self.bearer {
+            request = request.bearer_auth(token);
+        }
+        request.send().await.unwrap()
+    }
+
+    async fn next_event(&mut self) -> String {
+        next_event(&mut self.sse, &mut self.buffer).await
+    }
+
+    /// Calls a tool without arguments and returns the raw JSON-RPC response.
+    async fn call_tool(&mut self, id: u64, name: &str) -> String {
+        let call = json!({"jsonrpc": "2.0", "id": id, "method": "tools/call", "params": {"name": name, "arguments": {}}});
+        assert_eq!(self.post(call).await.status(), 202);
+        self.next_event().await
+    }
+}
+
+#[test]
+fn test_parses_basic_and_bearer_authorization_headers() {
+    assert_eq!(
+        Credentials::from_authorization_header("Basic amFuZUBleGFtcGxlLmNvbTpzZWNyZXQ6cGFydA==").unwrap(),
+        Credentials::basic("jane@example.com", "secret:part")
+    );
+    assert_eq!(Credentials::from_authorization_header("bearer tok123").unwrap(), Credentials::bearer("tok123"));
+
+    for invalid in ["Bearer", "Basic !!!", "Basic amFuZQ==", "Digest abc"] {
+        let err = Credentials::from_authorization_header(invalid).unwrap_err();
+        assert!(matches!(err, BitbucketError::Unauthorized { .. }), "{invalid}: {err:?}");
+    }
+}
+
+#[test]
+fn test_credentials_debug_hides_secrets() {
+    let debug = format!("{:?} {:?}", Credentials::basic("jane", "hunter2"), Credentials::bearer("tok123"));
+    assert!(debug.contains("jane"));
+    assert!(!debug.contains("hunter2") && !debug.contains("tok123"), "{debug}");
+}
+
+#[tokio::test]
+async fn test_rejects_session_without_credentials() {
+    let base = BitbucketClient::anonymous(format!("{}/2.0", mockito::server_url()));
+    let (url, ct) = start_server(base).await;
+
+    let resp = reqwest::get(format!("{url}/sse")).await.unwrap();
+    assert_eq!(resp.status(), 401);
+    assert!(resp.headers().contains_key("www-authenticate"));
+    ct.cancel();
+}
+
+#[tokio::test]
+async fn test_session_uses_credentials_from_authorization_header() {
+    let user = mockito::mock("GET", "/2.0/user")
+        .match_header("authorization", "Bearer session-token")
+        .with_status(200)
+        .with_body(r#"{"display_name": "Session User"}"#)
+        .create();
+    // The fallback credentials from `make_client` must not be used when the session sends its own.
+    let (url, ct) = start_server(make_client(&mockito::server_url())).await;
+    let mut session = Session::open(&url, Some("session-token")).await;
+
+    let result = session.call_tool(2, "get_user").await;
+    assert!(result.contains("Session User"), "{result}");
+    user.assert();
+
+    // Another caller holding the session id but different credentials cannot post to the session.
+    let hijack = session
+        .http
+        .post(&session.endpoint)
+        .bearer_auth("other-token")
+        .json(&json!({"jsonrpc": "2.0", "method": "ping", "id": 3}))
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(hijack.status(), 401);
+    ct.cancel();
+}
+
+#[tokio::test]
+async fn test_serves_concurrent_sessions_independently() {
+    let workspaces = mockito::mock("GET", "/concurrent/2.0/workspaces")
+        .with_status(200)
+        .with_body(r#"{"values": [{"slug": "shared-ws"}]}"#)
+        .expect(3)
+        .create();
+    // With fallback credentials, sessions may connect without their own Authorization header.
+    let (url, ct) = start_server(make_client(&format!("{}/concurrent", mockito::server_url()))).await;
+
+    let mut sessions = Vec::new();
+    for _ in 0..3 {
+        sessions.push(Session::open(&url, None).await);
+    }
+    assert_eq!(sessions.iter().map(|s| s.endpoint.clone()).collect::<std::collections::HashSet<_>>().len(), 3);
+
+    // Every session stays open while the others are used, and each gets its own response.
+    let results = futures::future::join_all(sessions.iter_mut().enumerate().map(|(i, session)| session.call_tool(10 + i as u64, "list_workspaces"))).await;
+    for (i, result) in results.iter().enumerate() {
+        assert!(result.contains("shared-ws"), "{result}");
+        assert!(result.contains(&format!(r#""id":{}"#, 10 + i)), "{result}");
+    }
+    workspaces.assert();
+    ct.cancel();
+}
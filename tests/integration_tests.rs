@@ -3,6 +3,8 @@
 // to avoid modifying the actual Bitbucket workspace
 
 use bitbucket_mcp::common::bitbucket::BitbucketClient;
+use bitbucket_mcp::common::pagination::PageOptions;
+use bitbucket_mcp::common::query::Filter;
 use std::env;
 
 fn get_integration_client() -> Option<BitbucketClient> {
@@ -10,12 +12,7 @@ fn get_integration_client() -> Option<BitbucketClient> {
     let api_username = env::var("BITBUCKET_API_USERNAME").ok()?;
     let api_token = env::var("BITBUCKET_API_TOKEN").ok()?;
     
-    Some(BitbucketClient {
-        api_username,
-        api_token,
-        client: reqwest::Client::new(),
-        base_url: "https://api.bitbucket.org/2.0".to_string(),
-    })
+    Some(BitbucketClient::new(api_username, api_token, "https://api.bitbucket.org/2.0"))
 }
 
 fn get_test_workspace() -> Option<String> {
@@ -34,7 +31,7 @@ async fn integration_test_get_user() {
         }
     };
     
-    let result = client.get_user().await;
+    let result = client.get_user(None).await;
     assert!(result.is_ok(), "Failed to get user: {:?}", result.err());
     
     let user = result.unwrap();
@@ -50,7 +47,7 @@ async fn integration_test_list_workspaces() {
         None => return,
     };
     
-    let result = client.list_workspaces().await;
+    let result = client.list_workspaces(&PageOptions::default(), None).await;
     assert!(result.is_ok(), "Failed to list workspaces: {:?}", result.err());
     
     let workspaces = result.unwrap();
@@ -74,7 +71,7 @@ async fn integration_test_get_workspace() {
         None => return,
     };
     
-    let result = client.get_workspace(&workspace).await;
+    let result = client.get_workspace(&workspace, None).await;
     assert!(result.is_ok(), "Failed to get workspace: {:?}", result.err());
     
     let workspace_data = result.unwrap();
@@ -95,7 +92,7 @@ async fn integration_test_list_repositories() {
         None => return,
     };
     
-    let result = client.list_repositories(&workspace).await;
+    let result = client.list_repositories(&workspace, &Filter::default(), &PageOptions::default(), None).await;
     assert!(result.is_ok(), "Failed to list repositories: {:?}", result.err());
     
     let repos = result.unwrap();
@@ -123,22 +120,22 @@ async fn integration_test_get_repository() {
     };
     
     // First, list repositories to get an actual repo
-    let repos_result = client.list_repositories(&workspace).await;
-    if let Ok(repos) = repos_result {
-        if let Some(values) = repos["values"].as_array() {
-            if !values.is_empty() {
-                let first_repo_slug = values[0]["slug"].as_str().unwrap();
-                println!("Testing with repository: {}", first_repo_slug);
+    let repos_result = client.list_repositories(&workspace, &Filter::default(), &PageOptions::default(), None).await;
+    if let Ok(repos) = repos_result
+        && let Some(values) = repos["values"].as_array()
+    {
+        if !values.is_empty() {
+            let first_repo_slug = values[0]["slug"].as_str().unwrap();
+            println!("Testing with repository: {}", first_repo_slug);
                 
-                let result = client.get_repository(&workspace, first_repo_slug).await;
-                assert!(result.is_ok(), "Failed to get repository: {:?}", result.err());
+            let result = client.get_repository(&workspace, first_repo_slug, None).await;
+            assert!(result.is_ok(), "Failed to get repository: {:?}", result.err());
                 
-                let repo = result.unwrap();
-                println!("Repository: {}", serde_json::to_string_pretty(&repo).unwrap());
-                assert_eq!(repo["slug"], first_repo_slug);
-            } else {
-                println!("No repositories found in workspace");
-            }
+            let repo = result.unwrap();
+            println!("Repository: {}", serde_json::to_string_pretty(&repo).unwrap());
+            assert_eq!(repo["slug"], first_repo_slug);
+        } else {
+            println!("No repositories found in workspace");
         }
     }
 }
@@ -157,23 +154,22 @@ async fn integration_test_list_pullrequests() {
     };
     
     // First, get a repository
-    let repos_result = client.list_repositories(&workspace).await;
-    if let Ok(repos) = repos_result {
-        if let Some(values) = repos["values"].as_array() {
-            if !values.is_empty() {
-                let first_repo_slug = values[0]["slug"].as_str().unwrap();
-                println!("Listing PRs for repository: {}", first_repo_slug);
+    let repos_result = client.list_repositories(&workspace, &Filter::default(), &PageOptions::default(), None).await;
+    if let Ok(repos) = repos_result
+        && let Some(values) = repos["values"].as_array()
+        && !values.is_empty()
+    {
+        let first_repo_slug = values[0]["slug"].as_str().unwrap();
+        println!("Listing PRs for repository: {}", first_repo_slug);
                 
-                let result = client.list_pullrequests(&workspace, first_repo_slug).await;
-                assert!(result.is_ok(), "Failed to list pull requests: {:?}", result.err());
+        let result = client.list_pullrequests(&workspace, first_repo_slug, &Filter::default(), &PageOptions::default(), None).await;
+        assert!(result.is_ok(), "Failed to list pull requests: {:?}", result.err());
                 
-                let prs = result.unwrap();
-                println!("Pull Requests: {}", serde_json::to_string_pretty(&prs).unwrap());
+        let prs = result.unwrap();
+        println!("Pull Requests: {}", serde_json::to_string_pretty(&prs).unwrap());
                 
-                let values = prs["values"].as_array().unwrap();
-                println!("Found {} pull requests (after pagination)", values.len());
-            }
-        }
+        let values = prs["values"].as_array().unwrap();
+        println!("Found {} pull requests (after pagination)", values.len());
     }
 }
 
@@ -191,40 +187,41 @@ async fn integration_test_list_pullrequest_comments() {
     };
     
     // First, get a repository and a PR
-    let repos_result = client.list_repositories(&workspace).await;
-    if let Ok(repos) = repos_result {
-        if let Some(repo_values) = repos["values"].as_array() {
-            if !repo_values.is_empty() {
-                let first_repo_slug = repo_values[0]["slug"].as_str().unwrap();
+    let repos_result = client.list_repositories(&workspace, &Filter::default(), &PageOptions::default(), None).await;
+    if let Ok(repos) = repos_result
+        && let Some(repo_values) = repos["values"].as_array()
+        && !repo_values.is_empty()
+    {
+        let first_repo_slug = repo_values[0]["slug"].as_str().unwrap();
                 
-                let prs_result = client.list_pullrequests(&workspace, first_repo_slug).await;
-                if let Ok(prs) = prs_result {
-                    if let Some(pr_values) = prs["values"].as_array() {
-                        if !pr_values.is_empty() {
-                            let first_pr_id = pr_values[0]["id"].as_i64().unwrap();
-                            println!("Listing comments for PR: {}", first_pr_id);
+        let prs_result = client.list_pullrequests(&workspace, first_repo_slug, &Filter::default(), &PageOptions::default(), None).await;
+        if let Ok(prs) = prs_result
+            && let Some(pr_values) = prs["values"].as_array()
+        {
+            if !pr_values.is_empty() {
+                let first_pr_id = pr_values[0]["id"].as_i64().unwrap();
+                println!("Listing comments for PR: {}", first_pr_id);
                             
-                            let result = client.list_pullrequest_comments(
-                                &workspace,
-                                first_repo_slug,
-                                &first_pr_id.to_string()
-                            ).await;
+                let result = client.list_pullrequest_comments(
+                    &workspace,
+                    first_repo_slug,
+                    &first_pr_id.to_string(),
+                    &PageOptions::default(),
+                    None,
+                ).await;
                             
-                            assert!(result.is_ok(), "Failed to list PR comments: {:?}", result.err());
+                assert!(result.is_ok(), "Failed to list PR comments: {:?}", result.err());
                             
-                            let comments = result.unwrap();
-                            println!("PR Comments: {}", serde_json::to_string_pretty(&comments).unwrap());
+                let comments = result.unwrap();
+                println!("PR Comments: {}", serde_json::to_string_pretty(&comments).unwrap());
                             
-                            let values = comments["values"].as_array().unwrap();
-                            println!("Found {} comments (after pagination)", values.len());
+                let values = comments["values"].as_array().unwrap();
+                println!("Found {} comments (after pagination)", values.len());
                             
-                            // Verify pagination was applied
-                            assert!(comments.get("size").is_some());
-                        } else {
-                            println!("No PRs found");
-                        }
-                    }
-                }
+                // Verify pagination was applied
+                assert!(comments.get("size").is_some());
+            } else {
+                println!("No PRs found");
             }
         }
     }
@@ -243,23 +240,22 @@ async fn integration_test_list_branches() {
         None => return,
     };
     
-    let repos_result = client.list_repositories(&workspace).await;
-    if let Ok(repos) = repos_result {
-        if let Some(values) = repos["values"].as_array() {
-            if !values.is_empty() {
-                let first_repo_slug = values[0]["slug"].as_str().unwrap();
-                println!("Listing branches for repository: {}", first_repo_slug);
+    let repos_result = client.list_repositories(&workspace, &Filter::default(), &PageOptions::default(), None).await;
+    if let Ok(repos) = repos_result
+        && let Some(values) = repos["values"].as_array()
+        && !values.is_empty()
+    {
+        let first_repo_slug = values[0]["slug"].as_str().unwrap();
+        println!("Listing branches for repository: {}", first_repo_slug);
                 
-                let result = client.list_branches(&workspace, first_repo_slug).await;
-                assert!(result.is_ok(), "Failed to list branches: {:?}", result.err());
+        let result = client.list_branches(&workspace, first_repo_slug, &Filter::default(), &PageOptions::default(), None).await;
+        assert!(result.is_ok(), "Failed to list branches: {:?}", result.err());
                 
-                let branches = result.unwrap();
-                println!("Branches: {}", serde_json::to_string_pretty(&branches).unwrap());
+        let branches = result.unwrap();
+        println!("Branches: {}", serde_json::to_string_pretty(&branches).unwrap());
                 
-                let values = branches["values"].as_array().unwrap();
-                println!("Found {} branches (after pagination)", values.len());
-            }
-        }
+        let values = branches["values"].as_array().unwrap();
+        println!("Found {} branches (after pagination)", values.len());
     }
 }
 
@@ -276,23 +272,22 @@ async fn integration_test_list_tags() {
         None => return,
     };
     
-    let repos_result = client.list_repositories(&workspace).await;
-    if let Ok(repos) = repos_result {
-        if let Some(values) = repos["values"].as_array() {
-            if !values.is_empty() {
-                let first_repo_slug = values[0]["slug"].as_str().unwrap();
-                println!("Listing tags for repository: {}", first_repo_slug);
+    let repos_result = client.list_repositories(&workspace, &Filter::default(), &PageOptions::default(), None).await;
+    if let Ok(repos) = repos_result
+        && let Some(values) = repos["values"].as_array()
+        && !values.is_empty()
+    {
+        let first_repo_slug = values[0]["slug"].as_str().unwrap();
+        println!("Listing tags for repository: {}", first_repo_slug);
                 
-                let result = client.list_tags(&workspace, first_repo_slug).await;
-                assert!(result.is_ok(), "Failed to list tags: {:?}", result.err());
+        let result = client.list_tags(&workspace, first_repo_slug, &PageOptions::default(), None).await;
+        assert!(result.is_ok(), "Failed to list tags: {:?}", result.err());
                 
-                let tags = result.unwrap();
-                println!("Tags: {}", serde_json::to_string_pretty(&tags).unwrap());
+        let tags = result.unwrap();
+        println!("Tags: {}", serde_json::to_string_pretty(&tags).unwrap());
                 
-                let values = tags["values"].as_array().unwrap();
-                println!("Found {} tags (after pagination)", values.len());
-            }
-        }
+        let values = tags["values"].as_array().unwrap();
+        println!("Found {} tags (after pagination)", values.len());
     }
 }
 
@@ -309,27 +304,26 @@ async fn integration_test_list_commits() {
         None => return,
     };
     
-    let repos_result = client.list_repositories(&workspace).await;
-    if let Ok(repos) = repos_result {
-        if let Some(values) = repos["values"].as_array() {
-            if !values.is_empty() {
-                let first_repo_slug = values[0]["slug"].as_str().unwrap();
-                println!("Listing commits for repository: {}", first_repo_slug);
+    let repos_result = client.list_repositories(&workspace, &Filter::default(), &PageOptions::default(), None).await;
+    if let Ok(repos) = repos_result
+        && let Some(values) = repos["values"].as_array()
+        && !values.is_empty()
+    {
+        let first_repo_slug = values[0]["slug"].as_str().unwrap();
+        println!("Listing commits for repository: {}", first_repo_slug);
                 
-                let result = client.list_commits(&workspace, first_repo_slug).await;
-                assert!(result.is_ok(), "Failed to list commits: {:?}", result.err());
+        let result = client.list_commits(&workspace, first_repo_slug, &Filter::default(), &PageOptions::default(), None).await;
+        assert!(result.is_ok(), "Failed to list commits: {:?}", result.err());
                 
-                let commits = result.unwrap();
-                println!("Commits count: {}", commits["values"].as_array().unwrap().len());
+        let commits = result.unwrap();
+        println!("Commits count: {}", commits["values"].as_array().unwrap().len());
                 
-                let values = commits["values"].as_array().unwrap();
-                println!("Found {} commits (after pagination)", values.len());
+        let values = commits["values"].as_array().unwrap();
+        println!("Found {} commits (after pagination)", values.len());
                 
-                // Verify we got commits
-                if !values.is_empty() {
-                    assert!(values[0].get("hash").is_some());
-                }
-            }
+        // Verify we got commits
+        if !values.is_empty() {
+            assert!(values[0].get("hash").is_some());
         }
     }
 }
@@ -347,23 +341,22 @@ async fn integration_test_list_issues() {
         None => return,
     };
     
-    let repos_result = client.list_repositories(&workspace).await;
-    if let Ok(repos) = repos_result {
-        if let Some(values) = repos["values"].as_array() {
-            if !values.is_empty() {
-                let first_repo_slug = values[0]["slug"].as_str().unwrap();
-                println!("Listing issues for repository: {}", first_repo_slug);
+    let repos_result = client.list_repositories(&workspace, &Filter::default(), &PageOptions::default(), None).await;
+    if let Ok(repos) = repos_result
+        && let Some(values) = repos["values"].as_array()
+        && !values.is_empty()
+    {
+        let first_repo_slug = values[0]["slug"].as_str().unwrap();
+        println!("Listing issues for repository: {}", first_repo_slug);
                 
-                let result = client.list_issues(&workspace, first_repo_slug).await;
-                assert!(result.is_ok(), "Failed to list issues: {:?}", result.err());
+        let result = client.list_issues(&workspace, first_repo_slug, &Filter::default(), &PageOptions::default(), None).await;
+        assert!(result.is_ok(), "Failed to list issues: {:?}", result.err());
                 
-                let issues = result.unwrap();
-                println!("Issues: {}", serde_json::to_string_pretty(&issues).unwrap());
+        let issues = result.unwrap();
+        println!("Issues: {}", serde_json::to_string_pretty(&issues).unwrap());
                 
-                let values = issues["values"].as_array().unwrap();
-                println!("Found {} issues (after pagination)", values.len());
-            }
-        }
+        let values = issues["values"].as_array().unwrap();
+        println!("Found {} issues (after pagination)", values.len());
     }
 }
 
@@ -383,30 +376,30 @@ async fn integration_test_pagination_verification() {
     println!("\n=== Testing Pagination Implementation ===\n");
     
     // Test that list methods return consistent format
-    let workspaces = client.list_workspaces().await.unwrap();
+    let workspaces = client.list_workspaces(&PageOptions::default(), None).await.unwrap();
     assert!(workspaces.get("values").is_some(), "Workspaces should have values array");
     assert!(workspaces.get("size").is_some(), "Workspaces should have size field");
     println!("✓ Workspaces pagination format correct");
     
-    let repos = client.list_repositories(&workspace).await.unwrap();
+    let repos = client.list_repositories(&workspace, &Filter::default(), &PageOptions::default(), None).await.unwrap();
     assert!(repos.get("values").is_some(), "Repositories should have values array");
     assert!(repos.get("size").is_some(), "Repositories should have size field");
     println!("✓ Repositories pagination format correct");
     
-    if let Some(repo_values) = repos["values"].as_array() {
-        if !repo_values.is_empty() {
-            let first_repo_slug = repo_values[0]["slug"].as_str().unwrap();
+    if let Some(repo_values) = repos["values"].as_array()
+        && !repo_values.is_empty()
+    {
+        let first_repo_slug = repo_values[0]["slug"].as_str().unwrap();
             
-            let prs = client.list_pullrequests(&workspace, first_repo_slug).await.unwrap();
-            assert!(prs.get("values").is_some(), "PRs should have values array");
-            assert!(prs.get("size").is_some(), "PRs should have size field");
-            println!("✓ Pull Requests pagination format correct");
+        let prs = client.list_pullrequests(&workspace, first_repo_slug, &Filter::default(), &PageOptions::default(), None).await.unwrap();
+        assert!(prs.get("values").is_some(), "PRs should have values array");
+        assert!(prs.get("size").is_some(), "PRs should have size field");
+        println!("✓ Pull Requests pagination format correct");
             
-            let branches = client.list_branches(&workspace, first_repo_slug).await.unwrap();
-            assert!(branches.get("values").is_some(), "Branches should have values array");
-            assert!(branches.get("size").is_some(), "Branches should have size field");
-            println!("✓ Branches pagination format correct");
-        }
+        let branches = client.list_branches(&workspace, first_repo_slug, &Filter::default(), &PageOptions::default(), None).await.unwrap();
+        assert!(branches.get("values").is_some(), "Branches should have values array");
+        assert!(branches.get("size").is_some(), "Branches should have size field");
+        println!("✓ Branches pagination format correct");
     }
     
     println!("\n=== All pagination tests passed! ===\n");
@@ -425,7 +418,7 @@ async fn integration_test_list_users() {
         None => return,
     };
     
-    let result = client.list_users(&workspace).await;
+    let result = client.list_users(&workspace, &PageOptions::default(), None).await;
     assert!(result.is_ok(), "Failed to list users: {:?}", result.err());
     
     let users = result.unwrap();
@@ -448,7 +441,7 @@ async fn integration_test_list_projects() {
         None => return,
     };
     
-    let result = client.list_projects(&workspace).await;
+    let result = client.list_projects(&workspace, &PageOptions::default(), None).await;
     assert!(result.is_ok(), "Failed to list projects: {:?}", result.err());
     
     let projects = result.unwrap();
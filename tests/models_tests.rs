@@ -0,0 +1,135 @@
+mod common;
+
+use bitbucket_mcp::common::models::{
+    AccountRef, BranchEndpoint, CreatePullRequest, IssueState, MergePullRequest, MergeStrategy, PipelineRefTarget,
+    PipelineTriggerTarget, PullRequestState, RefType, TriggerPipeline, UpdateIssue,
+};
+use bitbucket_mcp::common::pagination::PageOptions;
+use bitbucket_mcp::common::query::Filter;
+use common::make_client;
+use mockito::Matcher;
+use serde_json::json;
+
+#[tokio::test]
+async fn test_get_pullrequest_typed_decodes_response() {
+    let _m = mockito::mock("GET", "/2.0/repositories/ws/repo/pullrequests/7")
+        .with_status(200)
+        .with_body(json!({
+            "id": 7,
+            "title": "Add feature",
+            "state": "OPEN",
+            "author": {"display_name": "Jane", "uuid": "{u1}", "links": {}},
+            "source": {"branch": {"name": "feature"}, "commit": {"hash": "abc"}},
+            "destination": {"branch": {"name": "main"}},
+            "reviewers": [{"display_name": "Sam"}],
+            "close_source_branch": true,
+            "links": {"self": {"href": "https://api.bitbucket.org/..."}}
+        }).to_string())
+        .create();
+
+    let client = make_client(&mockito::server_url());
+    let pr = client.get_pullrequest_typed("ws", "repo", "7").await.unwrap();
+
+    assert_eq!(pr.id, 7);
+    assert_eq!(pr.state, Some(PullRequestState::Open));
+    assert_eq!(pr.source.branch.unwrap().name, "feature");
+    assert_eq!(pr.destination.branch.unwrap().name, "main");
+    assert_eq!(pr.author.unwrap().display_name.as_deref(), Some("Jane"));
+    assert_eq!(pr.reviewers.len(), 1);
+}
+
+#[tokio::test]
+async fn test_create_pullrequest_typed_sends_bitbucket_payload() {
+    let _m = mockito::mock("POST", "/2.0/repositories/ws/repo/pullrequests")
+        .match_body(Matcher::Json(json!({
+            "title": "Add feature",
+            "source": {"branch": {"name": "feature"}},
+            "destination": {"branch": {"name": "main"}},
+            "reviewers": [{"uuid": "{u2}"}],
+            "close_source_branch": true
+        })))
+        .with_status(201)
+        .with_body(r#"{"id": 8, "title": "Add feature", "state": "OPEN"}"#)
+        .create();
+
+    let client = make_client(&mockito::server_url());
+    let body = CreatePullRequest {
+        title: "Add feature".to_string(),
+        source: BranchEndpoint::branch("feature"),
+        destination: Some(BranchEndpoint::branch("main")),
+        reviewers: vec![AccountRef { uuid: Some("{u2}".to_string()), account_id: None }],
+        close_source_branch: Some(true),
+        ..CreatePullRequest::default()
+    };
+    let pr = client.create_pullrequest_typed("ws", "repo", &body).await.unwrap();
+
+    assert_eq!(pr.id, 8);
+}
+
+#[tokio::test]
+async fn test_list_pullrequests_typed_returns_page_with_cursor() {
+    let server_url = mockito::server_url();
+    let _m = mockito::mock("GET", "/2.0/repositories/ws/repo/pullrequests?pagelen=1")
+        .with_status(200)
+        .with_body(json!({
+            "values": [{"id": 1, "title": "One", "state": "MERGED"}],
+            "next": format!("{}/2.0/repositories/ws/repo/pullrequests?pagelen=1&page=2", server_url)
+        }).to_string())
+        .create();
+
+    let client = make_client(&server_url);
+    let page = PageOptions { limit: Some(1), ..PageOptions::default() };
+    let prs = client.list_pullrequests_typed("ws", "repo", &Filter::default(), &page).await.unwrap();
+
+    assert_eq!(prs.values[0].state, Some(PullRequestState::Merged));
+    assert!(prs.next.is_some());
+}
+
+#[test]
+fn test_unknown_pullrequest_state_does_not_fail_decoding() {
+    let pr: bitbucket_mcp::common::models::PullRequest = serde_json::from_value(json!({"id": 1, "state": "QUEUED"})).unwrap();
+    assert_eq!(pr.state, Some(PullRequestState::Other));
+}
+
+#[test]
+fn test_request_bodies_serialize_to_bitbucket_names() {
+    let merge = MergePullRequest { merge_strategy: Some(MergeStrategy::SquashFastForward), ..MergePullRequest::default() };
+    assert_eq!(serde_json::to_value(merge).unwrap(), json!({"merge_strategy": "squash_fast_forward"}));
+
+    let update = UpdateIssue { state: Some(IssueState::OnHold), ..UpdateIssue::default() };
+    assert_eq!(serde_json::to_value(update).unwrap(), json!({"state": "on hold"}));
+
+    let trigger = TriggerPipeline { target: PipelineRefTarget::branch("main").into(), variables: vec![] };
+    assert_eq!(
+        serde_json::to_value(trigger).unwrap(),
+        json!({"target": {"type": "pipeline_ref_target", "ref_type": "branch", "ref_name": "main"}})
+    );
+}
+
+#[test]
+fn test_trigger_pipeline_target_type_defaults_when_omitted() {
+    let trigger: TriggerPipeline = serde_json::from_value(json!({"target": {"ref_type": "tag", "ref_name": "v1"}})).unwrap();
+    assert_eq!(
+        trigger.target,
+        PipelineTriggerTarget::Ref(PipelineRefTarget { ref_type: RefType::Tag, ref_name: "v1".to_string(), selector: None })
+    );
+}
+
+#[test]
+fn test_trigger_pipeline_accepts_commit_and_pullrequest_targets() {
+    let commit = json!({"target": {"type": "pipeline_commit_target", "commit": {"hash": "ce5b7431"}, "selector": {"type": "custom", "pattern": "deploy"}}});
+    let trigger: TriggerPipeline = serde_json::from_value(commit.clone()).unwrap();
+    assert!(matches!(&trigger.target, PipelineTriggerTarget::Commit(t) if t.commit.hash == "ce5b7431"));
+    assert_eq!(serde_json::to_value(trigger).unwrap(), commit);
+
+    let pullrequest = json!({"target": {
+        "type": "pipeline_pullrequest_target",
+        "source": "feature",
+        "destination": "main",
+        "commit": {"hash": "1a372fc"},
+        "pullrequest": {"id": "3"},
+    }});
+    let trigger: TriggerPipeline = serde_json::from_value(pullrequest.clone()).unwrap();
+    assert!(matches!(&trigger.target, PipelineTriggerTarget::PullRequest(t) if t.pullrequest.id == "3"));
+    assert_eq!(serde_json::to_value(trigger).unwrap(), pullrequest);
+}
@@ -1,5 +1,9 @@
 mod common;
 
+use bitbucket_mcp::common::error::BitbucketError;
+use bitbucket_mcp::common::pagination::PageOptions;
+use bitbucket_mcp::common::query::Filter;
+use futures::{StreamExt, TryStreamExt};
 use serde_json::json;
 use common::make_client;
 
@@ -11,7 +15,7 @@ async fn test_pagination_single_page() {
         .create();
     
     let client = make_client(&mockito::server_url());
-    let result = client.list_pullrequests("ws", "repo").await.unwrap();
+    let result = client.list_pullrequests("ws", "repo", &Filter::default(), &PageOptions::default(), None).await.unwrap();
     
     assert_eq!(result["values"].as_array().unwrap().len(), 2);
     assert_eq!(result["size"], 2);
@@ -43,7 +47,7 @@ async fn test_pagination_multiple_pages() {
         .create();
     
     let client = make_client(&server_url);
-    let result = client.list_pullrequests("ws", "repo").await.unwrap();
+    let result = client.list_pullrequests("ws", "repo", &Filter::default(), &PageOptions::default(), None).await.unwrap();
     
     assert_eq!(result["values"].as_array().unwrap().len(), 5);
     assert_eq!(result["size"], 5);
@@ -57,7 +61,7 @@ async fn test_pagination_empty_result() {
         .create();
     
     let client = make_client(&mockito::server_url());
-    let result = client.list_pullrequests("ws", "repo").await.unwrap();
+    let result = client.list_pullrequests("ws", "repo", &Filter::default(), &PageOptions::default(), None).await.unwrap();
     
     assert_eq!(result["values"].as_array().unwrap().len(), 0);
     assert_eq!(result["size"], 0);
@@ -81,7 +85,7 @@ async fn test_pagination_list_comments_multiple_pages() {
         .create();
     
     let client = make_client(&server_url);
-    let result = client.list_pullrequest_comments("ws", "repo", "1").await.unwrap();
+    let result = client.list_pullrequest_comments("ws", "repo", "1", &PageOptions::default(), None).await.unwrap();
     
     assert_eq!(result["values"].as_array().unwrap().len(), 3);
     assert_eq!(result["size"], 3);
@@ -105,7 +109,7 @@ async fn test_pagination_list_repositories() {
         .create();
     
     let client = make_client(&server_url);
-    let result = client.list_repositories("ws").await.unwrap();
+    let result = client.list_repositories("ws", &Filter::default(), &PageOptions::default(), None).await.unwrap();
     
     assert_eq!(result["values"].as_array().unwrap().len(), 3);
     assert_eq!(result["size"], 3);
@@ -129,7 +133,7 @@ async fn test_pagination_list_workspaces() {
         .create();
     
     let client = make_client(&server_url);
-    let result = client.list_workspaces().await.unwrap();
+    let result = client.list_workspaces(&PageOptions::default(), None).await.unwrap();
     
     assert_eq!(result["values"].as_array().unwrap().len(), 2);
     assert_eq!(result["size"], 2);
@@ -153,7 +157,7 @@ async fn test_pagination_list_issues() {
         .create();
     
     let client = make_client(&server_url);
-    let result = client.list_issues("ws", "repo").await.unwrap();
+    let result = client.list_issues("ws", "repo", &Filter::default(), &PageOptions::default(), None).await.unwrap();
     
     assert_eq!(result["values"].as_array().unwrap().len(), 3);
     assert_eq!(result["size"], 3);
@@ -167,7 +171,7 @@ async fn test_pagination_list_branches() {
         .create();
     
     let client = make_client(&mockito::server_url());
-    let result = client.list_branches("ws", "repo").await.unwrap();
+    let result = client.list_branches("ws", "repo", &Filter::default(), &PageOptions::default(), None).await.unwrap();
     
     assert_eq!(result["values"].as_array().unwrap().len(), 2);
     assert_eq!(result["size"], 2);
@@ -181,7 +185,7 @@ async fn test_pagination_list_tags() {
         .create();
     
     let client = make_client(&mockito::server_url());
-    let result = client.list_tags("ws", "repo").await.unwrap();
+    let result = client.list_tags("ws", "repo", &PageOptions::default(), None).await.unwrap();
     
     assert_eq!(result["values"].as_array().unwrap().len(), 2);
     assert_eq!(result["size"], 2);
@@ -195,7 +199,7 @@ async fn test_pagination_list_commits() {
         .create();
     
     let client = make_client(&mockito::server_url());
-    let result = client.list_commits("ws", "repo").await.unwrap();
+    let result = client.list_commits("ws", "repo", &Filter::default(), &PageOptions::default(), None).await.unwrap();
     
     assert_eq!(result["values"].as_array().unwrap().len(), 2);
     assert_eq!(result["size"], 2);
@@ -209,7 +213,7 @@ async fn test_pagination_list_pipelines() {
         .create();
     
     let client = make_client(&mockito::server_url());
-    let result = client.list_pipelines("ws", "repo").await.unwrap();
+    let result = client.list_pipelines("ws", "repo", &Filter::default(), &PageOptions::default(), None).await.unwrap();
     
     assert_eq!(result["values"].as_array().unwrap().len(), 2);
     assert_eq!(result["size"], 2);
@@ -233,7 +237,7 @@ async fn test_pagination_list_activity() {
         .create();
     
     let client = make_client(&server_url);
-    let result = client.list_pullrequest_activity("ws", "repo", "1").await.unwrap();
+    let result = client.list_pullrequest_activity("ws", "repo", "1", &PageOptions::default(), None).await.unwrap();
     
     assert_eq!(result["values"].as_array().unwrap().len(), 2);
     assert_eq!(result["size"], 2);
@@ -257,7 +261,219 @@ async fn test_pagination_error_handling() {
         .create();
     
     let client = make_client(&server_url);
-    let result = client.list_pullrequests("ws", "repo").await;
+    let result = client.list_pullrequests("ws", "repo", &Filter::default(), &PageOptions::default(), None).await;
     
     assert!(result.is_err());
 }
+
+#[tokio::test]
+async fn test_pagination_limit_stops_early_with_cursor() {
+    let server_url = mockito::server_url();
+
+    let _m1 = mockito::mock("GET", "/2.0/repositories/ws/repo/commits?pagelen=2")
+        .with_status(200)
+        .with_body(json!({
+            "values": [{"hash": "a"}, {"hash": "b"}],
+            "next": format!("{}/2.0/repositories/ws/repo/commits?pagelen=2&page=2", server_url)
+        }).to_string())
+        .create();
+    let page2 = mockito::mock("GET", "/2.0/repositories/ws/repo/commits?pagelen=2&page=2")
+        .with_status(200)
+        .with_body(r#"{"values": [{"hash": "c"}]}"#)
+        .expect(0)
+        .create();
+
+    let client = make_client(&server_url);
+    let page = PageOptions { limit: Some(2), ..PageOptions::default() };
+    let result = client.list_commits("ws", "repo", &Filter::default(), &page, None).await.unwrap();
+
+    assert_eq!(result["size"], 2);
+    assert_eq!(result["next"], format!("{}/2.0/repositories/ws/repo/commits?pagelen=2&page=2", server_url));
+    page2.assert();
+}
+
+#[tokio::test]
+async fn test_pagination_resumes_from_cursor() {
+    let server_url = mockito::server_url();
+
+    let _m = mockito::mock("GET", "/2.0/repositories/ws/repo/tags?pagelen=2&page=2")
+        .with_status(200)
+        .with_body(r#"{"values": [{"name": "v3"}]}"#)
+        .create();
+
+    let client = make_client(&server_url);
+    let page = PageOptions {
+        page: Some(format!("{}/2.0/repositories/ws/repo/tags?pagelen=2&page=2", server_url)),
+        ..PageOptions::default()
+    };
+    let result = client.list_tags("ws", "repo", &page, None).await.unwrap();
+
+    assert_eq!(result["size"], 1);
+    assert!(result.get("next").is_none());
+}
+
+#[tokio::test]
+async fn test_pagination_rejects_foreign_cursor() {
+    let server_url = mockito::server_url();
+    let foreign = mockito::mock("GET", mockito::Matcher::Regex("^/(2\\.0evil|other)/".to_string())).expect(0).create();
+    let client = make_client(&server_url);
+    let cursors = [
+        "https://example.com/2.0/repositories/ws/repo/tags?page=2".to_string(),
+        format!("{server_url}/2.0evil/repositories/ws/repo/tags?page=2"),
+        format!("{server_url}/2.0/../other/repositories/ws/repo/tags?page=2"),
+    ];
+    for cursor in cursors {
+        let page = PageOptions { page: Some(cursor.clone()), ..PageOptions::default() };
+        let result = client.list_tags("ws", "repo", &page, None).await;
+
+        assert!(matches!(result, Err(BitbucketError::Pagination(_))), "{cursor}: {result:?}");
+    }
+    foreign.assert();
+}
+
+#[tokio::test]
+async fn test_pagination_max_pages_returns_cursor() {
+    let server_url = mockito::server_url();
+
+    let _m1 = mockito::mock("GET", "/2.0/repositories/ws/repo/refs/branches?page=3")
+        .with_status(200)
+        .with_body(json!({
+            "values": [{"name": "main"}],
+            "next": format!("{}/2.0/repositories/ws/repo/refs/branches?page=4", server_url)
+        }).to_string())
+        .create();
+
+    let client = make_client(&server_url);
+    let page = PageOptions { page: Some("3".to_string()), max_pages: Some(1), ..PageOptions::default() };
+    let result = client.list_branches("ws", "repo", &Filter::default(), &page, None).await.unwrap();
+
+    assert_eq!(result["size"], 1);
+    assert_eq!(result["next"], format!("{}/2.0/repositories/ws/repo/refs/branches?page=4", server_url));
+}
+
+#[tokio::test]
+async fn test_explicit_max_pages_is_not_capped_by_the_loop_guard() {
+    let server_url = mockito::server_url();
+    // Two pages linking to each other never end; only max_pages stops the walk.
+    let _a = mockito::mock("GET", "/2.0/repositories/ws/guard/refs/tags?page=a")
+        .with_status(200)
+        .with_body(json!({"values": [{"name": "a"}], "next": format!("{server_url}/2.0/repositories/ws/guard/refs/tags?page=b")}).to_string())
+        .create();
+    let _b = mockito::mock("GET", "/2.0/repositories/ws/guard/refs/tags?page=b")
+        .with_status(200)
+        .with_body(json!({"values": [{"name": "b"}], "next": format!("{server_url}/2.0/repositories/ws/guard/refs/tags?page=a")}).to_string())
+        .create();
+
+    let client = make_client(&server_url);
+    let page = PageOptions { page: Some("a".to_string()), max_pages: Some(1001), ..PageOptions::default() };
+    let result = client.list_tags("ws", "guard", &page, None).await.unwrap();
+    assert_eq!(result["size"], 1001);
+    assert_eq!(result["next"], format!("{server_url}/2.0/repositories/ws/guard/refs/tags?page=b"));
+
+    let page = PageOptions { page: Some("a".to_string()), ..PageOptions::default() };
+    let err = client.list_tags("ws", "guard", &page, None).await.unwrap_err();
+    assert!(matches!(err, BitbucketError::Pagination(_)), "{err:?}");
+}
+
+#[test]
+fn test_pagelen_defaults_to_limit_and_is_capped() {
+    assert_eq!(PageOptions::default().effective_pagelen(), None);
+    assert_eq!(PageOptions { limit: Some(25), ..PageOptions::default() }.effective_pagelen(), Some(25));
+    assert_eq!(PageOptions { limit: Some(500), ..PageOptions::default() }.effective_pagelen(), Some(100));
+    assert_eq!(PageOptions { pagelen: Some(10), limit: Some(50), ..PageOptions::default() }.effective_pagelen(), Some(10));
+}
+
+#[derive(serde::Deserialize)]
+struct Commit {
+    hash: String,
+}
+
+#[tokio::test]
+async fn test_paginate_stream_yields_typed_items_across_pages() {
+    let server_url = mockito::server_url();
+
+    let _m1 = mockito::mock("GET", "/2.0/repositories/ws/repo/commits")
+        .with_status(200)
+        .with_body(json!({
+            "values": [{"hash": "a"}, {"hash": "b"}],
+            "next": format!("{}/2.0/repositories/ws/repo/commits?page=2", server_url)
+        }).to_string())
+        .create();
+    let _m2 = mockito::mock("GET", "/2.0/repositories/ws/repo/commits?page=2")
+        .with_status(200)
+        .with_body(r#"{"values": [{"hash": "c"}]}"#)
+        .create();
+
+    let client = make_client(&server_url);
+    let stream = client.paginate_stream::<Commit>("/repositories/ws/repo/commits", &PageOptions::default());
+    // The stream owns its client, so it can be driven from another task.
+    let commits: Vec<Commit> = tokio::spawn(stream.try_collect()).await.unwrap().unwrap();
+
+    let hashes: Vec<_> = commits.iter().map(|c| c.hash.as_str()).collect();
+    assert_eq!(hashes, ["a", "b", "c"]);
+}
+
+#[tokio::test]
+async fn test_paginate_stream_fetches_pages_lazily() {
+    let server_url = mockito::server_url();
+
+    let _m1 = mockito::mock("GET", "/2.0/repositories/ws/repo/commits")
+        .with_status(200)
+        .with_body(json!({
+            "values": [{"hash": "a"}, {"hash": "b"}],
+            "next": format!("{}/2.0/repositories/ws/repo/commits?page=2", server_url)
+        }).to_string())
+        .create();
+    let page2 = mockito::mock("GET", "/2.0/repositories/ws/repo/commits?page=2")
+        .with_status(200)
+        .with_body(r#"{"values": [{"hash": "c"}]}"#)
+        .expect(0)
+        .create();
+
+    let client = make_client(&server_url);
+    let first_two: Vec<Commit> = client
+        .paginate_stream("/repositories/ws/repo/commits", &PageOptions::default())
+        .take(2)
+        .try_collect()
+        .await
+        .unwrap();
+
+    assert_eq!(first_two.len(), 2);
+    page2.assert();
+}
+
+#[tokio::test]
+async fn test_paginate_stream_reports_decode_errors() {
+    let _m = mockito::mock("GET", "/2.0/repositories/ws/repo/commits")
+        .with_status(200)
+        .with_body(r#"{"values": [{"hash": "a"}, {"id": 2}]}"#)
+        .create();
+
+    let client = make_client(&mockito::server_url());
+    let items: Vec<_> = client
+        .paginate_stream::<Commit>("/repositories/ws/repo/commits", &PageOptions::default())
+        .collect()
+        .await;
+
+    assert!(items[0].is_ok());
+    assert!(matches!(items[1], Err(BitbucketError::Decode(_))));
+}
+
+#[tokio::test]
+async fn test_pagination_detects_self_referencing_next_link() {
+    let server_url = mockito::server_url();
+
+    let _m = mockito::mock("GET", "/2.0/repositories/ws/repo/pullrequests?page=1")
+        .with_status(200)
+        .with_body(json!({
+            "values": [{"id": 1}],
+            "next": format!("{}/2.0/repositories/ws/repo/pullrequests?page=1", server_url)
+        }).to_string())
+        .create();
+
+    let client = make_client(&server_url);
+    let page = PageOptions { page: Some("1".to_string()), ..PageOptions::default() };
+    let err = client.list_pullrequests("ws", "repo", &Filter::default(), &page, None).await.unwrap_err();
+
+    assert!(matches!(err, BitbucketError::Pagination(_)));
+}
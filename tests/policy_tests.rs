@@ -0,0 +1,103 @@
+mod common;
+
+use bitbucket_mcp::common::bitbucket::{BitbucketClient, BitbucketTool};
+use bitbucket_mcp::common::config::ClientConfig;
+use bitbucket_mcp::common::error::BitbucketError;
+use bitbucket_mcp::common::policy::{self, ToolPolicy, Toolset};
+use common::make_client;
+
+/// Name prefixes of tools that change Bitbucket state; every such tool must be listed in `MUTATING_TOOLS`.
+const MUTATING_PREFIXES: &[&str] = &["create_", "update_", "delete_", "add_", "approve_", "unapprove_", "decline_", "merge_", "trigger_"];
+
+#[test]
+fn test_read_only_tool_list_has_no_mutating_tools() {
+    let tool = BitbucketTool::new(make_client("http://localhost").with_read_only(true));
+    let names: Vec<String> = tool.tools().into_iter().map(|t| t.name.to_string()).collect();
+
+    assert!(names.contains(&"get_pullrequest".to_string()));
+    assert!(names.contains(&"list_repositories".to_string()));
+    for name in &names {
+        assert!(!policy::is_mutating(name), "{name} is listed in read-only mode");
+        assert!(!MUTATING_PREFIXES.iter().any(|p| name.starts_with(p)), "{name} looks mutating but is not in MUTATING_TOOLS");
+    }
+
+    let all = BitbucketTool::new(make_client("http://localhost")).tools();
+    assert_eq!(all.len(), names.len() + policy::MUTATING_TOOLS.len());
+}
+
+#[test]
+fn test_read_only_policy_rejects_mutating_tools() {
+    let policy = ToolPolicy::read_only();
+
+    assert!(policy.check("list_pullrequests").is_ok());
+    let err = policy.check("delete_repository").unwrap_err();
+    assert!(matches!(err, BitbucketError::Denied(_)), "{err:?}");
+    assert!(err.to_string().contains("read-only"));
+    assert!(ToolPolicy::default().check("delete_repository").is_ok());
+}
+
+#[tokio::test]
+async fn test_read_only_client_refuses_mutating_requests() {
+    let delete = mockito::mock("DELETE", "/2.0/repositories/ws/read-only-repo").expect(0).create();
+    let _get = mockito::mock("GET", "/2.0/repositories/ws/read-only-repo")
+        .with_status(200)
+        .with_body(r#"{"slug": "read-only-repo"}"#)
+        .create();
+
+    let client = make_client(&mockito::server_url()).with_read_only(true);
+
+    let err = client.delete_repository("ws", "read-only-repo").await.unwrap_err();
+    assert!(matches!(err, BitbucketError::Denied(_)), "{err:?}");
+    client.get_repository("ws", "read-only-repo", None).await.unwrap();
+    delete.assert();
+}
+
+#[test]
+fn test_every_tool_belongs_to_a_toolset() {
+    for tool in BitbucketTool::new(make_client("http://localhost")).tools() {
+        assert!(Toolset::of(&tool.name).is_some(), "{} has no toolset", tool.name);
+    }
+}
+
+#[test]
+fn test_toolsets_and_allow_deny_lists() {
+    let config = ClientConfig {
+        toolsets: Some(vec![Toolset::PullRequests]),
+        allow_tools: vec!["get_file_source".to_string()],
+        deny_tools: vec!["merge_pullrequest".to_string()],
+        ..ClientConfig::default()
+    };
+    let policy = ToolPolicy::from_config(&config);
+
+    assert!(policy.check("get_pullrequest").is_ok());
+    assert!(policy.check("get_file_source").is_ok());
+    assert!(policy.check("get_user").is_ok(), "core tools are always enabled");
+    let err = policy.check("merge_pullrequest").unwrap_err();
+    assert!(err.to_string().contains("deny list"), "{err}");
+    let err = policy.check("list_issues").unwrap_err();
+    assert!(matches!(err, BitbucketError::Denied(_)) && err.to_string().contains("'issues' toolset"), "{err}");
+
+    let allow_only = ToolPolicy { allow: ["list_repositories".to_string()].into(), ..ToolPolicy::default() };
+    assert!(allow_only.check("list_repositories").is_ok());
+    assert!(allow_only.check("get_repository").is_err());
+}
+
+#[test]
+fn test_tool_list_follows_config() {
+    let config = ClientConfig { toolsets: Some(vec![Toolset::Snippets]), deny_tools: vec!["delete_snippet".to_string()], ..ClientConfig::default() };
+    let tool = BitbucketTool::new(BitbucketClient::from_config(config).unwrap());
+    let names: Vec<String> = tool.tools().into_iter().map(|t| t.name.to_string()).collect();
+
+    assert!(names.contains(&"list_snippets".to_string()) && names.contains(&"get_user".to_string()));
+    assert!(!names.contains(&"delete_snippet".to_string()) && !names.contains(&"list_pullrequests".to_string()));
+}
+
+#[test]
+fn test_config_rejects_unknown_tools_and_toolsets() {
+    let config = ClientConfig { deny_tools: vec!["drop_everything".to_string()], ..ClientConfig::default() };
+    let err = config.validate().unwrap_err();
+    assert!(err.to_string().contains("drop_everything"), "{err}");
+
+    assert!(ClientConfig::from_toml_str(r#"toolsets = ["repos", "wiki"]"#).is_err());
+    assert!("PullRequests".parse::<Toolset>().is_ok());
+}
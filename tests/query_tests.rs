@@ -0,0 +1,110 @@
+mod common;
+
+use bitbucket_mcp::common::error::BitbucketError;
+use bitbucket_mcp::common::pagination::PageOptions;
+use bitbucket_mcp::common::query::{Filter, Query, StateFilter};
+use common::make_client;
+use mockito::Matcher;
+
+#[test]
+fn test_query_builder_quotes_and_escapes_strings() {
+    let query = Query::field("title").contains(r#"say "hi" \ bye"#);
+    assert_eq!(query.to_string(), r#"title ~ "say \"hi\" \\ bye""#);
+}
+
+#[test]
+fn test_query_builder_leaves_numbers_booleans_and_null_unquoted() {
+    assert_eq!(Query::field("id").gt(10).to_string(), "id > 10");
+    assert_eq!(Query::field("is_private").eq(true).to_string(), "is_private = true");
+    assert_eq!(Query::field("parent").is_null().to_string(), "parent = null");
+}
+
+#[test]
+fn test_query_builder_parenthesises_mixed_combinators() {
+    let query = Query::field("state")
+        .eq("OPEN")
+        .or(Query::field("state").eq("MERGED"))
+        .and(Query::field("author.nickname").eq("jane"))
+        .and(Query::field("title").not_contains("wip"));
+
+    assert_eq!(
+        query.to_string(),
+        r#"(state = "OPEN" OR state = "MERGED") AND author.nickname = "jane" AND title !~ "wip""#
+    );
+}
+
+#[test]
+fn test_raw_query_is_grouped_when_combined() {
+    let query = Query::raw(r#"a = 1 OR b = 2"#).and(Query::field("c").eq(3));
+    assert_eq!(query.to_string(), "(a = 1 OR b = 2) AND c = 3");
+}
+
+#[test]
+fn test_filter_state_becomes_repeated_param_for_pull_requests() {
+    let filter = Filter { state: Some("OPEN, MERGED".to_string()), sort: Some("-updated_on".to_string()), ..Filter::default() };
+    let pairs = filter.query_pairs(StateFilter::Param).unwrap();
+
+    assert_eq!(
+        pairs,
+        vec![("state", "OPEN".to_string()), ("state", "MERGED".to_string()), ("sort", "-updated_on".to_string())]
+    );
+}
+
+#[test]
+fn test_filter_state_is_folded_into_q_for_field_endpoints() {
+    let filter = Filter {
+        q: Some(r#"kind = "bug""#.to_string()),
+        state: Some("new,open".to_string()),
+        ..Filter::default()
+    };
+    let pairs = filter.query_pairs(StateFilter::Field("state")).unwrap();
+
+    assert_eq!(pairs, vec![("q", r#"(kind = "bug") AND (state = "new" OR state = "open")"#.to_string())]);
+}
+
+#[test]
+fn test_filter_state_is_rejected_where_unsupported() {
+    let filter = Filter { state: Some("OPEN".to_string()), ..Filter::default() };
+    let err = filter.query_pairs(StateFilter::Unsupported("branches")).unwrap_err();
+
+    assert!(matches!(err, BitbucketError::Validation { .. }));
+    assert!(err.to_string().contains("branches"));
+}
+
+#[tokio::test]
+async fn test_list_pullrequests_sends_filter_parameters() {
+    let _m = mockito::mock("GET", Matcher::Regex(r"^/2\.0/repositories/ws/repo/pullrequests$".to_string()))
+        .match_query(Matcher::AllOf(vec![
+            Matcher::UrlEncoded("q".into(), r#"title ~ "fix""#.into()),
+            Matcher::UrlEncoded("state".into(), "OPEN".into()),
+            Matcher::UrlEncoded("sort".into(), "-updated_on".into()),
+        ]))
+        .with_status(200)
+        .with_body(r#"{"values": [{"id": 7}]}"#)
+        .create();
+
+    let client = make_client(&mockito::server_url());
+    let filter = Filter {
+        state: Some("OPEN".to_string()),
+        sort: Some("-updated_on".to_string()),
+        ..Filter::query(Query::field("title").contains("fix"))
+    };
+    let result = client.list_pullrequests("ws", "repo", &filter, &PageOptions::default(), None).await.unwrap();
+
+    assert_eq!(result["values"][0]["id"], 7);
+}
+
+#[tokio::test]
+async fn test_list_pipelines_filters_state_by_state_name() {
+    let _m = mockito::mock("GET", Matcher::Regex(r"^/2\.0/repositories/ws/repo/pipelines/$".to_string()))
+        .match_query(Matcher::UrlEncoded("q".into(), r#"state.name = "COMPLETED""#.into()))
+        .with_status(200)
+        .with_body(r#"{"values": [{"build_number": 3}]}"#)
+        .create();
+
+    let client = make_client(&mockito::server_url());
+    let filter = Filter { state: Some("COMPLETED".to_string()), ..Filter::default() };
+    let result = client.list_pipelines("ws", "repo", &filter, &PageOptions::default(), None).await.unwrap();
+
+    assert_eq!(result["size"], 1);
+}
@@ -0,0 +1,88 @@
+mod common;
+
+use std::time::{Duration, Instant};
+use bitbucket_mcp::common::rate_limit::{RateLimitConfig, RateLimiter};
+use common::make_client;
+
+#[tokio::test]
+async fn test_rate_limit_headers_are_recorded() {
+    let _m = mockito::mock("GET", "/2.0/user")
+        .with_status(200)
+        .with_header("X-RateLimit-Limit", "1000")
+        .with_header("X-RateLimit-Remaining", "998")
+        .with_header("X-RateLimit-Resource", "api")
+        .with_header("X-RateLimit-NearLimit", "false")
+        .with_body(r#"{"username": "testuser"}"#)
+        .create();
+
+    let client = make_client(&mockito::server_url());
+    assert!(client.rate_limit_status()["server"].is_null());
+
+    client.get_user(None).await.unwrap();
+
+    let status = client.rate_limit_status();
+    assert_eq!(status["server"]["limit"], 1000);
+    assert_eq!(status["server"]["remaining"], 998);
+    assert_eq!(status["server"]["resource"], "api");
+    assert_eq!(status["server"]["near_limit"], false);
+}
+
+#[tokio::test]
+async fn test_limiter_is_shared_between_clones() {
+    let _m = mockito::mock("GET", "/2.0/user")
+        .with_status(200)
+        .with_header("X-RateLimit-Remaining", "42")
+        .with_body(r#"{"username": "testuser"}"#)
+        .create();
+
+    let client = make_client(&mockito::server_url());
+    let clone = client.clone();
+    clone.get_user(None).await.unwrap();
+
+    assert_eq!(client.rate_limit_status()["server"]["remaining"], 42);
+}
+
+#[tokio::test]
+async fn test_token_bucket_spaces_out_requests() {
+    let _m = mockito::mock("GET", "/2.0/workspaces/ws")
+        .with_status(200)
+        .with_body(r#"{"slug": "ws"}"#)
+        .create();
+
+    let client = make_client(&mockito::server_url()).with_rate_limit(RateLimitConfig {
+        requests_per_second: Some(20.0),
+        burst: 1,
+        max_concurrent: 4,
+    });
+
+    let start = Instant::now();
+    for _ in 0..3 {
+        client.get_workspace("ws", None).await.unwrap();
+    }
+    // The first request uses the burst token, the next two wait ~50ms each.
+    assert!(start.elapsed() >= Duration::from_millis(90), "elapsed {:?}", start.elapsed());
+}
+
+#[tokio::test]
+async fn test_concurrency_cap_blocks_extra_requests() {
+    let limiter = RateLimiter::new(RateLimitConfig {
+        requests_per_second: None,
+        burst: 1,
+        max_concurrent: 1,
+    });
+
+    let permit = limiter.acquire().await;
+    assert_eq!(limiter.snapshot()["client"]["in_flight"], 1);
+    let blocked = tokio::time::timeout(Duration::from_millis(50), limiter.acquire()).await;
+    assert!(blocked.is_err());
+
+    drop(permit);
+    let acquired = tokio::time::timeout(Duration::from_millis(50), limiter.acquire()).await;
+    assert!(acquired.is_ok());
+}
+
+#[test]
+fn test_unlimited_config_has_no_token_bucket() {
+    let limiter = RateLimiter::new(RateLimitConfig::unlimited());
+    assert!(limiter.snapshot()["client"]["available_tokens"].is_null());
+}
@@ -0,0 +1,179 @@
+mod common;
+
+use std::time::Duration;
+use bitbucket_mcp::common::error::BitbucketError;
+use bitbucket_mcp::common::pagination::PageOptions;
+use bitbucket_mcp::common::query::Filter;
+use bitbucket_mcp::common::retry::RetryPolicy;
+use common::{fast_retry_policy, make_client};
+use reqwest::header::{HeaderMap, HeaderValue};
+use reqwest::Method;
+use serde_json::json;
+
+#[tokio::test]
+async fn test_get_retries_transient_server_error() {
+    let failing = mockito::mock("GET", "/2.0/repositories/ws/repo")
+        .with_status(503)
+        .expect(1)
+        .create();
+    let ok = mockito::mock("GET", "/2.0/repositories/ws/repo")
+        .with_status(200)
+        .with_body(r#"{"slug": "repo"}"#)
+        .create();
+
+    let client = make_client(&mockito::server_url());
+    let result = client.get_repository("ws", "repo", None).await.unwrap();
+
+    assert_eq!(result["slug"], "repo");
+    failing.assert();
+    ok.assert();
+}
+
+#[tokio::test]
+async fn test_rate_limited_request_honours_retry_after() {
+    let throttled = mockito::mock("GET", "/2.0/user")
+        .with_status(429)
+        .with_header("Retry-After", "0")
+        .expect(1)
+        .create();
+    let _ok = mockito::mock("GET", "/2.0/user")
+        .with_status(200)
+        .with_body(r#"{"username": "testuser"}"#)
+        .create();
+
+    let client = make_client(&mockito::server_url());
+    let result = client.get_user(None).await.unwrap();
+
+    assert_eq!(result["username"], "testuser");
+    throttled.assert();
+}
+
+#[tokio::test]
+async fn test_gives_up_after_max_retries() {
+    let failing = mockito::mock("GET", "/2.0/workspaces/ws")
+        .with_status(502)
+        .expect(3)
+        .create();
+
+    let client = make_client(&mockito::server_url())
+        .with_retry_policy(RetryPolicy { max_retries: 2, ..fast_retry_policy() });
+    let err = client.get_workspace("ws", None).await.unwrap_err();
+
+    assert!(matches!(err, BitbucketError::Api { .. }));
+    failing.assert();
+}
+
+#[tokio::test]
+async fn test_rate_limit_error_surfaces_retry_after_when_exhausted() {
+    let _m = mockito::mock("GET", "/2.0/user")
+        .with_status(429)
+        .with_header("Retry-After", "7")
+        .with_body(r#"{"type": "error", "error": {"message": "Rate limit exceeded"}}"#)
+        .create();
+
+    let client = make_client(&mockito::server_url()).with_retry_policy(RetryPolicy::none());
+    let err = client.get_user(None).await.unwrap_err();
+
+    match err {
+        BitbucketError::RateLimited { retry_after, .. } => assert_eq!(retry_after, Some(Duration::from_secs(7))),
+        other => panic!("expected RateLimited, got {other:?}"),
+    }
+}
+
+#[tokio::test]
+async fn test_post_is_not_retried_by_default() {
+    let failing = mockito::mock("POST", "/2.0/repositories/ws/repo/pullrequests/1/approve")
+        .with_status(503)
+        .expect(1)
+        .create();
+
+    let client = make_client(&mockito::server_url());
+    let result = client.approve_pullrequest("ws", "repo", "1").await;
+
+    assert!(result.is_err());
+    failing.assert();
+}
+
+#[tokio::test]
+async fn test_post_is_retried_when_opted_in() {
+    let failing = mockito::mock("POST", "/2.0/repositories/ws/repo/pullrequests/1/approve")
+        .with_status(503)
+        .expect(1)
+        .create();
+    let _ok = mockito::mock("POST", "/2.0/repositories/ws/repo/pullrequests/1/approve")
+        .with_status(200)
+        .with_body(r#"{"approved": true}"#)
+        .create();
+
+    let client = make_client(&mockito::server_url())
+        .with_retry_policy(RetryPolicy { retry_non_idempotent: true, ..fast_retry_policy() });
+    let result = client.approve_pullrequest("ws", "repo", "1").await.unwrap();
+
+    assert_eq!(result["approved"], true);
+    failing.assert();
+}
+
+#[tokio::test]
+async fn test_pagination_survives_transient_page_failure() {
+    let server_url = mockito::server_url();
+
+    let _m1 = mockito::mock("GET", "/2.0/repositories/ws/repo/commits")
+        .with_status(200)
+        .with_body(json!({
+            "values": [{"hash": "a"}],
+            "next": format!("{}/2.0/repositories/ws/repo/commits?page=2", server_url)
+        }).to_string())
+        .create();
+    let _m2_fail = mockito::mock("GET", "/2.0/repositories/ws/repo/commits?page=2")
+        .with_status(502)
+        .expect(1)
+        .create();
+    let _m2 = mockito::mock("GET", "/2.0/repositories/ws/repo/commits?page=2")
+        .with_status(200)
+        .with_body(r#"{"values": [{"hash": "b"}]}"#)
+        .create();
+
+    let client = make_client(&server_url);
+    let result = client.list_commits("ws", "repo", &Filter::default(), &PageOptions::default(), None).await.unwrap();
+
+    assert_eq!(result["size"], 2);
+}
+
+#[test]
+fn test_delay_prefers_retry_after_and_caps_at_max_delay() {
+    let policy = RetryPolicy { max_delay: Duration::from_secs(5), ..RetryPolicy::default() };
+
+    let mut headers = HeaderMap::new();
+    headers.insert("Retry-After", HeaderValue::from_static("2"));
+    assert_eq!(policy.delay_for(0, Some(&headers)), Duration::from_secs(2));
+
+    headers.insert("Retry-After", HeaderValue::from_static("120"));
+    assert_eq!(policy.delay_for(0, Some(&headers)), Duration::from_secs(5));
+}
+
+#[test]
+fn test_backoff_grows_exponentially_with_jitter() {
+    let policy = RetryPolicy {
+        base_delay: Duration::from_millis(100),
+        max_delay: Duration::from_secs(10),
+        ..RetryPolicy::default()
+    };
+
+    for attempt in 0..4 {
+        let full = Duration::from_millis(100 * 2u64.pow(attempt));
+        let delay = policy.backoff(attempt);
+        assert!(delay >= full / 2 && delay <= full, "attempt {attempt}: {delay:?}");
+    }
+}
+
+#[test]
+fn test_only_idempotent_methods_retry_by_default() {
+    let policy = RetryPolicy::default();
+    assert!(policy.allows_method(&Method::GET));
+    assert!(policy.allows_method(&Method::PUT));
+    assert!(policy.allows_method(&Method::DELETE));
+    assert!(!policy.allows_method(&Method::POST));
+
+    let opted_in = RetryPolicy { retry_non_idempotent: true, ..RetryPolicy::default() };
+    assert!(opted_in.allows_method(&Method::POST));
+}
@@ -0,0 +1,116 @@
+mod common;
+
+use bitbucket_mcp::common::config::ClientConfig;
+use bitbucket_mcp::common::error::BitbucketError;
+use bitbucket_mcp::common::pagination::PageOptions;
+use bitbucket_mcp::common::query::Filter;
+use bitbucket_mcp::common::scope::{glob_match, RepoScope};
+use common::{make_client, make_dc_client};
+use mockito::Matcher;
+
+fn scope(patterns: &[&str]) -> RepoScope {
+    RepoScope::new(&patterns.iter().map(|p| p.to_string()).collect::<Vec<_>>()).unwrap()
+}
+
+fn assert_out_of_scope(err: BitbucketError) {
+    assert!(matches!(err, BitbucketError::Denied(_)) && err.to_string().contains("out of scope"), "{err:?}");
+}
+
+#[test]
+fn test_glob_patterns() {
+    assert!(glob_match("payments-*", "payments-api"));
+    assert!(glob_match("payments-*", "Payments-"));
+    assert!(glob_match("pay?ents", "payments"));
+    assert!(glob_match("*-api*", "billing-api-v2"));
+    assert!(!glob_match("payments-*", "billing"));
+    assert!(!glob_match("acme", "acme-corp"));
+
+    let scope = scope(&["acme/payments-*", "tools"]);
+    assert!(scope.allows_repo("acme", "payments-api"));
+    assert!(!scope.allows_repo("acme", "website"));
+    assert!(scope.allows_workspace("acme"));
+    assert!(scope.allows_repo("tools", "anything"));
+    assert!(!scope.allows_workspace("other"));
+    assert!(!scope.grants_workspace("acme"));
+    assert!(scope.grants_workspace("tools"));
+}
+
+#[tokio::test]
+async fn test_out_of_scope_requests_are_not_sent() {
+    let outside = mockito::mock("GET", Matcher::Regex("^/2.0/repositories/(acme/website|other)".to_string())).expect(0).create();
+    let _inside = mockito::mock("GET", "/2.0/repositories/acme/payments-api")
+        .with_status(200)
+        .with_body(r#"{"slug": "payments-api"}"#)
+        .create();
+
+    let client = make_client(&mockito::server_url()).with_scope(scope(&["acme/payments-*"]));
+
+    client.get_repository("acme", "payments-api", None).await.unwrap();
+    assert_out_of_scope(client.get_repository("acme", "website", None).await.unwrap_err());
+    assert_out_of_scope(client.get_repository("acme/payments-api/../../other", "repo", None).await.unwrap_err());
+    assert_out_of_scope(client.list_repositories("other", &Filter::default(), &PageOptions::default(), None).await.unwrap_err());
+    outside.assert();
+}
+
+#[tokio::test]
+async fn test_workspace_level_changes_need_the_whole_workspace() {
+    let changes = mockito::mock("DELETE", "/2.0/workspaces/acme/projects/PAY").expect(0).create();
+    let snippets = mockito::mock("POST", "/2.0/snippets/acme").expect(0).create();
+
+    let client = make_client(&mockito::server_url()).with_scope(scope(&["acme/payments-*"]));
+    assert_out_of_scope(client.delete_project("acme", "PAY").await.unwrap_err());
+    assert_out_of_scope(client.create_snippet("acme", serde_json::json!({"title": "notes"})).await.unwrap_err());
+    changes.assert();
+    snippets.assert();
+
+    let _deleted = mockito::mock("DELETE", "/2.0/workspaces/tools/projects/OPS").with_status(204).create();
+    let client = make_client(&mockito::server_url()).with_scope(scope(&["acme/payments-*", "tools/*"]));
+    client.delete_project("tools", "OPS").await.unwrap();
+}
+
+#[tokio::test]
+async fn test_workspace_listings_only_show_repositories_in_scope() {
+    let _repos = mockito::mock("GET", "/2.0/repositories/listing")
+        .with_status(200)
+        .with_body(r#"{"values": [{"slug": "payments-api"}, {"slug": "website"}, {"name": "no slug"}]}"#)
+        .create();
+    let _dc_repos = mockito::mock("GET", Matcher::Regex("^/rest/api/1.0/projects/LIST/repos".to_string()))
+        .with_status(200)
+        .with_body(r#"{"values": [{"slug": "payments-api", "project": {"key": "LIST"}}, {"slug": "website", "project": {"key": "LIST"}}], "isLastPage": true}"#)
+        .create();
+
+    let client = make_client(&mockito::server_url()).with_scope(scope(&["listing/payments-*"]));
+    let repos = client.list_repositories("listing", &Filter::default(), &PageOptions::default(), None).await.unwrap();
+    assert_eq!(repos["values"], serde_json::json!([{"slug": "payments-api"}]));
+    assert_eq!(repos["size"], 1);
+
+    let client = make_dc_client(&mockito::server_url()).with_scope(scope(&["LIST/payments-*"]));
+    let repos = client.list_repositories("LIST", &Filter::default(), &PageOptions::default(), None).await.unwrap();
+    assert_eq!(repos["values"].as_array().unwrap().len(), 1);
+
+    // A pattern covering the whole workspace leaves the listing alone.
+    let client = make_client(&mockito::server_url()).with_scope(scope(&["listing"]));
+    let repos = client.list_repositories("listing", &Filter::default(), &PageOptions::default(), None).await.unwrap();
+    assert_eq!(repos["size"], 3);
+}
+
+#[tokio::test]
+async fn test_data_center_scope_uses_project_keys() {
+    let outside = mockito::mock("GET", Matcher::Regex("^/rest/api/1.0/projects/OPS/".to_string())).expect(0).create();
+
+    let client = make_dc_client(&mockito::server_url()).with_scope(scope(&["PAY/*"]));
+    let err = client.list_pullrequests("OPS", "infra", &Filter::default(), &PageOptions::default(), None).await.unwrap_err();
+
+    assert_out_of_scope(err);
+    outside.assert();
+}
+
+#[test]
+fn test_scope_config_is_validated() {
+    let config = ClientConfig { scope: vec!["acme/payments/*".to_string()], ..ClientConfig::default() };
+    assert!(matches!(config.validate(), Err(BitbucketError::Config(_))));
+
+    let config = ClientConfig::from_toml_str(r#"scope = ["acme/payments-*"]"#).unwrap();
+    assert_eq!(config.scope, vec!["acme/payments-*"]);
+    config.validate().unwrap();
+}